@@ -0,0 +1,89 @@
+use crate::features::is_enabled;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// Elite and weak, with the icon and label for their token HUD buttons
+const ADJUSTMENTS: [(&str, &str, &str); 2] = [
+    ("elite", "fa-solid fa-angles-up", "Elite"),
+    ("weak", "fa-solid fa-angles-down", "Weak"),
+];
+
+/// Add elite and weak toggles to the token HUD of an NPC for the GM
+fn inject_hud_buttons(hud: JsValue, html: JsValue) -> Result<(), Error> {
+    if !is_enabled("eliteWeakButtons") || !Game::instance()?.user()?.is_gm() {
+        return Ok(());
+    }
+    let token = Token::from(get_property(&hud, "object")?);
+    let Some(actor) = token.actor() else {
+        return Ok(());
+    };
+    if actor.actor_type().as_deref() != Some("npc") {
+        return Ok(());
+    }
+    let current = actor.adjustment();
+
+    let html = HtmlElement::from(html);
+    let column = html.query_selector(".col.right")?.ctx("token HUD column")?;
+    for (adjustment, icon, label) in ADJUSTMENTS {
+        let active = current.as_deref() == Some(adjustment);
+        column.insert_adjacent_html(
+            "beforeend",
+            &format!(
+                r#"<div class="control-icon johnys-adjustment{active}" data-johnys-adjustment="{adjustment}" data-tooltip="{tooltip}"><i class="{icon}"></i></div>"#,
+                active = if active { " active" } else { "" },
+                tooltip = if active {
+                    format!("Remove {label}")
+                } else {
+                    format!("Make {label}")
+                },
+            ),
+        )?;
+        let button = column
+            .query_selector(&format!("[data-johnys-adjustment={adjustment}]"))?
+            .ctx("adjustment button")?;
+
+        let actor_js = actor.as_js_value().clone();
+        let hud_js = hud.clone();
+        let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+            let actor = Actor::from(actor_js.clone());
+            let hud = hud_js.clone();
+            // clicking the active adjustment removes it
+            let new = (!active).then_some(adjustment);
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = actor.apply_adjustment(new).await {
+                    cprintln!("Error applying {adjustment} adjustment: {err}");
+                    UI::notify_error(&format!("Unable to adjust {}: {err}", actor.name()));
+                    return;
+                }
+                Application::from(hud).render().await.ok();
+            });
+        }) as Box<dyn Fn(JsValue)>);
+        button.add_event_listener("click", &click_fn)?;
+        click_fn.forget();
+    }
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Elite and Weak Buttons")
+            .hint("Add buttons to NPC token HUDs for the GM that apply or remove the elite or weak adjustment in one click.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "eliteWeakButtons");
+    });
+
+    hook!(
+        "renderTokenHUD",
+        |hud: JsValue, html: JsValue, _data: JsValue| {
+            if let Err(err) = inject_hud_buttons(hud, html) {
+                cprintln!("Error adding elite and weak buttons to token HUD: {err}");
+            }
+        }
+    );
+}
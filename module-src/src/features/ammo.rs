@@ -0,0 +1,139 @@
+use crate::features::is_enabled;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Flag on the ammo message once its ammunition has been given back
+const UNDONE_FLAG: &str = "ammoUndone";
+
+/// Use up a piece of ammunition when one of your ranged weapons makes a strike
+async fn decrement_ammo(message: Message) -> Result<(), Error> {
+    if !is_enabled("ammoAutoDecrement") {
+        return Ok(());
+    }
+    let is_strike = message
+        .pf2e_context()
+        .and_then(|context| context.roll_type())
+        .as_deref()
+        == Some("attack-roll");
+    let user = Game::instance()?.user()?;
+    // only the client that rolled the strike spends the ammo
+    if !is_strike || message.author().and_then(|author| author.id()) != user.id() {
+        return Ok(());
+    }
+    let (Some(actor), Some(weapon)) = (message.speaker_actor(), message.item()) else {
+        return Ok(());
+    };
+    let Some(ammo) = weapon.ammo() else {
+        return Ok(());
+    };
+    if !actor.is_owner() {
+        return Ok(());
+    }
+
+    let quantity = ammo.quantity();
+    if quantity <= 0.0 {
+        UI::notify_warn(&format!(
+            "{} is out of {} for {}.",
+            actor.name(),
+            ammo.name(),
+            weapon.name()
+        ));
+        return Ok(());
+    }
+    let left = quantity - 1.0;
+    ammo.set_quantity(left).await?;
+    if left <= 0.0 {
+        UI::notify_warn(&format!(
+            "{} used their last {}.",
+            actor.name(),
+            ammo.name()
+        ));
+    }
+
+    Message::create_whisper(
+        &format!(
+            r#"<div class="johnys-ammo">
+                <p>{actor_name} used 1 {ammo_name}, {left} left.</p>
+                <button type="button" data-johnys-ammo-undo="{uuid}"><i class="fa-solid fa-rotate-left"></i> Undo</button>
+            </div>"#,
+            actor_name = actor.name(),
+            ammo_name = ammo.name(),
+            uuid = ammo.uuid().unwrap_or_default(),
+        ),
+        &user.id().into_iter().collect::<Vec<_>>(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Give back the ammunition from an ammo message, once
+async fn undo(message: &Message, button: &HtmlElement) -> Result<(), Error> {
+    button.set_attribute("disabled", "true")?;
+    if message.get_flag(ID, UNDONE_FLAG).as_bool() == Some(true) {
+        return Ok(());
+    }
+    let uuid = button
+        .get_attribute("data-johnys-ammo-undo")
+        .ctx("ammunition UUID")?;
+    let ammo: Item = from_uuid_raw(&uuid).await?.into();
+    // the whisper is the rolling user's own, so they can flag it themselves
+    message
+        .set_flag(ID, UNDONE_FLAG, &JsValue::from(true))
+        .await?;
+    ammo.set_quantity(ammo.quantity() + 1.0).await
+}
+
+fn wire_undo_button(message: Message, html: HtmlElement) -> Result<(), Error> {
+    let Some(button) = html.query_selector("button[data-johnys-ammo-undo]")? else {
+        return Ok(());
+    };
+    if message.get_flag(ID, UNDONE_FLAG).as_bool() == Some(true) {
+        return button.set_attribute("disabled", "true");
+    }
+    let message = Rc::new(message);
+    let button_js = button.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+        let message = message.clone();
+        let button = HtmlElement::from(button_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = undo(&message, &button).await {
+                cprintln!("Error restoring ammunition: {err}");
+                UI::notify_error(&format!("Unable to restore ammunition: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Spend Ammunition on Strikes")
+            .hint("When you Strike with a ranged weapon that has ammunition selected, reduce the ammunition's quantity by 1 with an undo button in chat, and warn when it runs out. Leave this off if your PF2e version already spends ammunition.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "ammoAutoDecrement");
+    });
+
+    hook!("createChatMessage", async |message: JsValue| {
+        if let Err(err) = decrement_ammo(message.into()).await {
+            cprintln!("Error spending ammunition: {err}");
+        }
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        |message: JsValue, html: JsValue| {
+            if let Err(err) = wire_undo_button(message.into(), html.into()) {
+                cprintln!("Error wiring ammunition undo button: {err}");
+            }
+        }
+    );
+}
@@ -0,0 +1,120 @@
+use crate::features::is_enabled;
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    /// Rings drawn on each token, by token ID
+    static RINGS: RefCell<HashMap<String, Vec<Graphics>>> = RefCell::new(HashMap::new());
+}
+
+/// Ring color for the token's disposition, so enemy auras stand out
+fn ring_color(token: &Token) -> u32 {
+    match token.disposition() {
+        Some(disposition) if disposition < 0.0 => 0xd94c3d,
+        Some(disposition) if disposition > 0.0 => 0xf2c14e,
+        _ => 0xb0b0b0,
+    }
+}
+
+/// Redraw a ring around the token for each aura its creature emits
+fn draw_rings(game: &Game, token: &Token) -> Result<(), Error> {
+    let Some(id) = token.id() else {
+        return Ok(());
+    };
+    for ring in RINGS
+        .with_borrow_mut(|rings| rings.remove(&id))
+        .unwrap_or_default()
+    {
+        ring.destroy();
+    }
+    if !is_enabled("auraRings") {
+        return Ok(());
+    }
+    let (Some(actor), Some((width, height)), Some(pixels_per_foot)) =
+        (token.actor(), token.size(), game.pixels_per_foot())
+    else {
+        return Ok(());
+    };
+
+    let mut drawn = Vec::new();
+    for (_, radius) in actor.auras() {
+        let ring = Graphics::new()?;
+        // auras are measured from the creature's edge
+        let pixels = radius * pixels_per_foot + width.max(height) / 2.0;
+        ring.draw_ring(width / 2.0, height / 2.0, pixels, ring_color(token), 0.6)?;
+        token.add_child(&ring)?;
+        drawn.push(ring);
+    }
+    if !drawn.is_empty() {
+        RINGS.with_borrow_mut(|rings| rings.insert(id, drawn));
+    }
+    Ok(())
+}
+
+fn redraw_all() {
+    let Ok(game) = Game::instance() else {
+        return;
+    };
+    for token in game.scene_tokens() {
+        if let Err(err) = draw_rings(&game, &token) {
+            cprintln!("Error drawing aura rings: {err}");
+        }
+    }
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Aura Rings")
+            .hint("Draw a ring around tokens showing the reach of their auras, like Bless, a Marshal's stance, or Frightful Presence.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "auraRings");
+
+        Keybinding::new()
+            .name("Toggle Aura Rings")
+            .hint("Show or hide the rings around tokens with auras.")
+            .on_down(|| {
+                wasm_bindgen_futures::spawn_local(async {
+                    let shown = is_enabled("auraRings");
+                    if let Err(err) = set_setting(ID, "auraRings", &JsValue::from(!shown)).await {
+                        cprintln!("Error toggling aura rings: {err}");
+                    }
+                });
+            })
+            .register(ID, "toggleAuraRings");
+    });
+
+    hook!("canvasReady", || {
+        RINGS.with_borrow_mut(HashMap::clear);
+        redraw_all();
+    });
+
+    hook!("drawToken", |token: JsValue| {
+        let Ok(game) = Game::instance() else {
+            return;
+        };
+        if let Err(err) = draw_rings(&game, &token.into()) {
+            cprintln!("Error drawing aura rings: {err}");
+        }
+    });
+
+    // auras come and go with effects and stances
+    for hook in ["createItem", "updateItem", "deleteItem"] {
+        hook!(hook, |_item: JsValue| {
+            redraw_all();
+        });
+    }
+
+    hook!("clientSettingChanged", |key: JsValue| {
+        if key.as_string() == Some(format!("{ID}.auraRings")) {
+            redraw_all();
+        }
+    });
+}
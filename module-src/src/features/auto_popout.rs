@@ -1,18 +1,39 @@
-use crate::features::is_enabled;
+use crate::features::{apply_message_damage, is_enabled, register_api, setting_number};
 use crate::foundry::error::{ContextExt as _, Error};
-use crate::foundry::{cprintln, *};
-use crate::{hook, ID};
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, js_iter, ID};
 use futures::lock::Mutex;
+use gloo_timers::future::TimeoutFuture;
 use js_sys::Date;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 
+mod absent;
+mod broadcast;
+mod history;
+mod snooze;
+mod tray;
+
+/// How long after a popup other messages from the same roll are treated as duplicates
+const DUPLICATE_WINDOW_MS: f64 = 3000.0;
+
 #[derive(Clone, Debug)]
 struct MessageState {
     popped_out: bool,
     timestamp: f64,
     animation_complete: bool,
+    /// Identifies the roll this message came from, shared by pf2e-toolbelt's split messages
+    origin: Option<String>,
+    /// A fallback is scheduled in case Dice So Nice never reports the animation finished
+    waiting_for_dice: bool,
+    /// When the message was last updated, for coalescing bursts of updates
+    last_update: f64,
+    /// An update is waiting for the burst to settle before being handled
+    update_pending: bool,
 }
 
 impl Default for MessageState {
@@ -21,6 +42,10 @@ impl Default for MessageState {
             popped_out: false,
             timestamp: Date::now(),
             animation_complete: false,
+            origin: None,
+            waiting_for_dice: false,
+            last_update: 0.0,
+            update_pending: false,
         }
     }
 }
@@ -35,48 +60,737 @@ impl MessageState {
         map.get(msg_id).cloned().unwrap_or_default()
     }
 
-    async fn update(msg_id: String, updater: impl Fn(&mut MessageState)) {
+    async fn update<R>(msg_id: String, updater: impl FnOnce(&mut MessageState) -> R) -> R {
         let mut map = MESSAGE_STATE.lock().await;
         let state = map.entry(msg_id).or_default();
-        updater(state);
+        updater(state)
+    }
+
+    /// Check if a different message from the same roll was popped out recently
+    async fn is_duplicate(msg_id: &str, origin: &str) -> bool {
+        let map = MESSAGE_STATE.lock().await;
+        map.iter().any(|(id, state)| {
+            id != msg_id
+                && state.popped_out
+                && state.origin.as_deref() == Some(origin)
+                && state.timestamp > Date::now() - DUPLICATE_WINDOW_MS
+        })
+    }
+}
+
+/// Show the popup anyway if Dice So Nice hasn't finished animating after `seconds`
+///
+/// Skipped, hidden, or failed animations never fire `diceSoNiceRollComplete`.
+async fn dice_timeout(msg_id: String, seconds: f64) {
+    TimeoutFuture::new((seconds * 1000.0) as u32).await;
+    if MessageState::get(&msg_id).await.animation_complete {
+        return;
+    }
+    MessageState::update(msg_id.clone(), |state| state.animation_complete = true).await;
+    let Ok(Some(message)) = Game::instance().and_then(|game| game.get_message(&msg_id)) else {
+        return;
+    };
+    if let Err(err) = handle_message(message).await {
+        cprintln!("Error processing message after dice timeout: {err}");
+    }
+}
+
+/// Handle a message update once updates to it have stopped for the configured interval
+///
+/// pf2e-toolbelt and other modules can update a message several times a second.
+async fn handle_update(msg_id: String) -> Result<(), Error> {
+    let interval = setting_number("popupUpdateDebounce");
+    if interval > 0.0 {
+        let already_pending = MessageState::update(msg_id.clone(), |state| {
+            state.last_update = Date::now();
+            std::mem::replace(&mut state.update_pending, true)
+        })
+        .await;
+        if already_pending {
+            return Ok(());
+        }
+        loop {
+            TimeoutFuture::new(interval as u32).await;
+            let quiet_for = Date::now() - MessageState::get(&msg_id).await.last_update;
+            if quiet_for >= interval {
+                break;
+            }
+        }
+        MessageState::update(msg_id.clone(), |state| state.update_pending = false).await;
+    }
+
+    let game = Game::instance()?;
+    if let Some(message) = game.get_message(&msg_id)? {
+        absent::check(&message).await.ctx("absent players")?;
+        handle_message(message).await?;
+    }
+    Ok(())
+}
+
+/// Build a key identifying the roll behind a message
+///
+/// Foundry rolls have no ID, but pf2e-toolbelt's per-target messages carry an exact copy of the
+/// original roll, every die result included, which separate rolls practically never share.
+fn roll_origin(message: &Message) -> Option<String> {
+    message.first_roll()?.to_json()
+}
+
+/// Per-client override of whether an actor gets popups
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum PopupFilter {
+    Always,
+    Never,
+}
+
+impl PopupFilter {
+    /// Load the filters from client settings, keyed by world actor UUID
+    fn from_settings() -> HashMap<String, PopupFilter> {
+        serde_wasm_bindgen::from_value(get_setting(ID, "popupActorFilter")).unwrap_or_default()
+    }
+
+    async fn save(filters: &HashMap<String, PopupFilter>) -> Result<(), Error> {
+        let value = filters
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| Error::Custom(format!("Failed to serialize filters: {e}")))?;
+        set_setting(ID, "popupActorFilter", &value).await
+    }
+
+    /// Get the filter for an actor, including unlinked token copies of a world actor
+    fn for_actor(filters: &HashMap<String, PopupFilter>, actor: &Actor) -> Option<PopupFilter> {
+        let world_uuid = format!("Actor.{}", actor.id()?);
+        filters.get(&world_uuid).copied()
+    }
+}
+
+#[derive(Serialize)]
+struct ActorFilterRow {
+    uuid: String,
+    name: String,
+    img: Option<String>,
+    filter: Option<PopupFilter>,
+}
+
+#[derive(Serialize)]
+struct ActorFilterContext {
+    actors: Vec<ActorFilterRow>,
+}
+
+async fn open_actor_filter_menu() -> Result<(), Error> {
+    let game = Game::instance()?;
+    let gm_strategy = GMStrategy::from_settings(ID);
+    let ownership = OwnershipLevel::from_settings(ID);
+    let filters = PopupFilter::from_settings();
+    let rows: Vec<ActorFilterRow> = game
+        .actors()
+        .iter()
+        .filter(|actor| actor.is_owned_by_current_user(gm_strategy, ownership))
+        .filter_map(|actor| {
+            let uuid = actor.uuid()?;
+            Some(ActorFilterRow {
+                filter: filters.get(&uuid).copied(),
+                uuid,
+                name: actor.name(),
+                img: actor.img(),
+            })
+        })
+        .collect();
+
+    let html = application::render_template(
+        "modules/johnys-module/templates/popup-actor-filter.hbs",
+        &ActorFilterContext { actors: rows },
+    )
+    .await?;
+
+    let save_fn = Closure::wrap(Box::new(|html: JsValue| {
+        let form = HtmlElement::from(html);
+        let filters: HashMap<String, PopupFilter> = form
+            .query_selector_all("select[name]")
+            .iter()
+            .filter_map(|select| {
+                let filter = match select.value()?.as_str() {
+                    "always" => PopupFilter::Always,
+                    "never" => PopupFilter::Never,
+                    _ => return None,
+                };
+                Some((select.get_attribute("name")?, filter))
+            })
+            .collect();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = PopupFilter::save(&filters).await {
+                cprintln!("Error saving popup filters: {err}");
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+
+    application::show_dialog(
+        "Popup Actors",
+        html,
+        vec![
+            (
+                "save",
+                "Save",
+                Some(save_fn.into_js_value().unchecked_into()),
+            ),
+            ("cancel", "Cancel", None),
+        ],
+    )
+    .await
+}
+
+/// Check if popups have been turned off for this actor from its token HUD
+fn popups_disabled(actor: &Actor) -> bool {
+    actor
+        .get_flag(ID, "popupsDisabled")
+        .as_bool()
+        .unwrap_or(false)
+}
+
+/// Add a button to the token HUD that toggles popups for the token's actor
+fn inject_popup_toggle(hud: JsValue, html: JsValue) -> Result<(), Error> {
+    let token: Token = get_property(&hud, "object")?.into();
+    let actor = token.actor().ctx("token actor")?;
+    if !actor.is_owner() {
+        return Ok(());
+    }
+
+    let html = HtmlElement::from(html);
+    let column = html.query_selector(".col.right")?.ctx("token HUD column")?;
+    let disabled = popups_disabled(&actor);
+    column.insert_adjacent_html(
+        "beforeend",
+        r#"<div class="control-icon johnys-popup-toggle" data-tooltip="Toggle Damage Popups"><i class="fa-solid fa-bell-slash"></i></div>"#,
+    )?;
+    let button = column
+        .query_selector(".johnys-popup-toggle")?
+        .ctx("popup toggle")?;
+    button.toggle_class("active", disabled)?;
+
+    let actor_js = actor.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |event: JsValue| {
+        let actor: Actor = actor_js.clone().into();
+        let button = get_property(&event, "currentTarget").map(HtmlElement::from);
+        wasm_bindgen_futures::spawn_local(async move {
+            let disabled = !popups_disabled(&actor);
+            if let Err(err) = actor
+                .set_flag(ID, "popupsDisabled", &JsValue::from(disabled))
+                .await
+            {
+                cprintln!("Error toggling popups: {err}");
+                return;
+            }
+            if let Ok(button) = button {
+                button.toggle_class("active", disabled).ok();
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+
+    Ok(())
+}
+
+/// Warn the player when someone else targets a token they own
+fn warn_targeted(user: User, token: Token, targeted: bool) -> Result<(), Error> {
+    if !targeted || !is_enabled("popupTargetWarning") || !is_enabled("globalPopupEnabled") {
+        return Ok(());
+    }
+    let game = Game::instance()?;
+    if user.id() == game.user()?.id() {
+        return Ok(());
+    }
+    let actor = token.actor().ctx("token actor")?;
+    let gm_strategy = GMStrategy::from_settings(ID);
+    let ownership = OwnershipLevel::from_settings(ID);
+    if !actor.is_owned_by_current_user(gm_strategy, ownership) || popups_disabled(&actor) {
+        return Ok(());
+    }
+
+    // name whoever is acting for the targeting user: their combatant on their turn, else their character
+    let attacker = game
+        .combat()
+        .and_then(|combat| combat.combatant())
+        .filter(|combatant| {
+            combatant.actor().is_some_and(|actor| {
+                actor.is_owned_by(&user, GMStrategy::Normal, OwnershipLevel::Owner)
+            })
+        })
+        .map(|combatant| combatant.name())
+        .or_else(|| user.character().map(|character| character.name()))
+        .or_else(|| user.name())
+        .unwrap_or_else(|| "Someone".to_string());
+    let target = token.name().unwrap_or_else(|| actor.name());
+    UI::notify_warn(&format!("{attacker} is targeting {target}!"));
+    Ok(())
+}
+
+/// Close the popout after the configured delay unless the user has interacted with it
+async fn auto_close(popout: Application, seconds: f64) -> Result<(), Error> {
+    let element = popout.element().ctx("popout element")?;
+    let interacted = Rc::new(Cell::new(false));
+    let listener = {
+        let interacted = interacted.clone();
+        Closure::wrap(Box::new(move |_event: JsValue| interacted.set(true)) as Box<dyn Fn(JsValue)>)
+    };
+    for event in ["pointerdown", "keydown"] {
+        element.add_event_listener(event, &listener)?;
+    }
+
+    TimeoutFuture::new((seconds * 1000.0) as u32).await;
+
+    for event in ["pointerdown", "keydown"] {
+        element.remove_event_listener(event, &listener)?;
+    }
+    if !interacted.get() && popout.is_rendered() {
+        popout.close().await.ctx("close popout")?;
+    }
+    Ok(())
+}
+
+/// Add apply damage buttons for `actor` to the bottom of a popped out damage message
+///
+/// Healing rolls only get a heal button.
+fn inject_damage_buttons(
+    content: &HtmlElement,
+    message: &Message,
+    actor: &Actor,
+    healing: bool,
+) -> Result<(), Error> {
+    let name = escape_html(&actor.name());
+    let heal_button = format!(
+        r#"<button type="button" data-multiplier="-1" data-tooltip="Heal {name}"><i class="fa-solid fa-heart"></i> Heal</button>"#
+    );
+    let buttons = if healing {
+        heal_button
+    } else {
+        format!(
+            r#"
+            <button type="button" data-multiplier="1" data-tooltip="Apply full damage to {name}"><i class="fa-solid fa-heart-broken"></i> Full</button>
+            <button type="button" data-multiplier="0.5" data-tooltip="Apply half damage to {name}"><i class="fa-solid fa-heart-crack"></i> Half</button>
+            <button type="button" data-multiplier="2" data-tooltip="Apply double damage to {name}"><i class="fa-solid fa-skull"></i> Double</button>
+            {heal_button}
+            <button type="button" data-multiplier="1" data-shield-block="true" data-tooltip="Shield Block and apply damage to {name}"><i class="fa-solid fa-shield-blank"></i> Block</button>
+            "#
+        )
+    };
+    content.insert_adjacent_html(
+        "beforeend",
+        &format!(r#"<div class="johnys-damage-buttons">{buttons}</div>"#),
+    )?;
+    let buttons = content
+        .query_selector(".johnys-damage-buttons")?
+        .ctx("damage buttons")?;
+
+    let message_js = message.as_js_value().clone();
+    let actor_js = actor.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |event: JsValue| {
+        let Some(button) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("button[data-multiplier]"))
+        else {
+            return;
+        };
+        let multiplier = button
+            .get_attribute("data-multiplier")
+            .and_then(|m| m.parse().ok())
+            .unwrap_or(1.0);
+        let shield_block = button.get_attribute("data-shield-block").is_some();
+        let message: Message = message_js.clone().into();
+        let actor: Actor = actor_js.clone().into();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = apply_message_damage(&message, &actor, multiplier, shield_block).await
+            {
+                cprintln!("Error applying damage: {err}");
+                UI::notify_error(&format!("Unable to apply damage: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    buttons.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+
+    Ok(())
+}
+
+/// Add a one-line summary of the damage by type to the top of a popped out damage message
+fn inject_damage_breakdown(content: &HtmlElement, message: &Message) -> Result<(), Error> {
+    let Some(roll) = message.first_roll() else {
+        return Ok(());
+    };
+    let parts: Vec<String> = roll
+        .damage_instances()
+        .iter()
+        .map(|instance| {
+            let persistent = if instance.is_persistent() {
+                "persistent "
+            } else {
+                ""
+            };
+            let mut part = format!(
+                r#"<span class="damage-type {kind}"><strong>{total}</strong> {persistent}{kind}"#,
+                kind = instance.damage_type(),
+                total = instance.total(),
+            );
+            for component in ["precision", "splash"] {
+                let amount = instance.component_total(component);
+                if amount > 0.0 {
+                    part.push_str(&format!(" ({amount} {component})"));
+                }
+            }
+            part.push_str("</span>");
+            part
+        })
+        .collect();
+    if parts.is_empty() {
+        return Ok(());
+    }
+    content.insert_adjacent_html(
+        "afterbegin",
+        &format!(
+            r#"<div class="johnys-damage-breakdown">{}</div>"#,
+            parts.join(r#"<span class="separator">·</span>"#)
+        ),
+    )
+}
+
+/// The position the player last left a chat popout at, if any
+fn saved_popout_position() -> Option<ApplicationPosition> {
+    serde_wasm_bindgen::from_value(get_setting(ID, "popupPosition")).ok()
+}
+
+async fn save_popout_position(popout: Application) -> Result<(), Error> {
+    // anchored popups were placed by us, not the player
+    let anchored = popout
+        .element()
+        .is_some_and(|element| element.closest(".johnys-anchored-popup").is_some());
+    let Some(position) = popout.position() else {
+        return Ok(());
+    };
+    if anchored {
+        return Ok(());
+    }
+    if saved_popout_position() == Some(position) {
+        return Ok(());
+    }
+    let value = serde_wasm_bindgen::to_value(&position)
+        .map_err(|e| Error::Custom(format!("Failed to serialize position: {e}")))?;
+    set_setting(ID, "popupPosition", &value).await
+}
+
+/// How popups are shown to this client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PopupDisplay {
+    /// One chat popout window per message
+    Window,
+    /// A single tray window collecting every popup
+    Tray,
+    /// One chat popout window per message, placed beside the damaged token
+    Token,
+    /// No window; scroll the chat log to the message and flash it
+    Highlight,
+}
+
+impl PopupDisplay {
+    fn from_settings() -> Self {
+        match get_setting(ID, "popupDisplay").as_string().as_deref() {
+            Some("tray") => PopupDisplay::Tray,
+            Some("token") => PopupDisplay::Token,
+            Some("highlight") => PopupDisplay::Highlight,
+            _ => PopupDisplay::Window,
+        }
+    }
+}
+
+/// Which messages count as an actor taking damage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PopupTrigger {
+    /// The damage roll targeting the actor
+    Roll,
+    /// The message PF2e posts once damage is applied, after IWR and shields
+    Applied,
+}
+
+impl PopupTrigger {
+    fn from_settings() -> Self {
+        match get_setting(ID, "popupTrigger").as_string().as_deref() {
+            Some("applied") => PopupTrigger::Applied,
+            _ => PopupTrigger::Roll,
+        }
+    }
+}
+
+/// Extras to add to a popup
+#[derive(Debug, Clone, Copy, Default)]
+struct PopupOptions {
+    /// Add apply damage buttons
+    damage_buttons: bool,
+    /// Style the popup as healing rather than damage
+    healing: bool,
+    /// Summarize the damage by type
+    damage_breakdown: bool,
+}
+
+/// Place a popout beside `actor`'s token on the canvas, flipping to the left near the screen edge
+fn anchored_position(actor: &Actor) -> Option<ApplicationPosition> {
+    const GAP: f64 = 10.0;
+    let (left, top, right, _) = actor.active_token()?.client_bounds()?;
+    let saved = saved_popout_position().unwrap_or_default();
+    let width = saved.width.unwrap_or(320.0);
+    let height = saved.height.unwrap_or(400.0);
+    let (viewport_width, viewport_height) = Document::viewport_size();
+    let left = if right + GAP + width <= viewport_width {
+        right + GAP
+    } else {
+        left - GAP - width
+    };
+    Some(ApplicationPosition {
+        left: Some(left.clamp(0.0, (viewport_width - width).max(0.0))),
+        top: Some(top.clamp(0.0, (viewport_height - height).max(0.0))),
+        width: saved.width,
+        height: saved.height,
+    })
+}
+
+/// Point the player at `message` in the chat log instead of opening a window
+async fn highlight_in_chat(message: &Message) -> Result<(), Error> {
+    const HIGHLIGHT_MS: u32 = 4000;
+    let ui = UI::instance()?;
+    ui.show_chat(is_enabled("popupHighlightExpand"))?;
+    // give the sidebar a frame to render the chat log
+    TimeoutFuture::new(50).await;
+    let element = ui
+        .chat_message_element(&message.id())
+        .ctx("chat message element")?;
+    element.scroll_into_view()?;
+    element.toggle_class("johnys-highlighted-message", true)?;
+    TimeoutFuture::new(HIGHLIGHT_MS).await;
+    element.toggle_class("johnys-highlighted-message", false)
+}
+
+/// Show `message` to the player controlling `actor`
+async fn show_popup(message: &Message, actor: &Actor, options: PopupOptions) -> Result<(), Error> {
+    if let Some(sound) = get_setting(ID, "popupSound").as_string() {
+        if !sound.is_empty() {
+            play_sound(&sound, setting_number("popupSoundVolume")).ctx("popup sound")?;
+        }
+    }
+    history::record(message, actor);
+
+    match PopupDisplay::from_settings() {
+        display @ (PopupDisplay::Window | PopupDisplay::Token) => {
+            let anchor = match display {
+                PopupDisplay::Token => anchored_position(actor),
+                _ => None,
+            };
+            let position = anchor.or_else(saved_popout_position);
+            let popout = message.popout(position.as_ref()).await.ctx("popout")?;
+            let element = popout.element().ctx("popout element")?;
+            element.toggle_class("johnys-healing-popup", options.healing)?;
+            element.toggle_class("johnys-anchored-popup", anchor.is_some())?;
+            let content = element
+                .query_selector(".window-content")?
+                .ctx("popout content")?;
+            if options.damage_breakdown {
+                inject_damage_breakdown(&content, message).ctx("damage breakdown")?;
+            }
+            if options.damage_buttons {
+                inject_damage_buttons(&content, message, actor, options.healing)
+                    .ctx("damage buttons")?;
+            }
+            snooze::inject_button(&content).ctx("snooze button")?;
+            let close_after = setting_number("popupAutoCloseSeconds");
+            if close_after > 0.0 {
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Err(err) = auto_close(popout, close_after).await {
+                        cprintln!("Error auto-closing popout: {err}");
+                    }
+                });
+            }
+        }
+        PopupDisplay::Highlight => highlight_in_chat(message).await.ctx("chat highlight")?,
+        PopupDisplay::Tray => tray::push(message, actor, options)
+            .await
+            .ctx("popup tray")?,
     }
+    Ok(())
+}
+
+/// Check if a damage roll is big enough to pop up, with a threshold of 0 letting everything through
+fn exceeds_threshold(total: f64, threshold: f64) -> bool {
+    total > threshold
 }
 
 async fn handle_message(message: Message) -> Result<(), Error> {
-    if !is_enabled("popupEnabled") || !is_enabled("globalPopupEnabled") {
+    if !is_enabled("popupEnabled") || !is_enabled("globalPopupEnabled") || snooze::is_snoozed() {
         return Ok(());
     }
+    let trigger = PopupTrigger::from_settings();
+    if trigger == PopupTrigger::Applied {
+        if let Some(applied) = message.applied_damage() {
+            return handle_applied_damage(message, applied).await;
+        }
+    }
     let msg_type = message.pf2e_type().unwrap_or_default();
-    if !matches!(msg_type.as_str(), "damage-roll" | "spell-cast") {
+    let save_prompt = is_enabled("popupSavePrompts")
+        && (msg_type == "saving-throw" || message.toolbelt_save().is_some());
+    let inline_check = is_enabled("popupInlineChecks") && message.has_inline_checks();
+    let effect_link = is_enabled("popupEffects") && message.has_effect_links();
+    let persistent = is_enabled("popupPersistentDamage")
+        && matches!(msg_type.as_str(), "damage-roll" | "flat-check")
+        && message.is_persistent_damage();
+    if !save_prompt
+        && !inline_check
+        && !persistent
+        && !effect_link
+        && !matches!(msg_type.as_str(), "damage-roll" | "spell-cast")
+    {
         return Ok(());
     }
 
     let damaging_effect = message
         .pf2e_context()
-        .ctx("pf2e context")?
-        .options()
-        .iter()
-        .any(|i| i == "damaging-effect");
+        .map(|context| context.options().iter().any(|i| i == "damaging-effect"))
+        .unwrap_or_default();
 
-    if msg_type == "spell-cast" && damaging_effect {
+    // the damage will pop out once it's applied instead
+    if trigger == PopupTrigger::Applied && msg_type == "damage-roll" && !persistent {
+        return Ok(());
+    }
+    // damaging spells pop out with their damage roll unless they're asking for a save
+    if msg_type == "spell-cast" && damaging_effect && !save_prompt && !effect_link {
+        return Ok(());
+    }
+    let healing =
+        msg_type == "damage-roll" && message.first_roll().is_some_and(|roll| roll.is_healing());
+    if healing && get_setting(ID, "popupHealing").as_string().as_deref() == Some("skip") {
         return Ok(());
     }
+    // healing and persistent damage aren't attacks, so they can't be critical hits
+    if msg_type == "damage-roll" && !healing && !persistent && is_enabled("popupCriticalOnly") {
+        let context = message.pf2e_context().ctx("pf2e context")?;
+        let critical = context.outcome() == Some(DegreeOfSuccess::CriticalSuccess)
+            || context
+                .options()
+                .iter()
+                .any(|i| i == "check:outcome:critical-success");
+        if !critical {
+            return Ok(());
+        }
+    }
+    if msg_type == "damage-roll" {
+        let threshold = setting_number("popupMinimumDamage");
+        let total = message
+            .first_roll()
+            .map(|roll| roll.total())
+            .unwrap_or_default();
+        if !exceeds_threshold(total, threshold) {
+            return Ok(());
+        }
+    }
     let msg_id = message.id();
 
     let state = MessageState::get(&msg_id).await;
     let dice_so_nice_active = Game::is_module_active("dice-so-nice");
     let wait_for_animation =
         msg_type == "damage-roll" && dice_so_nice_active && !state.animation_complete;
-    if wait_for_animation || state.popped_out {
+    if state.popped_out {
+        return Ok(());
+    }
+    if wait_for_animation {
+        let timeout = setting_number("popupDiceTimeout");
+        if timeout > 0.0 && !state.waiting_for_dice {
+            MessageState::update(msg_id.clone(), |state| state.waiting_for_dice = true).await;
+            wasm_bindgen_futures::spawn_local(dice_timeout(msg_id, timeout));
+        }
         return Ok(());
     }
+    let mut current_targets = message.target_uuids().await;
+    if inline_check || effect_link {
+        // inline links carry no pf2e target, so use whatever the author has targeted
+        let author_targets = message.author().map(|author| author.targets());
+        for token in author_targets.unwrap_or_default() {
+            if let Some(uuid) = token.actor().and_then(|actor| actor.uuid()) {
+                current_targets.push(uuid);
+            }
+        }
+    }
+    if persistent {
+        // persistent damage is rolled by the affected actor rather than targeting it
+        if let Some(uuid) = message.speaker_actor().and_then(|actor| actor.uuid()) {
+            current_targets.push(uuid);
+        }
+    }
+    let options = PopupOptions {
+        damage_buttons: msg_type == "damage-roll" && is_enabled("popupDamageButtons"),
+        healing,
+        damage_breakdown: msg_type == "damage-roll"
+            && !healing
+            && is_enabled("popupDamageBreakdown"),
+    };
+    pop_out_for_targets(&message, current_targets, roll_origin(&message), options).await
+}
+
+/// Pop out a PF2e "damage taken" message for the actor it was applied to
+async fn handle_applied_damage(message: Message, applied: AppliedDamage) -> Result<(), Error> {
+    // fully resisted damage changes nothing, and undone damage was never really taken
+    if applied.is_reverted() || !applied.has_changes() {
+        return Ok(());
+    }
+    let healing = applied.is_healing();
+    if healing && get_setting(ID, "popupHealing").as_string().as_deref() == Some("skip") {
+        return Ok(());
+    }
+    if MessageState::get(&message.id()).await.popped_out {
+        return Ok(());
+    }
+    let targets = applied.actor_uuid().into_iter().collect();
+    let options = PopupOptions {
+        healing,
+        ..Default::default()
+    };
+    pop_out_for_targets(&message, targets, None, options).await
+}
+
+/// Pop out `message` for the first target actor this client should see it for
+async fn pop_out_for_targets(
+    message: &Message,
+    targets: Vec<String>,
+    origin: Option<String>,
+    options: PopupOptions,
+) -> Result<(), Error> {
+    let msg_id = message.id();
+    if is_enabled("popupSuppressWhenVisible") && Document::has_focus() {
+        let chat_visible = UI::instance().is_ok_and(|ui| ui.is_chat_visible());
+        let already_open =
+            Application::find_chat_popout(&msg_id).is_some_and(|app| app.is_rendered());
+        if chat_visible || already_open {
+            // the player has already seen it, so don't pop it later either
+            MessageState::update(msg_id, |state| state.popped_out = true).await;
+            return Ok(());
+        }
+    }
+    if let Some(origin) = &origin {
+        if MessageState::is_duplicate(&msg_id, origin).await {
+            return Ok(());
+        }
+    }
     let gm_strategy = GMStrategy::from_settings(ID);
-    let current_targets = message.target_uuids().await;
-    for uuid in current_targets {
+    let ownership = OwnershipLevel::from_settings(ID);
+    let filters = PopupFilter::from_settings();
+    for uuid in targets {
         if let Ok(actor) = Game::from_uuid(&uuid).await {
-            if actor.is_owned_by_current_user(gm_strategy) {
-                message.popout().await.ctx("popout")?;
-                MessageState::update(msg_id, |state| state.popped_out = true).await;
+            let wanted = match PopupFilter::for_actor(&filters, &actor) {
+                Some(PopupFilter::Always) => true,
+                Some(PopupFilter::Never) => false,
+                None => actor.is_owned_by_current_user(gm_strategy, ownership),
+            } && !popups_disabled(&actor);
+            if wanted {
+                MessageState::update(msg_id, |state| {
+                    state.popped_out = true;
+                    state.timestamp = Date::now();
+                    state.origin = origin;
+                })
+                .await;
+                show_popup(message, &actor, options).await?;
                 break;
             }
         }
@@ -105,11 +819,358 @@ pub fn init() {
             .default_bool(true)
             .register(ID, "popupEnabled");
 
+        SettingConfig::new()
+            .name("Popup Display")
+            .hint("Open each popup in its own window, or collect them in a single tray window with a dismiss button per message.")
+            .scope("client")
+            .config(true)
+            .type_string()
+            .default_string("window")
+            .choices(&[
+                ("window", "One window per message"),
+                ("tray", "Single popup tray"),
+                ("token", "One window per message, next to the damaged token"),
+                ("highlight", "No window, highlight the message in chat"),
+            ])
+            .register(ID, "popupDisplay");
+
+        SettingConfig::new()
+            .name("Expand Sidebar for Highlights")
+            .hint("When popups are shown by highlighting the message in chat, open the sidebar if it's collapsed.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "popupHighlightExpand");
+
+        SettingConfig::new()
+            .name("Damage Popup Trigger")
+            .hint("Pop out damage rolls that target your actors, or wait for the damage to be applied and pop out the message showing what was actually taken after resistances and shields. Damage that was fully resisted doesn't pop up in applied mode, and the damage threshold and critical hit filters only apply to rolls.")
+            .scope("client")
+            .config(true)
+            .type_string()
+            .default_string("roll")
+            .choices(&[
+                ("roll", "Damage rolls"),
+                ("applied", "Applied damage"),
+            ])
+            .register(ID, "popupTrigger");
+
+        SettingConfig::new()
+            .name("Auto-close Damage Popups")
+            .hint("Close damage popups after this many seconds unless you click or type in them. Set to 0 to never close them automatically.")
+            .scope("client")
+            .config(true)
+            .type_number()
+            .range(0.0, 120.0, 5.0)
+            .default_number(0.0)
+            .register(ID, "popupAutoCloseSeconds");
+
+        SettingConfig::new()
+            .name("Damage Threshold for Popups")
+            .hint("Only show damage popups when the damage roll total is more than this. Set to 0 to show every damage roll.")
+            .scope("client")
+            .config(true)
+            .type_number()
+            .default_number(0.0)
+            .register(ID, "popupMinimumDamage");
+
+        SettingConfig::new()
+            .name("Only Pop Up on Critical Hits")
+            .hint("Only show damage popups when the attack that caused them was a critical success. Saving throw spells are unaffected.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "popupCriticalOnly");
+
+        SettingConfig::new()
+            .name("Pop Out Saving Throw Prompts")
+            .hint("Also pop out saving throw requests (including PF2e Toolbelt save prompts) that target an actor you own.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "popupSavePrompts");
+
+        SettingConfig::new()
+            .name("Pop Out Inline Check Prompts")
+            .hint("Pop out chat messages containing inline checks (such as hazard or GM-authored @Check links) when the author is targeting an actor you own.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "popupInlineChecks");
+
+        SettingConfig::new()
+            .name("Skip Popups When Chat Is Visible")
+            .hint("Don't show a popup while this window is focused and the chat log (or a popout of the same message) is already open.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "popupSuppressWhenVisible");
+
+        SettingConfig::new()
+            .name("Popup Sound")
+            .hint("Sound to play whenever a popup is shown for one of your actors. Leave empty for no sound.")
+            .scope("client")
+            .config(true)
+            .type_string()
+            .file_picker("audio")
+            .default_string("")
+            .register(ID, "popupSound");
+
+        SettingConfig::new()
+            .name("Popup Sound Volume")
+            .hint("Volume of the popup sound.")
+            .scope("client")
+            .config(true)
+            .type_number()
+            .range(0.0, 1.0, 0.05)
+            .default_number(0.8)
+            .register(ID, "popupSoundVolume");
+
+        SettingConfig::new()
+            .name("Healing Popups")
+            .hint("Whether healing rolls targeting your actors pop up. Healing popups are tinted green so they can't be mistaken for damage.")
+            .scope("client")
+            .config(true)
+            .type_string()
+            .default_string("show")
+            .choices(&[("show", "Show healing popups"), ("skip", "Don't pop up healing")])
+            .register(ID, "popupHealing");
+
+        SettingConfig::new()
+            .name("Pop Out Persistent Damage")
+            .hint("Pop out recurring persistent damage rolls and their recovery flat checks for actors you own.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "popupPersistentDamage");
+
+        SettingConfig::new()
+            .name("Pop Out Effect Messages")
+            .hint("Pop out chat messages linking effects or conditions (such as spell effect buttons) when they target an actor you own, so you remember to apply them.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "popupEffects");
+
+        SettingConfig::new()
+            .name("Warn When Targeted")
+            .hint("Show a notification naming the attacker when another user targets a token you own.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "popupTargetWarning");
+
+        SettingConfig::new()
+            .name("Dice Animation Timeout")
+            .hint("With Dice So Nice, show the popup after this many seconds even if the dice animation never reports finishing. Set to 0 to always wait.")
+            .scope("client")
+            .config(true)
+            .type_number()
+            .range(0.0, 30.0, 1.0)
+            .default_number(5.0)
+            .register(ID, "popupDiceTimeout");
+
+        SettingConfig::new()
+            .name("Message Update Debounce (ms)")
+            .hint("Wait until a chat message has stopped being updated for this long before checking it for popups again. Set to 0 to check on every update.")
+            .scope("client")
+            .config(true)
+            .type_number()
+            .range(0.0, 2000.0, 50.0)
+            .default_number(250.0)
+            .register(ID, "popupUpdateDebounce");
+
+        SettingConfig::new()
+            .name("Snooze Duration (minutes)")
+            .hint("How long the snooze button in popups stops further popups on this client.")
+            .scope("client")
+            .config(true)
+            .type_number()
+            .range(1.0, 60.0, 1.0)
+            .default_number(10.0)
+            .register(ID, "popupSnoozeMinutes");
+
+        SettingConfig::new()
+            .scope("client")
+            .config(false)
+            .type_number()
+            .default_number(0.0)
+            .register(ID, "popupSnoozedUntil");
+
+        SettingConfig::new()
+            .name("Popup History Size")
+            .hint("How many recent popups to remember for the Recent Popups window.")
+            .scope("client")
+            .config(true)
+            .type_number()
+            .range(1.0, 50.0, 1.0)
+            .default_number(10.0)
+            .register(ID, "popupHistorySize");
+
+        Keybinding::new()
+            .name("Open Recent Popups")
+            .hint("List the messages that recently popped out, with buttons to pop them out again.")
+            .on_down(|| {
+                wasm_bindgen_futures::spawn_local(async {
+                    if let Err(err) = history::open().await {
+                        cprintln!("Error opening popup history: {err}");
+                    }
+                });
+            })
+            .register(ID, "openPopupHistory");
+
+        Keybinding::new()
+            .name("Reopen Last Popup")
+            .hint("Pop out the most recent popup again, in case it was closed by accident.")
+            .key("KeyP", &["Shift"])
+            .on_down(|| {
+                wasm_bindgen_futures::spawn_local(async {
+                    if let Err(err) = history::reopen_latest().await {
+                        cprintln!("Error reopening last popup: {err}");
+                        UI::notify_error(&err.to_string());
+                    }
+                });
+            })
+            .register(ID, "reopenLastPopup");
+
+        SettingConfig::new()
+            .name("Apply Damage Buttons in Popups")
+            .hint("Add buttons to damage popups for applying full, half, or double damage, healing, or shield blocking directly to your actor.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "popupDamageButtons");
+
+        SettingConfig::new()
+            .name("Damage Breakdown in Popups")
+            .hint("Show a summary of the damage by type (including precision, splash, and persistent damage) at the top of damage popups, to help reason about resistances before applying it.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "popupDamageBreakdown");
+
+        SettingConfig::new()
+            .scope("client")
+            .config(false)
+            .type_object()
+            .default_value(&js_sys::Object::new())
+            .register(ID, "popupPosition");
+
+        SettingConfig::new()
+            .scope("client")
+            .config(false)
+            .type_object()
+            .default_value(&js_sys::Object::new())
+            .register(ID, "popupActorFilter");
+
+        SettingsMenu::new()
+            .name("Popup Actors")
+            .label("Choose Actors")
+            .hint("Choose actors to always or never show popups for on this client, such as minions or familiars.")
+            .icon("fa-solid fa-users")
+            .restricted(false)
+            .on_open(|| {
+                wasm_bindgen_futures::spawn_local(async {
+                    if let Err(err) = open_actor_filter_menu().await {
+                        cprintln!("Error opening popup actor menu: {err}");
+                    }
+                });
+            })
+            .register(ID, "popupActorFilterMenu");
+
+        SettingConfig::new()
+            .name("Disconnected Players")
+            .hint("What the GM's client does when damage pops out for an actor whose players are all disconnected. Uses the GM's own popup filters.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .default_string("notify")
+            .choices(&[
+                ("ignore", "Nothing"),
+                ("notify", "Notify the GM"),
+                ("defer", "Notify the GM and pop out when the player reconnects"),
+            ])
+            .register(ID, "popupAbsentPlayers");
+
         GMStrategy::register_setting(ID);
+        OwnershipLevel::register_setting(ID);
+    });
+
+    hook!("targetToken", |user: JsValue,
+                          token: JsValue,
+                          targeted: JsValue| {
+        if let Err(err) = warn_targeted(user.into(), token.into(), targeted.is_truthy()) {
+            cprintln!("Error warning about target: {err}");
+        }
+    });
+
+    hook!("ready", || {
+        broadcast::init_socket();
+        snooze::resume();
+
+        // game.modules.get("johnys-module").api.forcePopup(messageId, [userId, ...])
+        let force_popup_fn = Closure::wrap(Box::new(|message_id: JsValue, user_ids: JsValue| {
+            let message_id = message_id.as_string().unwrap_or_default();
+            let user_ids: Vec<String> =
+                js_iter!(user_ids).filter_map(|id| id.as_string()).collect();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = broadcast::force_popup(message_id, user_ids).await {
+                    cprintln!("Error forcing popup: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn(JsValue, JsValue)>);
+        register_api("forcePopup", &force_popup_fn.into_js_value());
+
+        // game.modules.get("johnys-module").api.openPopupHistory()
+        let open_history_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = history::open().await {
+                    cprintln!("Error opening popup history: {err}");
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openPopupHistory", &open_history_fn.into_js_value());
+    });
+
+    hook!(
+        "getChatMessageContextOptions",
+        |_app: JsValue, options: JsValue| {
+            broadcast::add_context_menu_entry(options);
+        }
+    );
+
+    hook!(
+        "renderTokenHUD",
+        |hud: JsValue, html: JsValue, _data: JsValue| {
+            if let Err(err) = inject_popup_toggle(hud, html) {
+                cprintln!("Error adding popup toggle to token HUD: {err}");
+            }
+        }
+    );
+
+    hook!("closeChatPopout", async |app: JsValue| {
+        if let Err(err) = save_popout_position(app.into()).await {
+            cprintln!("Error saving popout position: {err}");
+        }
     });
 
     hook!("createChatMessage", async |message: JsValue| {
-        if let Err(err) = handle_message(message.into()).await {
+        let message: Message = message.into();
+        if let Err(err) = absent::check(&message).await {
+            cprintln!("Error checking for absent players: {err}");
+        }
+        if let Err(err) = handle_message(message).await {
             cprintln!("Error in chat message handler: {err}");
         }
     });
@@ -117,7 +1178,8 @@ pub fn init() {
     hook!(
         "updateChatMessage",
         async |message: JsValue, _changes: JsValue, _options: JsValue| {
-            if let Err(err) = handle_message(message.into()).await {
+            let message: Message = message.into();
+            if let Err(err) = handle_update(message.id()).await {
                 cprintln!("Error in message update handler: {err}");
             }
         }
@@ -139,3 +1201,23 @@ pub fn init() {
         }
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_at_threshold_does_not_pop_up() {
+        assert!(!exceeds_threshold(5.0, 5.0));
+    }
+
+    #[test]
+    fn damage_above_threshold_pops_up() {
+        assert!(exceeds_threshold(6.0, 5.0));
+    }
+
+    #[test]
+    fn zero_threshold_shows_any_damage() {
+        assert!(exceeds_threshold(1.0, 0.0));
+    }
+}
@@ -0,0 +1,153 @@
+use super::{broadcast, popups_disabled};
+use crate::features::{is_enabled, is_responsible_gm};
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::ID;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+/// What the GM's client does when a popup's players are all disconnected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AbsentStrategy {
+    /// Nothing
+    Ignore,
+    /// Tell the GM who missed it
+    Notify,
+    /// Tell the GM, and pop it out for the players once they reconnect
+    Defer,
+}
+
+impl AbsentStrategy {
+    fn from_settings() -> Self {
+        match get_setting(ID, "popupAbsentPlayers").as_string().as_deref() {
+            Some("ignore") => AbsentStrategy::Ignore,
+            Some("defer") => AbsentStrategy::Defer,
+            _ => AbsentStrategy::Notify,
+        }
+    }
+}
+
+thread_local! {
+    /// Message IDs each disconnected user should see when they return
+    static MISSED: RefCell<HashMap<String, Vec<String>>> = RefCell::new(HashMap::new());
+    /// Messages already checked, so updates to them don't repeat the notification
+    static CHECKED: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// The actors a message could pop up for, going by the message alone
+///
+/// Each player's own popup settings are unknown to the GM, so any message that pops out for
+/// someone with the default settings counts.
+async fn popup_targets(message: &Message) -> Vec<String> {
+    let msg_type = message.pf2e_type().unwrap_or_default();
+    let persistent =
+        matches!(msg_type.as_str(), "damage-roll" | "flat-check") && message.is_persistent_damage();
+    let inline_link = message.has_inline_checks() || message.has_effect_links();
+    let damaging_spell = msg_type == "spell-cast"
+        && message
+            .pf2e_context()
+            .is_some_and(|context| context.options().iter().any(|i| i == "damaging-effect"));
+    let save_prompt = msg_type == "saving-throw" || message.toolbelt_save().is_some();
+    // damaging spells are caught by their damage roll instead
+    let pops_out = persistent
+        || inline_link
+        || save_prompt
+        || msg_type == "damage-roll"
+        || (msg_type == "spell-cast" && !damaging_spell);
+    if !pops_out {
+        return Vec::new();
+    }
+
+    let mut targets = message.target_uuids().await;
+    if inline_link {
+        let author_targets = message.author().map(|author| author.targets());
+        for token in author_targets.unwrap_or_default() {
+            targets.extend(token.actor().and_then(|actor| actor.uuid()));
+        }
+    }
+    if persistent {
+        targets.extend(message.speaker_actor().and_then(|actor| actor.uuid()));
+    }
+    targets
+}
+
+/// On the GM's client, note target actors whose players aren't connected to see a popup
+pub(super) async fn check(message: &Message) -> Result<(), Error> {
+    let strategy = AbsentStrategy::from_settings();
+    let game = Game::instance()?;
+    if strategy == AbsentStrategy::Ignore
+        || !is_enabled("globalPopupEnabled")
+        || !is_responsible_gm(&game)?
+    {
+        return Ok(());
+    }
+    let targets = popup_targets(message).await;
+    // pf2e-toolbelt may add the targets in a later update
+    if targets.is_empty() || !CHECKED.with_borrow_mut(|checked| checked.insert(message.id())) {
+        return Ok(());
+    }
+    let ownership = OwnershipLevel::from_settings(ID);
+    let users: Vec<User> = game.users()?.iter().filter(|user| !user.is_gm()).collect();
+
+    let mut missed = Vec::new();
+    for uuid in targets {
+        let Ok(actor) = Game::from_uuid(&uuid).await else {
+            continue;
+        };
+        if popups_disabled(&actor) {
+            continue;
+        }
+        let owners: Vec<&User> = users
+            .iter()
+            .filter(|user| actor.is_owned_by(user, GMStrategy::Never, ownership))
+            .collect();
+        if owners.is_empty() || owners.iter().any(|user| user.is_active()) {
+            continue;
+        }
+        let names: Vec<String> = owners.iter().filter_map(|user| user.name()).collect();
+        missed.push(format!("{} ({})", actor.name(), names.join(", ")));
+        if strategy == AbsentStrategy::Defer {
+            MISSED.with_borrow_mut(|pending| {
+                for user_id in owners.iter().filter_map(|user| user.id()) {
+                    let messages = pending.entry(user_id).or_default();
+                    if !messages.contains(&message.id()) {
+                        messages.push(message.id());
+                    }
+                }
+            });
+        }
+    }
+
+    if !missed.is_empty() {
+        let later = if strategy == AbsentStrategy::Defer {
+            " It will pop out when they reconnect."
+        } else {
+            ""
+        };
+        UI::notify_warn(&format!(
+            "No one is connected to see the popup for {}.{later}",
+            missed.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Pop out everything a returning player missed while disconnected
+pub(super) async fn send_missed(user_id: String) -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !is_responsible_gm(&game)? {
+        return Ok(());
+    }
+    let Some(message_ids) = MISSED.with_borrow_mut(|pending| pending.remove(&user_id)) else {
+        return Ok(());
+    };
+    for message_id in message_ids {
+        if game.get_message(&message_id)?.is_none() {
+            continue;
+        }
+        if let Err(err) = broadcast::force_popup(message_id, vec![user_id.clone()]).await {
+            cprintln!("Error sending missed popup: {err}");
+        }
+    }
+    Ok(())
+}
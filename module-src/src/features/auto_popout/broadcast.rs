@@ -0,0 +1,177 @@
+use super::{absent, saved_popout_position};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::ID;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum BroadcastMessage {
+    /// Pop out a message for the listed users regardless of their settings
+    #[serde(rename_all = "camelCase")]
+    ForcePopup {
+        message_id: String,
+        user_ids: Vec<String>,
+    },
+    /// A player just connected and wants any popups they missed while away
+    #[serde(rename_all = "camelCase")]
+    RequestMissed { user_id: String },
+}
+
+/// Ask the listed players' clients to pop out a message
+pub(super) async fn force_popup(message_id: String, user_ids: Vec<String>) -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !game.user()?.is_gm() {
+        return Err("Only a GM can force popups".into());
+    }
+    // the socket doesn't echo back to the sender
+    if let Some(own_id) = game.user()?.id() {
+        if user_ids.contains(&own_id) {
+            show_forced_popup(&message_id).await?;
+        }
+    }
+    socket::emit(
+        ID,
+        &BroadcastMessage::ForcePopup {
+            message_id,
+            user_ids,
+        },
+    )
+}
+
+async fn show_forced_popup(message_id: &str) -> Result<(), Error> {
+    let game = Game::instance()?;
+    let message = game.get_message(message_id)?.ctx("message not found")?;
+    message.popout(saved_popout_position().as_ref()).await?;
+    Ok(())
+}
+
+async fn receive(data: JsValue, sender_id: String) -> Result<(), Error> {
+    let message = serde_wasm_bindgen::from_value(data);
+    if let Ok(BroadcastMessage::ForcePopup { .. }) = message {
+        // players could otherwise send popups to each other from the console
+        let game = Game::instance()?;
+        if !game
+            .users()?
+            .get(&sender_id)
+            .is_some_and(|user| user.is_gm())
+        {
+            return Ok(());
+        }
+    }
+    match message {
+        Ok(BroadcastMessage::ForcePopup {
+            message_id,
+            user_ids,
+        }) => {
+            let user_id = Game::instance()?.user()?.id().unwrap_or_default();
+            if user_ids.contains(&user_id) {
+                show_forced_popup(&message_id).await?;
+            }
+        }
+        Ok(BroadcastMessage::RequestMissed { user_id }) => absent::send_missed(user_id).await?,
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+/// Ask the GM for any popups this player missed while disconnected
+fn request_missed() -> Result<(), Error> {
+    let user = Game::instance()?.user()?;
+    if user.is_gm() {
+        return Ok(());
+    }
+    socket::emit(
+        ID,
+        &BroadcastMessage::RequestMissed {
+            user_id: user.id().unwrap_or_default(),
+        },
+    )
+}
+
+/// Let the GM pick which players should see the message, defaulting to everyone connected
+async fn choose_players(message_id: String) -> Result<(), Error> {
+    let game = Game::instance()?;
+    let players: String = game
+        .users()?
+        .iter()
+        .filter(|user| !user.is_gm())
+        .filter_map(|user| {
+            Some(format!(
+                r#"<label class="checkbox"><input type="checkbox" name="{}" {}> {}</label>"#,
+                user.id()?,
+                if user.is_active() { "checked" } else { "" },
+                escape_html(&user.name().unwrap_or_default())
+            ))
+        })
+        .collect();
+
+    let send_fn = Closure::wrap(Box::new(move |html: JsValue| {
+        let form = HtmlElement::from(html);
+        let user_ids: Vec<String> = form
+            .query_selector_all("input[type=checkbox]:checked")
+            .iter()
+            .filter_map(|input| input.get_attribute("name"))
+            .collect();
+        let message_id = message_id.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = force_popup(message_id, user_ids).await {
+                cprintln!("Error forcing popup: {err}");
+                UI::notify_error(&err.to_string());
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+
+    application::show_dialog(
+        "Force Popup",
+        format!(r#"<div class="johnys-force-popup">{players}</div>"#),
+        vec![
+            (
+                "send",
+                "Pop Out",
+                Some(send_fn.into_js_value().unchecked_into()),
+            ),
+            ("cancel", "Cancel", None),
+        ],
+    )
+    .await
+}
+
+/// Add a "Force Popup" entry to the chat message context menu for GMs
+pub(super) fn add_context_menu_entry(options: JsValue) {
+    let entry = context_menu_entry(
+        "Force Popup for Players",
+        "fa-solid fa-up-right-from-square",
+        |_| Game::instance().is_ok_and(|game| game.user().is_ok_and(|user| user.is_gm())),
+        |li| {
+            let Some(message_id) = li.get_attribute("data-message-id") else {
+                return;
+            };
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = choose_players(message_id).await {
+                    cprintln!("Error opening force popup dialog: {err}");
+                }
+            });
+        },
+    );
+    if let Ok(options) = options.dyn_into::<js_sys::Array>() {
+        options.push(&entry);
+    }
+}
+
+pub(super) fn init_socket() {
+    let result = socket::on_with_sender(ID, |data, sender_id| {
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = receive(data, sender_id).await {
+                cprintln!("Error handling forced popup: {err}");
+            }
+        });
+    });
+    if let Err(err) = result {
+        cprintln!("Error listening for forced popups: {err}");
+    }
+    if let Err(err) = request_missed() {
+        cprintln!("Error requesting missed popups: {err}");
+    }
+}
@@ -0,0 +1,141 @@
+use super::saved_popout_position;
+use crate::features::setting_number;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use js_sys::Date;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+
+struct HistoryEntry {
+    message_id: String,
+    actor_name: String,
+    timestamp: f64,
+}
+
+thread_local! {
+    static HISTORY: RefCell<VecDeque<HistoryEntry>> = const { RefCell::new(VecDeque::new()) };
+    static WINDOW: RefCell<Option<Application>> = const { RefCell::new(None) };
+}
+
+/// Remember a message that was popped out on this client, newest first
+pub(super) fn record(message: &Message, actor: &Actor) {
+    let limit = setting_number("popupHistorySize").max(0.0) as usize;
+    let message_id = message.id();
+    HISTORY.with_borrow_mut(|history| {
+        history.retain(|entry| entry.message_id != message_id);
+        history.push_front(HistoryEntry {
+            message_id,
+            actor_name: actor.name(),
+            timestamp: Date::now(),
+        });
+        history.truncate(limit);
+    });
+    // keep an open history window current
+    if WINDOW.with_borrow(Option::is_some) {
+        wasm_bindgen_futures::spawn_local(async {
+            if let Err(err) = open().await {
+                cprintln!("Error refreshing popup history: {err}");
+            }
+        });
+    }
+}
+
+/// Pop out the most recent popup again
+pub(super) async fn reopen_latest() -> Result<(), Error> {
+    let latest =
+        HISTORY.with_borrow(|history| history.front().map(|entry| entry.message_id.clone()));
+    match latest {
+        Some(message_id) => reopen(&message_id).await,
+        None => {
+            UI::notify_info("Nothing has popped out yet.");
+            Ok(())
+        }
+    }
+}
+
+/// Pop a message out again at the saved popout position
+async fn reopen(message_id: &str) -> Result<(), Error> {
+    let game = Game::instance()?;
+    let message = game.get_message(message_id)?.ctx("message not found")?;
+    message.popout(saved_popout_position().as_ref()).await?;
+    Ok(())
+}
+
+/// Open the history window, or refresh it if it's already open
+pub(super) async fn open() -> Result<(), Error> {
+    let existing = WINDOW.with_borrow(|window| {
+        window
+            .as_ref()
+            .map(|window| Application::from(window.as_js_value().clone()))
+    });
+    let window = match existing {
+        Some(window) => window,
+        None => application::WindowBuilder::new("johnys-popup-history", "Recent Popups")
+            .icon("fa-solid fa-clock-rotate-left")
+            .classes(&["johnys-popup-history"])
+            .position(&ApplicationPosition {
+                width: Some(320.0),
+                ..Default::default()
+            })
+            .render(render_entries)
+            .on_close(|| {
+                WINDOW.with_borrow_mut(|window| window.take());
+            })
+            .build()?,
+    };
+    WINDOW.with_borrow_mut(|slot| *slot = Some(window.as_js_value().clone().into()));
+    window.render().await
+}
+
+/// List every remembered popup with a button to pop it out again
+async fn render_entries() -> Result<JsValue, Error> {
+    let rows: String = HISTORY.with_borrow(|history| {
+        history
+            .iter()
+            .map(|entry| {
+                let time = Date::new(&JsValue::from(entry.timestamp)).to_locale_time_string("default");
+                format!(
+                    r#"<li class="popup-history-entry">
+                        <span class="popup-history-actor">{}</span>
+                        <span class="popup-history-time">{time}</span>
+                        <a data-reopen="{}" data-tooltip="Pop Out Again"><i class="fa-solid fa-up-right-from-square"></i></a>
+                    </li>"#,
+                    escape_html(&entry.actor_name),
+                    entry.message_id
+                )
+            })
+            .collect()
+    });
+
+    let container = Document::create_element("ol")?;
+    container.set_class_name("popup-history-entries")?;
+    if rows.is_empty() {
+        container.insert_adjacent_html(
+            "afterbegin",
+            r#"<li class="popup-history-empty">Nothing has popped out yet.</li>"#,
+        )?;
+    } else {
+        container.insert_adjacent_html("afterbegin", &rows)?;
+    }
+
+    let reopen_fn = Closure::wrap(Box::new(|event: JsValue| {
+        let Some(message_id) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("[data-reopen]"))
+            .and_then(|button| button.get_attribute("data-reopen"))
+        else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = reopen(&message_id).await {
+                cprintln!("Error reopening popup: {err}");
+                UI::notify_error(&err.to_string());
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    container.add_event_listener("click", &reopen_fn)?;
+    reopen_fn.forget();
+
+    Ok(container.as_js_value().clone())
+}
@@ -0,0 +1,81 @@
+use crate::features::setting_number;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::ID;
+use gloo_timers::future::TimeoutFuture;
+use js_sys::Date;
+use wasm_bindgen::prelude::*;
+
+/// When the current snooze ends, in milliseconds since the epoch
+fn snoozed_until() -> f64 {
+    get_setting(ID, "popupSnoozedUntil")
+        .as_f64()
+        .unwrap_or_default()
+}
+
+/// Check if the player has snoozed popups on this client
+pub(super) fn is_snoozed() -> bool {
+    snoozed_until() > Date::now()
+}
+
+/// Suppress popups on this client for the configured number of minutes
+async fn snooze() -> Result<(), Error> {
+    let minutes = setting_number("popupSnoozeMinutes");
+    let until = Date::now() + minutes * 60_000.0;
+    set_setting(ID, "popupSnoozedUntil", &JsValue::from(until)).await?;
+    UI::notify_info(&format!(
+        "Popups snoozed for {minutes} minutes, until {}.",
+        Date::new(&JsValue::from(until)).to_locale_time_string("default")
+    ));
+    wasm_bindgen_futures::spawn_local(notify_when_over(until));
+    Ok(())
+}
+
+/// Let the player know once a snooze ends, if it hasn't been replaced by a later one
+async fn notify_when_over(until: f64) {
+    let remaining = until - Date::now();
+    if remaining <= 0.0 {
+        return;
+    }
+    TimeoutFuture::new(remaining as u32).await;
+    if snoozed_until() == until {
+        UI::notify_info("Popups are no longer snoozed.");
+    }
+}
+
+/// Remind the player of a snooze carried over from before a reload
+pub(super) fn resume() {
+    let until = snoozed_until();
+    if until <= Date::now() {
+        return;
+    }
+    let minutes = ((until - Date::now()) / 60_000.0).ceil();
+    UI::notify_info(&format!("Popups are snoozed for {minutes} more minutes."));
+    wasm_bindgen_futures::spawn_local(notify_when_over(until));
+}
+
+/// Add a snooze button to the bottom of a popup
+pub(super) fn inject_button(content: &HtmlElement) -> Result<(), Error> {
+    let minutes = setting_number("popupSnoozeMinutes");
+    content.insert_adjacent_html(
+        "beforeend",
+        &format!(
+            r#"<div class="johnys-snooze"><button type="button" data-tooltip="Stop popups on this client for {minutes} minutes"><i class="fa-solid fa-bell-slash"></i> Snooze {minutes} min</button></div>"#
+        ),
+    )?;
+    let button = content
+        .query_selector(".johnys-snooze button")?
+        .ctx("snooze button")?;
+
+    let click_fn = Closure::wrap(Box::new(|_event: JsValue| {
+        wasm_bindgen_futures::spawn_local(async {
+            if let Err(err) = snooze().await {
+                cprintln!("Error snoozing popups: {err}");
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use super::{inject_damage_breakdown, inject_damage_buttons, snooze, PopupOptions};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+struct TrayEntry {
+    message_id: String,
+    actor_uuid: String,
+    options: PopupOptions,
+}
+
+thread_local! {
+    static TRAY: RefCell<Option<Application>> = const { RefCell::new(None) };
+    static ENTRIES: RefCell<Vec<TrayEntry>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Add a message to the popup tray, opening the tray if needed
+pub(super) async fn push(
+    message: &Message,
+    actor: &Actor,
+    options: PopupOptions,
+) -> Result<(), Error> {
+    let message_id = message.id();
+    let actor_uuid = actor.uuid().ctx("actor uuid")?;
+    ENTRIES.with_borrow_mut(|entries| {
+        if !entries.iter().any(|entry| entry.message_id == message_id) {
+            entries.push(TrayEntry {
+                message_id,
+                actor_uuid,
+                options,
+            });
+        }
+    });
+    render().await
+}
+
+/// Remove a message from the tray, closing it once it's empty
+async fn dismiss(message_id: &str) -> Result<(), Error> {
+    let empty = ENTRIES.with_borrow_mut(|entries| {
+        entries.retain(|entry| entry.message_id != message_id);
+        entries.is_empty()
+    });
+    if empty {
+        if let Some(tray) = TRAY.with_borrow_mut(Option::take) {
+            tray.close().await?;
+        }
+        Ok(())
+    } else {
+        render().await
+    }
+}
+
+async fn render() -> Result<(), Error> {
+    let existing = TRAY.with_borrow(|tray| {
+        tray.as_ref()
+            .map(|tray| Application::from(tray.as_js_value().clone()))
+    });
+    let tray = match existing {
+        Some(tray) => tray,
+        None => application::WindowBuilder::new("johnys-popup-tray", "Popups")
+            .icon("fa-solid fa-inbox")
+            .classes(&["johnys-popup-tray"])
+            .position(&ApplicationPosition {
+                width: Some(400.0),
+                height: Some(600.0),
+                ..Default::default()
+            })
+            .render(render_entries)
+            .on_close(|| {
+                TRAY.with_borrow_mut(|tray| tray.take());
+                ENTRIES.with_borrow_mut(Vec::clear);
+            })
+            .build()?,
+    };
+    TRAY.with_borrow_mut(|slot| *slot = Some(tray.as_js_value().clone().into()));
+    tray.render().await
+}
+
+/// Render every queued message with a header naming the actor and a dismiss button
+async fn render_entries() -> Result<JsValue, Error> {
+    let game = Game::instance()?;
+    let entries: Vec<(String, String, PopupOptions)> = ENTRIES.with_borrow(|entries| {
+        entries
+            .iter()
+            .map(|e| (e.message_id.clone(), e.actor_uuid.clone(), e.options))
+            .collect()
+    });
+
+    let container = Document::create_element("div")?;
+    container.set_class_name("popup-tray-entries")?;
+    for (message_id, actor_uuid, options) in entries.iter().rev() {
+        let Some(message) = game.get_message(message_id)? else {
+            continue;
+        };
+        let actor = Game::from_uuid(actor_uuid).await?;
+
+        let entry = Document::create_element("section")?;
+        entry.set_class_name("popup-tray-entry")?;
+        entry.toggle_class("johnys-healing-popup", options.healing)?;
+        entry.insert_adjacent_html(
+            "afterbegin",
+            &format!(
+                r#"<header class="popup-tray-header">
+                    <span>{}</span>
+                    <a data-dismiss="{message_id}" data-tooltip="Dismiss"><i class="fa-solid fa-xmark"></i></a>
+                </header>"#,
+                escape_html(&actor.name())
+            ),
+        )?;
+        let card = message.render_html().await?;
+        if options.damage_breakdown {
+            inject_damage_breakdown(&card, &message)?;
+        }
+        entry.append_child(&card)?;
+        if options.damage_buttons {
+            inject_damage_buttons(&entry, &message, &actor, options.healing)?;
+        }
+        container.append_child(&entry)?;
+    }
+    snooze::inject_button(&container)?;
+
+    let dismiss_fn = Closure::wrap(Box::new(|event: JsValue| {
+        let Some(message_id) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("[data-dismiss]"))
+            .and_then(|button| button.get_attribute("data-dismiss"))
+        else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = dismiss(&message_id).await {
+                cprintln!("Error dismissing popup: {err}");
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    container.add_event_listener("click", &dismiss_fn)?;
+    dismiss_fn.forget();
+
+    Ok(container.as_js_value().clone())
+}
@@ -0,0 +1,114 @@
+use crate::features::is_enabled;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    /// Messages whose consumable this client already used
+    static CONSUMED: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// Use one charge or one of the item, deleting it once it's all gone if it's set to auto-destroy
+///
+/// Returns a note on what's left for the notification.
+async fn consume(item: &Item) -> Result<String, Error> {
+    if let Some((charges, max_charges)) = item.uses().filter(|(_, max)| *max > 1.0) {
+        if charges > 1.0 {
+            item.set_uses(charges - 1.0).await?;
+            return Ok(format!("{} charges left", charges - 1.0));
+        }
+        // the last charge uses up one of the item, and the next one starts full
+        if item.quantity() > 1.0 {
+            item.set_uses(max_charges).await?;
+        }
+    }
+
+    let left = item.quantity() - 1.0;
+    if left <= 0.0 && item.auto_destroy() {
+        item.delete().await?;
+        return Ok("used up".to_string());
+    }
+    item.set_quantity(left.max(0.0)).await?;
+    Ok(format!("{} left", left.max(0.0)))
+}
+
+/// Add a Consume button to a consumable's chat card for its owner
+fn inject_consume_button(message: Message, html: HtmlElement) -> Result<(), Error> {
+    if !is_enabled("consumableButtons") {
+        return Ok(());
+    }
+    let Some(item) = message.item() else {
+        return Ok(());
+    };
+    if item.item_type().as_deref() != Some("consumable")
+        || !item.actor().is_some_and(|actor| actor.is_owner())
+    {
+        return Ok(());
+    }
+    let content = html
+        .query_selector(".message-content")?
+        .ctx("message content")?;
+    if CONSUMED.with_borrow(|consumed| consumed.contains(&message.id())) {
+        content.insert_adjacent_html(
+            "beforeend",
+            r#"<span class="johnys-consumed"><i class="fa-solid fa-check"></i> Consumed</span>"#,
+        )?;
+        return Ok(());
+    }
+    content.insert_adjacent_html(
+        "beforeend",
+        r#"<button type="button" class="johnys-consume"><i class="fa-solid fa-flask"></i> Consume</button>"#,
+    )?;
+    let button = content
+        .query_selector("button.johnys-consume")?
+        .ctx("consume button")?;
+
+    let button_js = button.as_js_value().clone();
+    let message_id = message.id();
+    let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+        let button = HtmlElement::from(button_js.clone());
+        let message_id = message_id.clone();
+        let item = Item::from(item.as_js_value().clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            match consume(&item).await {
+                Ok(left) => {
+                    CONSUMED.with_borrow_mut(|consumed| consumed.insert(message_id));
+                    UI::notify_info(&format!("Used {}, {left}.", item.name()));
+                    button.set_attribute("disabled", "true").ok();
+                }
+                Err(err) => {
+                    cprintln!("Error consuming item: {err}");
+                    UI::notify_error(&format!("Unable to consume the item: {err}"));
+                }
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Consume Buttons")
+            .hint("Add a Consume button to the chat cards of consumables you own, which uses a charge or one of the item and removes it when it's used up if the item is set to auto-destroy.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "consumableButtons");
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        |message: JsValue, html: JsValue| {
+            if let Err(err) = inject_consume_button(message.into(), html.into()) {
+                cprintln!("Error adding consume button: {err}");
+            }
+        }
+    );
+}
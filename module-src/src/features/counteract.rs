@@ -0,0 +1,106 @@
+use crate::features::register_api;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::hook;
+use wasm_bindgen::prelude::*;
+
+/// How many ranks above the counteracting effect's each degree of success can reach, if any
+fn max_rank_difference(degree: DegreeOfSuccess) -> Option<f64> {
+    match degree {
+        DegreeOfSuccess::CriticalSuccess => Some(3.0),
+        DegreeOfSuccess::Success => Some(1.0),
+        // only effects of a lower rank
+        DegreeOfSuccess::Failure => Some(-1.0),
+        DegreeOfSuccess::CriticalFailure => None,
+    }
+}
+
+/// Read a number field from the dialog
+fn field(form: &HtmlElement, name: &str, label: &str) -> Result<f64, Error> {
+    form.query_selector(&format!("[name={name}]"))?
+        .and_then(|input| input.value())
+        .and_then(|value| value.trim().parse().ok())
+        .ctx(&format!("Enter {label}"))
+}
+
+/// Work out the counteract outcome from the dialog and post it to chat
+async fn resolve(form: HtmlElement) -> Result<(), Error> {
+    let rank = field(&form, "rank", "the counteract rank")?;
+    let total = field(&form, "total", "the counteract check result")?;
+    let dc = field(&form, "dc", "the target's counteract DC")?;
+    let target_rank = field(&form, "targetRank", "the target's counteract rank")?;
+    let natural = form
+        .query_selector("[name=natural]")?
+        .and_then(|select| select.value())
+        .and_then(|value| value.parse().ok());
+
+    let degree = DegreeOfSuccess::from_check(total, dc, natural);
+    let counteracted = max_rank_difference(degree).is_some_and(|max| target_rank <= rank + max);
+    let outcome = if counteracted {
+        "The effect is counteracted."
+    } else {
+        "The effect is not counteracted."
+    };
+    Message::create(&format!(
+        r#"<div class="johnys-counteract">
+            <p><strong>Counteract</strong></p>
+            <p>Rank {rank} counteract check: {total} against DC {dc}, a <span class="johnys-degree-badge {slug}">{label}</span>.</p>
+            <p>Against a rank {target_rank} effect: <strong>{outcome}</strong></p>
+        </div>"#,
+        slug = degree.slug(),
+        label = degree.label(),
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Ask for the counteract check and both effects' ranks
+async fn open() -> Result<(), Error> {
+    let resolve_fn = Closure::wrap(Box::new(|html: JsValue| {
+        let form = HtmlElement::from(html);
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = resolve(form).await {
+                cprintln!("Error resolving counteract check: {err}");
+                UI::notify_error(&err.to_string());
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+
+    application::show_dialog(
+        "Counteract",
+        r#"<div class="form-group"><label>Counteract rank</label><input type="number" name="rank" min="0" max="10"></div>
+        <div class="form-group"><label>Counteract check result</label><input type="number" name="total"></div>
+        <div class="form-group"><label>Die roll</label><select name="natural">
+            <option value="">Other</option>
+            <option value="20">Natural 20</option>
+            <option value="1">Natural 1</option>
+        </select></div>
+        <div class="form-group"><label>Target's counteract DC</label><input type="number" name="dc"></div>
+        <div class="form-group"><label>Target's counteract rank</label><input type="number" name="targetRank" min="0" max="10"></div>"#
+            .to_string(),
+        vec![
+            (
+                "counteract",
+                "Counteract",
+                Some(resolve_fn.into_js_value().unchecked_into()),
+            ),
+            ("cancel", "Cancel", None),
+        ],
+    )
+    .await
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.counteract()
+        let counteract_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening counteract check: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("counteract", &counteract_fn.into_js_value());
+    });
+}
@@ -0,0 +1,232 @@
+use crate::features::geometry::{segments_cross, Point};
+use crate::features::is_enabled;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+const COVER_SLUG: &str = "johnys-cover";
+
+/// Cover levels with their circumstance bonus to AC
+#[derive(Clone, Copy)]
+enum Cover {
+    Lesser,
+    Standard,
+    Greater,
+}
+
+impl Cover {
+    fn bonus(self) -> f64 {
+        match self {
+            Cover::Lesser => 1.0,
+            Cover::Standard => 2.0,
+            Cover::Greater => 4.0,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Cover::Lesser => "lesser",
+            Cover::Standard => "standard",
+            Cover::Greater => "greater",
+        }
+    }
+
+    fn from_bonus(bonus: f64) -> Option<Self> {
+        [Cover::Lesser, Cover::Standard, Cover::Greater]
+            .into_iter()
+            .find(|cover| cover.bonus() == bonus)
+    }
+}
+
+/// Check if the line passes through the rectangle
+fn crosses_bounds(line: (Point, Point), (left, top, right, bottom): (f64, f64, f64, f64)) -> bool {
+    [
+        ((left, top), (right, top)),
+        ((right, top), (right, bottom)),
+        ((right, bottom), (left, bottom)),
+        ((left, bottom), (left, top)),
+    ]
+    .into_iter()
+    .any(|edge| segments_cross(line, edge))
+}
+
+/// Estimate the target's cover from the attacker
+///
+/// Walls are checked with lines to the target's center and just inside its corners: some blocked
+/// is standard cover, and everything but the center blocked is greater cover. Without a wall in
+/// the way, a creature between them gives lesser cover.
+fn estimate_cover(game: &Game, attacker: &Token, target: &Token) -> Option<Cover> {
+    let from = attacker.center()?;
+    let center = target.center()?;
+    let (left, top, right, bottom) = target.bounds()?;
+    let inset_x = (right - left) * 0.1;
+    let inset_y = (bottom - top) * 0.1;
+    let corners = [
+        (left + inset_x, top + inset_y),
+        (right - inset_x, top + inset_y),
+        (right - inset_x, bottom - inset_y),
+        (left + inset_x, bottom - inset_y),
+    ];
+    let center_blocked = Game::wall_between(from, center, "sight");
+    let corners_blocked = corners
+        .into_iter()
+        .filter(|corner| Game::wall_between(from, *corner, "sight"))
+        .count();
+
+    match (center_blocked, corners_blocked) {
+        // no line of effect at all
+        (true, 4) => None,
+        (false, 4) => Some(Cover::Greater),
+        (true, _) | (false, 1..) => Some(Cover::Standard),
+        (false, 0) => {
+            let between = game.scene_tokens().into_iter().any(|token| {
+                token.id() != attacker.id()
+                    && token.id() != target.id()
+                    && token
+                        .bounds()
+                        .is_some_and(|bounds| crosses_bounds((from, center), bounds))
+            });
+            between.then_some(Cover::Lesser)
+        }
+    }
+}
+
+/// Source data for an effect giving the cover's bonus to AC until the start of the next turn
+fn cover_effect(cover: Cover) -> Result<JsValue, Error> {
+    let effect = format!(
+        r#"{{
+            "type": "effect",
+            "name": "Cover ({label})",
+            "img": "systems/pf2e/icons/conditions-2/status_acup.webp",
+            "system": {{
+                "slug": "{COVER_SLUG}",
+                "duration": {{ "value": 1, "unit": "rounds", "expiry": "turn-start", "sustained": false }},
+                "tokenIcon": {{ "show": true }},
+                "rules": [{{ "key": "FlatModifier", "selector": "ac", "type": "circumstance", "value": {bonus} }}]
+            }}
+        }}"#,
+        label = cover.label(),
+        bonus = cover.bonus(),
+    );
+    Ok(js_sys::JSON::parse(&effect)?)
+}
+
+/// Tell the GM the cover the current user's target seems to have, with a button to apply it
+async fn suggest_cover(user: User, target: Token, targeted: bool) -> Result<(), Error> {
+    if !targeted || !is_enabled("coverSuggestions") {
+        return Ok(());
+    }
+    let game = Game::instance()?;
+    if user.id() != game.user()?.id() || game.combat().is_none() {
+        return Ok(());
+    }
+    let Some(attacker) = game.controlled_tokens().into_iter().next() else {
+        return Ok(());
+    };
+    if attacker.id() == target.id() {
+        return Ok(());
+    }
+    let Some(cover) = estimate_cover(&game, &attacker, &target) else {
+        return Ok(());
+    };
+    let creature = target.actor().ctx("target actor")?;
+    let gm_ids: Vec<String> = game
+        .users()?
+        .iter()
+        .filter(|user| user.is_gm())
+        .filter_map(|user| user.id())
+        .collect();
+
+    Message::create_whisper(
+        &format!(
+            r#"<div class="johnys-cover">
+                <p>{target_name} seems to have {label} cover from {attacker_name}: +{bonus} AC.</p>
+                <button type="button" data-johnys-cover="{uuid}" data-johnys-cover-bonus="{bonus}"><i class="fa-solid fa-shield-halved"></i> Apply Cover</button>
+            </div>"#,
+            target_name = creature.name(),
+            label = cover.label(),
+            attacker_name = attacker.name().unwrap_or_default(),
+            bonus = cover.bonus(),
+            uuid = creature.uuid().unwrap_or_default(),
+        ),
+        &gm_ids,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Give the creature from a cover message the suggested cover, replacing any it had
+async fn apply_cover(button: &HtmlElement) -> Result<(), Error> {
+    let uuid = button
+        .get_attribute("data-johnys-cover")
+        .ctx("cover actor UUID")?;
+    let cover = button
+        .get_attribute("data-johnys-cover-bonus")
+        .and_then(|bonus| bonus.parse().ok())
+        .and_then(Cover::from_bonus)
+        .ctx("cover bonus")?;
+    let creature = Game::actor_from_uuid(&uuid).await?;
+    for effect in creature
+        .effects()
+        .into_iter()
+        .filter(|effect| effect.slug().as_deref() == Some(COVER_SLUG))
+    {
+        effect.delete().await?;
+    }
+    creature.create_items(&[cover_effect(cover)?]).await?;
+    button.set_attribute("disabled", "true")
+}
+
+fn wire_apply_button(html: HtmlElement) -> Result<(), Error> {
+    let Some(button) = html.query_selector("button[data-johnys-cover]")? else {
+        return Ok(());
+    };
+    if !Game::instance()?.user()?.is_gm() {
+        return button.set_attribute("disabled", "true");
+    }
+    let button_js = button.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+        let button = HtmlElement::from(button_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = apply_cover(&button).await {
+                cprintln!("Error applying cover: {err}");
+                UI::notify_error(&format!("Unable to apply cover: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Cover Suggestions")
+            .hint("During combat, when you target a creature, estimate its cover from your token using walls and creatures in between, and whisper the GM the suggested AC bonus with a button to apply it.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "coverSuggestions");
+    });
+
+    hook!(
+        "targetToken",
+        async |user: JsValue, token: JsValue, targeted: JsValue| {
+            if let Err(err) = suggest_cover(user.into(), token.into(), targeted.is_truthy()).await {
+                cprintln!("Error suggesting cover: {err}");
+            }
+        }
+    );
+
+    hook!(
+        "renderChatMessageHTML",
+        |_message: JsValue, html: JsValue| {
+            if let Err(err) = wire_apply_button(HtmlElement::from(html)) {
+                cprintln!("Error wiring cover button: {err}");
+            }
+        }
+    );
+}
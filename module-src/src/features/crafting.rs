@@ -0,0 +1,257 @@
+use crate::features::{register_api, selected_actor};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+    /// UUID of the actor whose projects the window shows
+    static ACTOR_UUID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// An item being crafted, stored in the crafter's flags
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Project {
+    id: String,
+    name: String,
+    dc: f64,
+    /// Value of the materials put in so far, in gold pieces
+    cost_paid: f64,
+    days_remaining: f64,
+}
+
+fn projects(actor: &Actor) -> Vec<Project> {
+    serde_wasm_bindgen::from_value(actor.get_flag(ID, "craftingProjects")).unwrap_or_default()
+}
+
+async fn save_projects(actor: &Actor, projects: &[Project]) -> Result<(), Error> {
+    let value = projects
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| Error::Custom(format!("Failed to save crafting projects: {e}")))?;
+    actor.set_flag(ID, "craftingProjects", &value).await
+}
+
+async fn window_actor() -> Result<Actor, Error> {
+    let uuid = ACTOR_UUID
+        .with_borrow(Clone::clone)
+        .ctx("no crafter chosen")?;
+    Game::from_uuid(&uuid).await
+}
+
+/// Open the selected character's projects, or refresh the window if it's already open
+async fn open() -> Result<(), Error> {
+    let actor = selected_actor().ctx("Select a character to see their crafting projects")?;
+    ACTOR_UUID.with_borrow_mut(|uuid| *uuid = actor.uuid());
+    application::SingletonWindow::open(&WINDOW, || {
+        application::WindowBuilder::new("johnys-crafting", "Crafting Projects")
+            .icon("fa-solid fa-hammer")
+            .classes(&["johnys-crafting"])
+            .position(&ApplicationPosition {
+                width: Some(420.0),
+                ..Default::default()
+            })
+            .render(render_projects)
+    })
+    .await
+}
+
+fn spawn_update(update: impl std::future::Future<Output = Result<(), Error>> + 'static) {
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = update.await {
+            cprintln!("Error updating crafting projects: {err}");
+            UI::notify_error(&format!("Unable to update crafting projects: {err}"));
+        }
+    });
+}
+
+/// List the crafter's projects with buttons to work on them, and a form to start a new one
+async fn render_projects() -> Result<JsValue, Error> {
+    let actor = window_actor().await?;
+    let rows: String = projects(&actor)
+        .iter()
+        .map(|project| {
+            format!(
+                r#"<li class="johnys-row">
+                    <span class="crafting-name">{name}</span>
+                    <span>DC {dc}</span>
+                    <span>{cost_paid} gp paid</span>
+                    <span>{days} days left</span>
+                    <a data-crafting-action="work" data-project-id="{id}" data-tooltip="Work a Day"><i class="fa-solid fa-hammer"></i></a>
+                    <a data-crafting-action="remove" data-project-id="{id}" data-tooltip="Remove"><i class="fa-solid fa-trash"></i></a>
+                </li>"#,
+                name = escape_html(&project.name),
+                dc = project.dc,
+                cost_paid = project.cost_paid,
+                days = project.days_remaining,
+                id = project.id,
+            )
+        })
+        .collect();
+    let rows = if rows.is_empty() {
+        r#"<li class="johnys-empty">No projects in progress.</li>"#.to_string()
+    } else {
+        rows
+    };
+
+    let container = Document::create_element("div")?;
+    container.set_class_name("crafting-projects")?;
+    container.insert_adjacent_html(
+        "afterbegin",
+        &format!(
+            r#"<p><strong>{name}</strong></p>
+            <ol class="johnys-rows">{rows}</ol>
+            <div class="crafting-new">
+                <input type="text" name="name" placeholder="Item">
+                <input type="number" name="dc" placeholder="DC">
+                <input type="number" name="costPaid" placeholder="Paid (gp)">
+                <input type="number" name="days" placeholder="Days">
+                <button type="button" data-crafting-action="add"><i class="fa-solid fa-plus"></i> Add</button>
+            </div>"#,
+            name = escape_html(&actor.name()),
+        ),
+    )?;
+
+    let container_js = container.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |event: JsValue| {
+        let Some(button) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("[data-crafting-action]"))
+        else {
+            return;
+        };
+        let container = HtmlElement::from(container_js.clone());
+        match button.get_attribute("data-crafting-action").as_deref() {
+            Some("add") => spawn_update(add_project(container)),
+            Some("work") => spawn_update(work_a_day(button)),
+            Some("remove") => spawn_update(remove_project(button)),
+            _ => {}
+        }
+    }) as Box<dyn Fn(JsValue)>);
+    container.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+
+    Ok(container.as_js_value().clone())
+}
+
+/// Start a project from the window's form
+async fn add_project(container: HtmlElement) -> Result<(), Error> {
+    let value = |name: &str| -> Result<Option<String>, Error> {
+        Ok(container
+            .query_selector(&format!(".crafting-new [name={name}]"))?
+            .and_then(|input| input.value())
+            .filter(|value| !value.trim().is_empty()))
+    };
+    let number = |name: &str, label: &str| -> Result<f64, Error> {
+        value(name)?
+            .and_then(|value| value.trim().parse().ok())
+            .ctx(&format!("Enter {label}"))
+    };
+    let project = Project {
+        id: js_sys::Date::now().to_string(),
+        name: value("name")?.ctx("Enter the item being crafted")?,
+        dc: number("dc", "the Crafting DC")?,
+        cost_paid: number("costPaid", "how much has been paid")?,
+        days_remaining: number("days", "how many days are left")?,
+    };
+    let actor = window_actor().await?;
+    let mut all = projects(&actor);
+    all.push(project);
+    save_projects(&actor, &all).await
+}
+
+async fn remove_project(button: HtmlElement) -> Result<(), Error> {
+    let id = button
+        .get_attribute("data-project-id")
+        .ctx("crafting project")?;
+    let actor = window_actor().await?;
+    let mut all = projects(&actor);
+    all.retain(|project| project.id != id);
+    save_projects(&actor, &all).await
+}
+
+/// Roll Crafting for a day of work on the project and record the progress
+///
+/// A success takes a day off the project, a critical success two, and a critical failure ruins a
+/// tenth of the materials paid for.
+async fn work_a_day(button: HtmlElement) -> Result<(), Error> {
+    let id = button
+        .get_attribute("data-project-id")
+        .ctx("crafting project")?;
+    let actor = window_actor().await?;
+    let mut all = projects(&actor);
+    let index = all
+        .iter()
+        .position(|project| project.id == id)
+        .ctx("Project not found")?;
+
+    let Some(check) = actor.roll_skill("crafting", all[index].dc, None).await? else {
+        return Ok(());
+    };
+    let degree = check
+        .degree_of_success()
+        .ctx("Crafting degree of success")?;
+    let project = &mut all[index];
+    let mut note = String::new();
+    match degree {
+        DegreeOfSuccess::CriticalSuccess => project.days_remaining -= 2.0,
+        DegreeOfSuccess::Success => project.days_remaining -= 1.0,
+        DegreeOfSuccess::Failure => {}
+        DegreeOfSuccess::CriticalFailure => {
+            let lost = (project.cost_paid * 0.1 * 100.0).round() / 100.0;
+            project.cost_paid -= lost;
+            note = format!(" {lost} gp of materials were ruined.");
+        }
+    }
+    project.days_remaining = project.days_remaining.max(0.0);
+
+    let progress = if project.days_remaining <= 0.0 {
+        format!("{} is finished!", escape_html(&project.name))
+    } else {
+        format!("{} days left.", project.days_remaining)
+    };
+    Message::create(&format!(
+        r#"<div class="johnys-crafting-progress">
+            <p>{actor_name} worked a day on {name}: <span class="johnys-degree-badge {slug}">{label}</span>.{note}</p>
+            <p>{progress}</p>
+        </div>"#,
+        actor_name = escape_html(&actor.name()),
+        name = escape_html(&project.name),
+        slug = degree.slug(),
+        label = degree.label(),
+    ))
+    .await?;
+
+    if project.days_remaining <= 0.0 {
+        all.remove(index);
+    }
+    save_projects(&actor, &all).await
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.openCraftingProjects()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening crafting projects: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openCraftingProjects", &open_fn.into_js_value());
+    });
+
+    hook!("updateActor", async |actor: JsValue| {
+        let shown = ACTOR_UUID.with_borrow(Clone::clone);
+        if !application::SingletonWindow::is_open(&WINDOW) || Actor::from(actor).uuid() != shown {
+            return;
+        }
+        if let Err(err) = application::SingletonWindow::refresh(&WINDOW).await {
+            cprintln!("Error refreshing crafting projects: {err}");
+        }
+    });
+}
@@ -0,0 +1,85 @@
+use crate::features::is_enabled;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// Critical specialization effect of each weapon group
+const EFFECTS: [(&str, &str); 17] = [
+    ("axe", "Choose one creature adjacent to the target and within reach. If its AC is lower than your attack roll result, it takes damage equal to the weapon damage dice you rolled, including extra dice from a striking rune. This isn't doubled and no bonuses apply."),
+    ("bomb", "Increase the radius of the bomb's splash damage to 10 feet."),
+    ("bow", "If the target is adjacent to a surface, it gets stuck to it by the missile and is immobilized. It must spend an Interact action and succeed at a DC 10 Athletics check to pull the missile free."),
+    ("brawling", "The target must succeed at a Fortitude save against your class DC or be slowed 1 until the end of your next turn."),
+    ("club", "You knock the target up to 10 feet away from you (you choose the distance). This is forced movement."),
+    ("crossbow", "The target takes 1d8 persistent bleed damage, plus the weapon's item bonus to attack rolls."),
+    ("dart", "The target takes 1d6 persistent bleed damage, plus the weapon's item bonus to attack rolls."),
+    ("firearm", "The target must succeed at a Fortitude save against your class DC or be stunned 1."),
+    ("flail", "The target is knocked prone."),
+    ("hammer", "The target is knocked prone."),
+    ("knife", "The target takes 1d6 persistent bleed damage, plus the weapon's item bonus to attack rolls."),
+    ("pick", "The target takes 2 additional damage per weapon damage die."),
+    ("polearm", "The target is moved 5 feet in a direction of your choice. This is forced movement."),
+    ("shield", "You knock the target 5 feet away from you. This is forced movement."),
+    ("sling", "The target must succeed at a Fortitude save against your class DC or be stunned 1."),
+    ("spear", "The target is clumsy 1 until the start of your next turn."),
+    ("sword", "The target is off-guard until the start of your next turn."),
+];
+
+/// Add the weapon group's critical specialization effect to a critical hit by someone who has it
+fn remind_critical_specialization(message: Message, html: HtmlElement) -> Result<(), Error> {
+    if !is_enabled("critSpecReminders") || !message.is_content_visible() {
+        return Ok(());
+    }
+    let Some(context) = message.pf2e_context() else {
+        return Ok(());
+    };
+    if context.roll_type().as_deref() != Some("attack-roll")
+        || context.outcome() != Some(DegreeOfSuccess::CriticalSuccess)
+    {
+        return Ok(());
+    }
+    let (Some(actor), Some(weapon)) = (message.speaker_actor(), message.item()) else {
+        return Ok(());
+    };
+    let Some(group) = weapon.weapon_group() else {
+        return Ok(());
+    };
+    let Some((_, effect)) = EFFECTS.iter().find(|(slug, _)| *slug == group) else {
+        return Ok(());
+    };
+    if !actor.has_critical_specialization(&weapon, &context.options()) {
+        return Ok(());
+    }
+
+    let content = html
+        .query_selector(".message-content")?
+        .ctx("message content")?;
+    content.insert_adjacent_html(
+        "beforeend",
+        &format!(
+            r#"<div class="johnys-crit-spec"><strong>Critical Specialization ({group}):</strong> {effect}</div>"#
+        ),
+    )
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Critical Specialization Reminders")
+            .hint("On a critical hit by someone with critical specialization for the weapon, add the weapon group's effect to the attack in the chat log.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "critSpecReminders");
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        |message: JsValue, html: JsValue| {
+            if let Err(err) = remind_critical_specialization(message.into(), html.into()) {
+                cprintln!("Error adding critical specialization reminder: {err}");
+            }
+        }
+    );
+}
@@ -0,0 +1,132 @@
+use crate::features::{format_coins, player_characters, register_api, treasure};
+use crate::foundry::error::Error;
+use crate::foundry::{application, cprintln, *};
+use crate::hook;
+use wasm_bindgen::prelude::*;
+
+/// Each share of the copper split between `count` characters; the leftover copper pieces go one
+/// each to the first characters
+fn shares(copper: f64, count: usize) -> Vec<f64> {
+    let even = (copper / count as f64).floor();
+    let leftover = (copper - even * count as f64) as usize;
+    (0..count)
+        .map(|index| even + if index < leftover { 1.0 } else { 0.0 })
+        .collect()
+}
+
+/// Split the coins from the form between the checked characters and post a receipt
+async fn split(form: HtmlElement) -> Result<(), Error> {
+    let coins = |name: &str| -> Result<f64, Error> {
+        Ok(form
+            .query_selector(&format!("[name={name}]"))?
+            .and_then(|input| input.value())
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .unwrap_or_default()
+            .max(0.0)
+            .floor())
+    };
+    let copper = coins("gp")? * 100.0 + coins("sp")? * 10.0 + coins("cp")?;
+    if copper <= 0.0 {
+        return Err("Enter an amount to split".into());
+    }
+    let chosen: Vec<String> = form
+        .query_selector_all("[name=actor]")
+        .into_iter()
+        .filter(|checkbox| checkbox.is_checked())
+        .filter_map(|checkbox| checkbox.value())
+        .collect();
+    let actors: Vec<Actor> = player_characters()?
+        .into_iter()
+        .filter(|actor| actor.uuid().is_some_and(|uuid| chosen.contains(&uuid)))
+        .collect();
+    if actors.is_empty() {
+        return Err("Choose who to split the coins between".into());
+    }
+
+    let mut receipt = String::new();
+    for (actor, share) in actors.iter().zip(shares(copper, actors.len())) {
+        if share > 0.0 {
+            actor.add_coins(share).await?;
+        }
+        receipt.push_str(&format!(
+            "<li><strong>{}</strong> received {}.</li>",
+            actor.name(),
+            format_coins(share)
+        ));
+    }
+    treasure::record(copper).await?;
+    Message::create(&format!(
+        r#"<div class="johnys-currency-split">
+            <p><strong>{total}</strong> split between {count} characters</p>
+            <ul>{receipt}</ul>
+        </div>"#,
+        total = format_coins(copper),
+        count = actors.len(),
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Ask for the amount and who shares it, everyone in the party by default
+async fn open() -> Result<(), Error> {
+    if !Game::instance()?.user()?.is_gm() {
+        return Err("Only the GM can split currency".into());
+    }
+    let characters: String = player_characters()?
+        .iter()
+        .filter_map(|actor| {
+            Some(format!(
+                r#"<label class="currency-actor"><input type="checkbox" name="actor" value="{}" checked> {}</label>"#,
+                actor.uuid()?,
+                actor.name()
+            ))
+        })
+        .collect();
+    if characters.is_empty() {
+        return Err("There are no player characters to split coins between".into());
+    }
+
+    let split_fn = Closure::wrap(Box::new(|html: JsValue| {
+        let form = HtmlElement::from(html);
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = split(form).await {
+                cprintln!("Error splitting currency: {err}");
+                UI::notify_error(&err.to_string());
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+
+    application::show_dialog(
+        "Split Currency",
+        format!(
+            r#"<div class="form-group"><label>Gold</label><input type="number" name="gp" min="0" value="0"></div>
+            <div class="form-group"><label>Silver</label><input type="number" name="sp" min="0" value="0"></div>
+            <div class="form-group"><label>Copper</label><input type="number" name="cp" min="0" value="0"></div>
+            <div class="johnys-currency-actors">{characters}</div>"#
+        ),
+        vec![
+            (
+                "split",
+                "Split",
+                Some(split_fn.into_js_value().unchecked_into()),
+            ),
+            ("cancel", "Cancel", None),
+        ],
+    )
+    .await
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.splitCurrency()
+        let split_currency_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening currency splitter: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("splitCurrency", &split_currency_fn.into_js_value());
+    });
+}
@@ -0,0 +1,176 @@
+use crate::features::{is_enabled, register_api};
+use crate::foundry::error::Error;
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+}
+
+/// A user's natural d20 results this session, stored in their flags
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+struct D20Stats {
+    rolls: u32,
+    sum: f64,
+    crits: u32,
+    fumbles: u32,
+}
+
+impl D20Stats {
+    fn average(&self) -> f64 {
+        if self.rolls == 0 {
+            0.0
+        } else {
+            (self.sum / self.rolls as f64 * 10.0).round() / 10.0
+        }
+    }
+}
+
+fn stats(user: &User) -> D20Stats {
+    serde_wasm_bindgen::from_value(user.get_flag(ID, "d20Statistics")).unwrap_or_default()
+}
+
+async fn save_stats(user: &User, stats: &D20Stats) -> Result<(), Error> {
+    let value = stats
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| Error::Custom(format!("Failed to save d20 statistics: {e}")))?;
+    user.set_flag(ID, "d20Statistics", &value).await
+}
+
+/// Count the natural d20 in a message this user rolled, if they've opted in
+async fn record(message: Message) -> Result<(), Error> {
+    if !is_enabled("d20Statistics") {
+        return Ok(());
+    }
+    let user = Game::instance()?.user()?;
+    if message.author().and_then(|author| author.id()) != user.id() {
+        return Ok(());
+    }
+    let Some(natural) = message.first_roll().and_then(|roll| roll.natural_d20()) else {
+        return Ok(());
+    };
+    let mut stats = stats(&user);
+    stats.rolls += 1;
+    stats.sum += natural;
+    if natural == 20.0 {
+        stats.crits += 1;
+    } else if natural == 1.0 {
+        stats.fumbles += 1;
+    }
+    save_stats(&user, &stats).await
+}
+
+/// Open the leaderboard, or refresh it if it's already open
+async fn open() -> Result<(), Error> {
+    let is_gm = Game::instance()?.user()?.is_gm();
+    application::SingletonWindow::open(&WINDOW, || {
+        let builder = application::WindowBuilder::new("johnys-d20-stats", "d20 Leaderboard")
+            .icon("fa-solid fa-dice-d20")
+            .classes(&["johnys-d20-stats"])
+            .position(&ApplicationPosition {
+                width: Some(360.0),
+                ..Default::default()
+            })
+            .render(render_leaderboard);
+        if !is_gm {
+            return builder;
+        }
+        builder.control(
+            "johnysResetD20Stats",
+            "New Session: Reset",
+            "fa-solid fa-rotate",
+            || {
+                wasm_bindgen_futures::spawn_local(async {
+                    if let Err(err) = reset().await {
+                        cprintln!("Error resetting d20 statistics: {err}");
+                        UI::notify_error(&format!("Unable to reset d20 statistics: {err}"));
+                    }
+                });
+            },
+        )
+    })
+    .await
+}
+
+/// Everyone who's rolled this session, luckiest average first
+async fn render_leaderboard() -> Result<JsValue, Error> {
+    let mut ranked: Vec<(String, D20Stats)> = Game::instance()?
+        .users()?
+        .iter()
+        .map(|user| (escape_html(&user.name().unwrap_or_default()), stats(&user)))
+        .filter(|(_, stats)| stats.rolls > 0)
+        .collect();
+    ranked.sort_by(|(_, a), (_, b)| b.average().total_cmp(&a.average()));
+    if ranked.is_empty() {
+        return Ok(JsValue::from_str(
+            r#"<p class="johnys-empty">No d20s rolled this session. Players opt in under the module's settings.</p>"#,
+        ));
+    }
+    let rows: String = ranked
+        .iter()
+        .map(|(name, stats)| {
+            format!(
+                "<tr><td>{name}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                stats.rolls,
+                stats.average(),
+                stats.crits,
+                stats.fumbles
+            )
+        })
+        .collect();
+    Ok(JsValue::from_str(&format!(
+        r#"<table class="d20-stats-table">
+            <thead><tr><th>Player</th><th>Rolls</th><th>Average</th><th>Nat 20s</th><th>Nat 1s</th></tr></thead>
+            <tbody>{rows}</tbody>
+        </table>"#
+    )))
+}
+
+/// Clear everyone's statistics for a new session
+async fn reset() -> Result<(), Error> {
+    for user in Game::instance()?.users()?.iter() {
+        if stats(&user).rolls > 0 {
+            save_stats(&user, &D20Stats::default()).await?;
+        }
+    }
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("d20 Statistics")
+            .hint("Track your natural d20 results this session, with crits, fumbles, and your average, and show them on the d20 leaderboard.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "d20Statistics");
+
+        // game.modules.get("johnys-module").api.openD20Leaderboard()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening d20 leaderboard: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openD20Leaderboard", &open_fn.into_js_value());
+    });
+
+    hook!("createChatMessage", async |message: JsValue| {
+        if let Err(err) = record(message.into()).await {
+            cprintln!("Error recording d20 statistics: {err}");
+        }
+    });
+
+    hook!("updateUser", async |_user: JsValue| {
+        if let Err(err) = application::SingletonWindow::refresh(&WINDOW).await {
+            cprintln!("Error refreshing d20 leaderboard: {err}");
+        }
+    });
+}
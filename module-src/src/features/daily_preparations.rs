@@ -0,0 +1,306 @@
+use crate::features::{register_api, selected_actor};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Something to do during daily preparations
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum StepKind {
+    /// A reminder, since preparing spells is done on the sheet
+    PrepareSpells,
+    Focus,
+    DailyUses,
+    Invest,
+    Reagents,
+}
+
+/// A line of the checklist, stored in the character's flags so it can be undone
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Step {
+    kind: StepKind,
+    label: String,
+    done: bool,
+    /// What the step changed, as item ID (empty for the actor) and the value before
+    #[serde(default)]
+    undo: Vec<(String, f64)>,
+}
+
+fn steps(actor: &Actor) -> Vec<Step> {
+    serde_wasm_bindgen::from_value(actor.get_flag(ID, "dailyPreparations")).unwrap_or_default()
+}
+
+async fn save_steps(actor: &Actor, steps: &[Step]) -> Result<(), Error> {
+    let value = steps
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| Error::Custom(format!("Failed to save daily preparations: {e}")))?;
+    actor.set_flag(ID, "dailyPreparations", &value).await
+}
+
+/// Items that need investing again: worn, with the invested trait, and not invested yet
+fn uninvested(actor: &Actor) -> Vec<Item> {
+    actor
+        .items()
+        .into_iter()
+        .filter(|item| {
+            item.carry_type().as_deref() == Some("worn")
+                && item.traits().iter().any(|t| t == "invested")
+                && !item.is_invested()
+        })
+        .collect()
+}
+
+/// Abilities and items with daily uses that have been spent
+fn spent_daily_uses(actor: &Actor) -> Vec<Item> {
+    actor
+        .items()
+        .into_iter()
+        .filter(|item| {
+            item.frequency()
+                .is_some_and(|(value, max, per)| per == "day" && value < max)
+        })
+        .collect()
+}
+
+/// The steps that apply to the character this morning
+fn checklist(actor: &Actor) -> Vec<Step> {
+    let step = |kind, label: String| Step {
+        kind,
+        label,
+        done: false,
+        undo: Vec::new(),
+    };
+    let mut steps = Vec::new();
+    let prepared: Vec<String> = actor
+        .spellcasting_entries()
+        .iter()
+        .filter(|entry| entry.is_prepared())
+        .map(|entry| entry.name())
+        .collect();
+    if !prepared.is_empty() {
+        steps.push(step(
+            StepKind::PrepareSpells,
+            format!("Prepare spells: {}", prepared.join(", ")),
+        ));
+    }
+    if let Some((focus, max)) = actor.focus_points().filter(|(_, max)| *max > 0.0) {
+        steps.push(step(
+            StepKind::Focus,
+            format!("Refill focus points ({focus} / {max})"),
+        ));
+    }
+    let spent = spent_daily_uses(actor);
+    if !spent.is_empty() {
+        let names: Vec<String> = spent.iter().map(Item::name).collect();
+        steps.push(step(
+            StepKind::DailyUses,
+            format!("Restore daily uses: {}", names.join(", ")),
+        ));
+    }
+    let uninvested = uninvested(actor);
+    if !uninvested.is_empty() {
+        let names: Vec<String> = uninvested.iter().map(Item::name).collect();
+        steps.push(step(
+            StepKind::Invest,
+            format!("Invest items: {}", names.join(", ")),
+        ));
+    }
+    if let Some((reagents, max)) = actor.infused_reagents().filter(|(_, max)| *max > 0.0) {
+        steps.push(step(
+            StepKind::Reagents,
+            format!("Refresh infused reagents ({reagents} / {max})"),
+        ));
+    }
+    steps
+}
+
+/// Carry out a step, returning what it changed
+async fn apply(actor: &Actor, kind: StepKind) -> Result<Vec<(String, f64)>, Error> {
+    let mut undo = Vec::new();
+    match kind {
+        StepKind::PrepareSpells => {}
+        StepKind::Focus => {
+            if let Some((focus, max)) = actor.focus_points() {
+                actor.set_focus_points(max).await?;
+                undo.push((String::new(), focus));
+            }
+        }
+        StepKind::DailyUses => {
+            for item in spent_daily_uses(actor) {
+                let (Some(id), Some((value, max, _))) = (item.id(), item.frequency()) else {
+                    continue;
+                };
+                item.set_frequency_uses(max).await?;
+                undo.push((id, value));
+            }
+        }
+        StepKind::Invest => {
+            for item in uninvested(actor) {
+                let Some(id) = item.id() else {
+                    continue;
+                };
+                item.set_invested(true).await?;
+                undo.push((id, 0.0));
+            }
+        }
+        StepKind::Reagents => {
+            if let Some((reagents, max)) = actor.infused_reagents() {
+                actor.set_infused_reagents(max).await?;
+                undo.push((String::new(), reagents));
+            }
+        }
+    }
+    Ok(undo)
+}
+
+/// Put back what a step changed
+async fn revert(actor: &Actor, kind: StepKind, undo: &[(String, f64)]) -> Result<(), Error> {
+    let items = actor.items();
+    let item = |id: &str| items.iter().find(|item| item.id().as_deref() == Some(id));
+    for (id, value) in undo {
+        match kind {
+            StepKind::PrepareSpells => {}
+            StepKind::Focus => actor.set_focus_points(*value).await?,
+            StepKind::Reagents => actor.set_infused_reagents(*value).await?,
+            StepKind::DailyUses => {
+                if let Some(item) = item(id) {
+                    item.set_frequency_uses(*value).await?;
+                }
+            }
+            StepKind::Invest => {
+                if let Some(item) = item(id) {
+                    item.set_invested(false).await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The checklist card, with a button on each line to mark it done or undo it
+fn render_card(actor: &Actor, steps: &[Step]) -> String {
+    let rows: String = steps
+        .iter()
+        .enumerate()
+        .map(|(index, step)| {
+            let (class, icon, label) = if step.done {
+                (" done", "fa-solid fa-rotate-left", "Undo")
+            } else {
+                ("", "fa-solid fa-check", "Done")
+            };
+            format!(
+                r#"<li class="daily-prep-step{class}">
+                    <span>{text}</span>
+                    <button type="button" data-daily-prep="{index}"><i class="{icon}"></i> {label}</button>
+                </li>"#,
+                text = step.label,
+            )
+        })
+        .collect();
+    format!(
+        r#"<div class="johnys-daily-preps" data-actor-uuid="{uuid}">
+            <p><strong>{name}'s daily preparations</strong></p>
+            <ul>{rows}</ul>
+        </div>"#,
+        uuid = actor.uuid().unwrap_or_default(),
+        name = actor.name(),
+    )
+}
+
+/// Start the selected character's checklist and whisper it to their player and the GM
+async fn start() -> Result<(), Error> {
+    let actor = selected_actor().ctx("Select a character to prepare for the day")?;
+    let steps = checklist(&actor);
+    if steps.is_empty() {
+        return Err(format!("{} has nothing to prepare", actor.name()).into());
+    }
+    save_steps(&actor, &steps).await?;
+
+    let game = Game::instance()?;
+    let user_ids: Vec<String> = game
+        .users()?
+        .iter()
+        .filter(|user| user.is_gm())
+        .filter_map(|user| user.id())
+        .chain(game.user()?.id())
+        .collect();
+    Message::create_whisper(&render_card(&actor, &steps), &user_ids).await?;
+    Ok(())
+}
+
+/// Mark the step done, or undo it if it already is, and update the card
+async fn toggle(message: Message, actor_uuid: String, index: usize) -> Result<(), Error> {
+    let actor: Actor = Game::from_uuid(&actor_uuid).await?;
+    let mut steps = steps(&actor);
+    let step = steps
+        .get_mut(index)
+        .ctx("These preparations were replaced by newer ones")?;
+    if step.done {
+        revert(&actor, step.kind, &step.undo).await?;
+        step.undo.clear();
+        step.done = false;
+    } else {
+        step.undo = apply(&actor, step.kind).await?;
+        step.done = true;
+    }
+    save_steps(&actor, &steps).await?;
+    message.set_content(&render_card(&actor, &steps)).await
+}
+
+/// Let the character's owners tick off the checklist's steps
+fn wire_buttons(message: Message, html: HtmlElement) -> Result<(), Error> {
+    let Some(card) = html.query_selector(".johnys-daily-preps")? else {
+        return Ok(());
+    };
+    let actor_uuid = card
+        .get_attribute("data-actor-uuid")
+        .ctx("daily preparations actor")?;
+    let click_fn = Closure::wrap(Box::new(move |event: JsValue| {
+        let Some(index) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("[data-daily-prep]"))
+            .and_then(|button| button.get_attribute("data-daily-prep"))
+            .and_then(|index| index.parse().ok())
+        else {
+            return;
+        };
+        let message = Message::from(message.as_js_value().clone());
+        let actor_uuid = actor_uuid.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = toggle(message, actor_uuid, index).await {
+                cprintln!("Error updating daily preparations: {err}");
+                UI::notify_error(&format!("Unable to update daily preparations: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    card.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.dailyPreparations()
+        let start_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = start().await {
+                    cprintln!("Error starting daily preparations: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("dailyPreparations", &start_fn.into_js_value());
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        |message: JsValue, html: JsValue| {
+            if let Err(err) = wire_buttons(message.into(), html.into()) {
+                cprintln!("Error wiring daily preparations: {err}");
+            }
+        }
+    );
+}
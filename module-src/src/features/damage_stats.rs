@@ -0,0 +1,283 @@
+use crate::features::{is_responsible_gm, register_api};
+use crate::foundry::error::Error;
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+    /// Damage recorded but not yet saved, by actor UUID
+    static PENDING: RefCell<HashMap<String, Tally>> = RefCell::new(HashMap::new());
+    /// Whether a save is running, which picks up anything recorded meanwhile
+    static SAVING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Damage an actor dealt and took this session
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct Tally {
+    name: String,
+    /// Damage rolled, before the target's resistances
+    dealt: f64,
+    taken: f64,
+    biggest_hit: f64,
+}
+
+impl Tally {
+    /// Count more damage for the same actor
+    fn add(&mut self, other: &Tally) {
+        self.name.clone_from(&other.name);
+        self.dealt += other.dealt;
+        self.taken += other.taken;
+        self.biggest_hit = self.biggest_hit.max(other.biggest_hit);
+    }
+}
+
+/// Each actor's tally this session, by actor UUID
+fn tallies() -> HashMap<String, Tally> {
+    serde_wasm_bindgen::from_value(get_setting(ID, "damageStatistics")).unwrap_or_default()
+}
+
+async fn save_tallies(tallies: &HashMap<String, Tally>) -> Result<(), Error> {
+    let value = tallies
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| Error::Custom(format!("Failed to save damage statistics: {e}")))?;
+    set_setting(ID, "damageStatistics", &value).await
+}
+
+/// Tallies with the most damage dealt first
+fn ranked() -> Vec<Tally> {
+    let mut ranked: Vec<Tally> = tallies().into_values().collect();
+    ranked.sort_by(|a, b| b.dealt.total_cmp(&a.dealt));
+    ranked
+}
+
+/// Count damage rolled by an actor or applied to one
+async fn record(message: Message) -> Result<(), Error> {
+    if !is_responsible_gm(&Game::instance()?)? {
+        return Ok(());
+    }
+    let (actor, dealt, taken) = if let Some(applied) = message.applied_damage() {
+        if applied.is_healing() {
+            return Ok(());
+        }
+        let Some(uuid) = applied.actor_uuid() else {
+            return Ok(());
+        };
+        let actor: Actor = Game::from_uuid(&uuid).await?;
+        // the message keeps what the hit points were before, for undoing it
+        let lost = |path: &str, now: Option<f64>| match (applied.previous_value(path), now) {
+            (Some(before), Some(now)) => (before - now).max(0.0),
+            _ => 0.0,
+        };
+        let taken = lost("system.attributes.hp.value", actor.hit_points())
+            + lost("system.attributes.hp.temp", actor.temp_hit_points());
+        (actor, 0.0, taken)
+    } else if message.pf2e_type().as_deref() == Some("damage-roll") {
+        let Some(roll) = message.first_roll() else {
+            return Ok(());
+        };
+        // persistent damage is "rolled" by the creature taking it
+        if roll.is_healing() || message.is_persistent_damage() {
+            return Ok(());
+        }
+        let Some(actor) = message.speaker_actor() else {
+            return Ok(());
+        };
+        (actor, roll.total(), 0.0)
+    } else {
+        return Ok(());
+    };
+    if dealt <= 0.0 && taken <= 0.0 {
+        return Ok(());
+    }
+    let Some(uuid) = actor.uuid() else {
+        return Ok(());
+    };
+
+    PENDING.with_borrow_mut(|pending| {
+        pending.entry(uuid).or_default().add(&Tally {
+            name: actor.name(),
+            dealt,
+            taken,
+            biggest_hit: dealt,
+        })
+    });
+    if SAVING.replace(true) {
+        return Ok(());
+    }
+    let result = save_pending().await;
+    SAVING.set(false);
+    result?;
+    application::SingletonWindow::refresh(&WINDOW).await
+}
+
+/// Save recorded damage one batch at a time
+///
+/// Damage messages often arrive several at once, and each reading the setting before the others
+/// have written it would lose all but the last.
+async fn save_pending() -> Result<(), Error> {
+    loop {
+        let pending = PENDING.take();
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let mut all = tallies();
+        for (uuid, recorded) in pending {
+            all.entry(uuid).or_default().add(&recorded);
+        }
+        save_tallies(&all).await?;
+    }
+}
+
+/// Open the statistics, or refresh them if they're already open
+async fn open() -> Result<(), Error> {
+    if !Game::instance()?.user()?.is_gm() {
+        return Err("Only the GM can see damage statistics".into());
+    }
+    application::SingletonWindow::open(&WINDOW, || {
+        application::WindowBuilder::new("johnys-damage-stats", "Damage Statistics")
+            .icon("fa-solid fa-chart-column")
+            .classes(&["johnys-damage-stats"])
+            .position(&ApplicationPosition {
+                width: Some(400.0),
+                ..Default::default()
+            })
+            .render(render_table)
+            .control(
+                "johnysPostDamageSummary",
+                "Post Session Summary",
+                "fa-solid fa-trophy",
+                || spawn_update(post_summary()),
+            )
+            .control(
+                "johnysResetDamageStats",
+                "New Session: Reset",
+                "fa-solid fa-rotate",
+                || spawn_update(reset()),
+            )
+    })
+    .await
+}
+
+fn spawn_update(update: impl std::future::Future<Output = Result<(), Error>> + 'static) {
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = update.await {
+            cprintln!("Error updating damage statistics: {err}");
+            UI::notify_error(&format!("Unable to update damage statistics: {err}"));
+        }
+    });
+}
+
+/// A table of everyone's damage this session, most dealt first
+fn table(ranked: &[Tally]) -> String {
+    let rows: String = ranked
+        .iter()
+        .map(|tally| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&tally.name),
+                tally.dealt,
+                tally.biggest_hit,
+                tally.taken
+            )
+        })
+        .collect();
+    format!(
+        r#"<table class="damage-stats-table">
+            <thead><tr><th>Name</th><th>Dealt</th><th>Biggest Hit</th><th>Taken</th></tr></thead>
+            <tbody>{rows}</tbody>
+        </table>"#
+    )
+}
+
+async fn render_table() -> Result<JsValue, Error> {
+    let ranked = ranked();
+    let content = if ranked.is_empty() {
+        r#"<p class="johnys-empty">No damage recorded this session.</p>"#.to_string()
+    } else {
+        table(&ranked)
+    };
+    Ok(JsValue::from_str(&content))
+}
+
+/// Post the session's recap, crowning whoever dealt the most damage
+async fn post_summary() -> Result<(), Error> {
+    let ranked = ranked();
+    let Some(mvp) = ranked.first() else {
+        return Err("No damage recorded this session".into());
+    };
+    let mut highlights = format!(
+        r#"<p class="damage-stats-mvp"><i class="fa-solid fa-trophy"></i> MVP: <strong>{}</strong> with {} damage</p>"#,
+        escape_html(&mvp.name),
+        mvp.dealt
+    );
+    if let Some(biggest) = ranked
+        .iter()
+        .max_by(|a, b| a.biggest_hit.total_cmp(&b.biggest_hit))
+    {
+        highlights.push_str(&format!(
+            "<p>Biggest hit: <strong>{}</strong> for {}</p>",
+            escape_html(&biggest.name),
+            biggest.biggest_hit
+        ));
+    }
+    if let Some(tank) = ranked
+        .iter()
+        .filter(|tally| tally.taken > 0.0)
+        .max_by(|a, b| a.taken.total_cmp(&b.taken))
+    {
+        highlights.push_str(&format!(
+            "<p>Took the most: <strong>{}</strong> with {}</p>",
+            escape_html(&tank.name),
+            tank.taken
+        ));
+    }
+    Message::create(&format!(
+        r#"<div class="johnys-damage-summary">
+            <p><strong>Session damage recap</strong></p>
+            {highlights}
+            {table}
+        </div>"#,
+        table = table(&ranked),
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Start a new session's statistics
+async fn reset() -> Result<(), Error> {
+    save_tallies(&HashMap::new()).await?;
+    application::SingletonWindow::refresh(&WINDOW).await
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .scope("world")
+            .config(false)
+            .type_object()
+            .default_value(&js_sys::Object::new())
+            .register(ID, "damageStatistics");
+
+        // game.modules.get("johnys-module").api.openDamageStatistics()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening damage statistics: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openDamageStatistics", &open_fn.into_js_value());
+    });
+
+    hook!("createChatMessage", async |message: JsValue| {
+        if let Err(err) = record(message.into()).await {
+            cprintln!("Error recording damage statistics: {err}");
+        }
+    });
+}
@@ -0,0 +1,138 @@
+use crate::features::{apply_message_damage, dedup_uuids, is_enabled};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    /// Message and actor UUID pairs this client has already applied damage for
+    static APPLIED: RefCell<HashSet<(String, String)>> = RefCell::new(HashSet::new());
+}
+
+/// The buttons for one target, or a note that its damage was already applied
+fn prompt_buttons(actor: &Actor, applied: bool) -> String {
+    if applied {
+        return r#"<span class="johnys-damage-applied"><i class="fa-solid fa-check"></i> Applied</span>"#
+            .to_string();
+    }
+    let shield_block = if actor.has_raised_shield() {
+        r#"<button type="button" data-multiplier="1" data-shield-block="true" data-tooltip="Shield Block, then apply the rest"><i class="fa-solid fa-shield-blank"></i> Shield Block</button>"#
+    } else {
+        ""
+    };
+    format!(
+        r#"<button type="button" data-multiplier="1" data-tooltip="Apply full damage"><i class="fa-solid fa-heart-broken"></i> Apply</button>
+        <button type="button" data-multiplier="0.5" data-tooltip="Apply half damage"><i class="fa-solid fa-heart-crack"></i> Half</button>
+        {shield_block}"#
+    )
+}
+
+/// Add apply buttons to a damage roll in the chat log for each targeted actor this player owns
+async fn inject_damage_prompt(message: Message, html: HtmlElement) -> Result<(), Error> {
+    if !is_enabled("damagePromptEnabled")
+        || message.pf2e_type().as_deref() != Some("damage-roll")
+        || message.first_roll().is_some_and(|roll| roll.is_healing())
+    {
+        return Ok(());
+    }
+    let mut targets = message.target_uuids().await;
+    dedup_uuids(&mut targets);
+
+    let mut rows = String::new();
+    for uuid in targets {
+        let Ok(actor) = Game::from_uuid(&uuid).await else {
+            continue;
+        };
+        // GMs already get PF2e's own buttons for everyone
+        if !actor.is_owned_by_current_user(GMStrategy::Never, OwnershipLevel::Owner) {
+            continue;
+        }
+        let applied =
+            APPLIED.with_borrow(|applied| applied.contains(&(message.id(), uuid.clone())));
+        rows.push_str(&format!(
+            r#"<div class="johnys-damage-prompt" data-actor-uuid="{uuid}">
+                <span class="johnys-damage-prompt-name">{}</span>
+                <div class="johnys-damage-buttons">{}</div>
+            </div>"#,
+            escape_html(&actor.name()),
+            prompt_buttons(&actor, applied)
+        ));
+    }
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let content = html
+        .query_selector(".message-content")?
+        .ctx("message content")?;
+    content.insert_adjacent_html("beforeend", &rows)?;
+
+    let message_js = message.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |event: JsValue| {
+        let Some(button) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest(".johnys-damage-prompt button"))
+        else {
+            return;
+        };
+        let Some(row) = button.closest(".johnys-damage-prompt") else {
+            return;
+        };
+        let multiplier = button
+            .get_attribute("data-multiplier")
+            .and_then(|m| m.parse().ok())
+            .unwrap_or(1.0);
+        let shield_block = button.get_attribute("data-shield-block").is_some();
+        let message: Message = message_js.clone().into();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = apply_from_prompt(&message, &row, multiplier, shield_block).await {
+                cprintln!("Error applying damage: {err}");
+                UI::notify_error(&format!("Unable to apply damage: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    content.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+
+    Ok(())
+}
+
+/// Apply the damage for one prompt row and replace its buttons so it can't be applied twice
+async fn apply_from_prompt(
+    message: &Message,
+    row: &HtmlElement,
+    multiplier: f64,
+    shield_block: bool,
+) -> Result<(), Error> {
+    let uuid = row.get_attribute("data-actor-uuid").ctx("prompt actor")?;
+    let actor: Actor = Game::from_uuid(&uuid).await?;
+    apply_message_damage(message, &actor, multiplier, shield_block).await?;
+    APPLIED.with_borrow_mut(|applied| applied.insert((message.id(), uuid)));
+    if let Some(buttons) = row.query_selector(".johnys-damage-buttons")? {
+        buttons.set_inner_html(&prompt_buttons(&actor, true))?;
+    }
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Damage Prompts in Chat")
+            .hint("Add Apply, Half, and Shield Block buttons to damage rolls in the chat log that target creatures you own. Shield Block only shows while your shield is raised, and uses the PF2e system to apply hardness and shield damage.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "damagePromptEnabled");
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        async |message: JsValue, html: JsValue| {
+            if let Err(err) = inject_damage_prompt(message.into(), html.into()).await {
+                cprintln!("Error adding damage prompt: {err}");
+            }
+        }
+    );
+}
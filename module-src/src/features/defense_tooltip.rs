@@ -0,0 +1,141 @@
+use crate::features::is_enabled;
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+const SAVES: [(&str, &str); 3] = [("fortitude", "Fort"), ("reflex", "Ref"), ("will", "Will")];
+
+/// How much of a creature's defenses the viewer may see
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Detail {
+    None,
+    /// Only how hurt it is
+    Health,
+    All,
+}
+
+impl Detail {
+    /// The detail shown for the token: everything for the GM, nothing for players unless the world
+    /// setting lets them see some, in which case owners see everything
+    fn for_token(actor: &Actor) -> Result<Self, Error> {
+        if Game::instance()?.user()?.is_gm() {
+            return Ok(Detail::All);
+        }
+        let players = match get_setting(ID, "defenseTooltipPlayers")
+            .as_string()
+            .as_deref()
+        {
+            Some("health") => Detail::Health,
+            Some("all") => Detail::All,
+            _ => Detail::None,
+        };
+        if players != Detail::None && actor.is_owner() {
+            return Ok(Detail::All);
+        }
+        Ok(players)
+    }
+}
+
+fn remove_tooltip() -> Result<(), Error> {
+    if let Some(tooltip) = Document::body()?.query_selector("#johnys-defense-tooltip")? {
+        tooltip.remove()?;
+    }
+    Ok(())
+}
+
+/// Show the hovered creature's AC, saves, and hit points next to its token
+fn show_tooltip(token: &Token) -> Result<(), Error> {
+    remove_tooltip()?;
+    if !is_enabled("defenseTooltips") {
+        return Ok(());
+    }
+    let Some(actor) = token.actor() else {
+        return Ok(());
+    };
+    let detail = Detail::for_token(&actor)?;
+    if detail == Detail::None {
+        return Ok(());
+    }
+    let Some((_, top, right, _)) = token.client_bounds() else {
+        return Ok(());
+    };
+
+    let mut lines = Vec::new();
+    if detail == Detail::All {
+        if let Some(ac) = actor.armor_class() {
+            lines.push(format!("<div><strong>AC</strong> {ac}</div>"));
+        }
+        let saves: Vec<String> = SAVES
+            .iter()
+            .filter_map(|(save, label)| {
+                let modifier = actor.save_modifier(save)?;
+                Some(format!("<strong>{label}</strong> {modifier:+}"))
+            })
+            .collect();
+        if !saves.is_empty() {
+            lines.push(format!("<div>{}</div>", saves.join(" ")));
+        }
+    }
+    if let (Some(hp), Some(max_hp)) = (actor.hit_points(), actor.max_hit_points()) {
+        let percent = (hp / max_hp.max(1.0) * 100.0).round().clamp(0.0, 100.0);
+        lines.push(if detail == Detail::All {
+            format!("<div><strong>HP</strong> {hp}/{max_hp} ({percent}%)</div>")
+        } else {
+            format!("<div><strong>HP</strong> {percent}%</div>")
+        });
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let tooltip = Document::create_element("div")?;
+    tooltip.set_attribute("id", "johnys-defense-tooltip")?;
+    tooltip.set_style(&format!("left: {}px; top: {top}px;", right + 8.0))?;
+    tooltip.set_inner_html(&format!(
+        "<div class=\"defense-tooltip-name\">{}</div>{}",
+        escape_html(&token.name().unwrap_or_else(|| actor.name())),
+        lines.join("")
+    ))?;
+    Document::body()?.append_child(&tooltip)
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Defense Tooltips")
+            .hint(
+                "Show a creature's AC, saves, and hit points next to its token when you hover it.",
+            )
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "defenseTooltips");
+
+        SettingConfig::new()
+            .name("Defense Tooltips for Players")
+            .hint("What players see when hovering creatures they don't own. With anything but nothing, players also see everything about their own creatures. The GM always sees everything.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .choices(&[
+                ("none", "Nothing"),
+                ("health", "Hit points as a percentage"),
+                ("all", "AC, saves, and hit points"),
+            ])
+            .default_string("none")
+            .register(ID, "defenseTooltipPlayers");
+    });
+
+    hook!("hoverToken", |token: JsValue, hovered: JsValue| {
+        let result = if hovered.as_bool() == Some(true) {
+            show_tooltip(&token.into())
+        } else {
+            remove_tooltip()
+        };
+        if let Err(err) = result {
+            cprintln!("Error showing defense tooltip: {err}");
+        }
+    });
+}
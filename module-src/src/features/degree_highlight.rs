@@ -0,0 +1,63 @@
+use crate::features::is_enabled;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// Check if this user is allowed to know how the check went
+fn outcome_visible(message: &Message) -> bool {
+    if !message.is_content_visible() {
+        return false;
+    }
+    let is_gm = Game::instance().is_ok_and(|game| game.user().is_ok_and(|user| user.is_gm()));
+    // PF2e can hide check results from players
+    is_gm
+        || get_setting("pf2e", "metagame_showResults")
+            .as_bool()
+            .unwrap_or(true)
+}
+
+/// Color a rendered check message by its degree of success and badge it with the outcome
+fn highlight_outcome(message: Message, html: HtmlElement) -> Result<(), Error> {
+    if !is_enabled("degreeHighlightEnabled") || !outcome_visible(&message) {
+        return Ok(());
+    }
+    let Some(outcome) = message.pf2e_context().and_then(|context| context.outcome()) else {
+        return Ok(());
+    };
+    html.toggle_class("johnys-degree", true)?;
+    html.toggle_class(outcome.slug(), true)?;
+    let content = html
+        .query_selector(".message-content")?
+        .ctx("message content")?;
+    content.insert_adjacent_html(
+        "afterbegin",
+        &format!(
+            r#"<div class="johnys-degree-badge {}">{}</div>"#,
+            outcome.slug(),
+            outcome.label()
+        ),
+    )
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Highlight Degrees of Success")
+            .hint("Color checks in the chat log by their outcome, from critical success to critical failure, and label each with a badge. Results the GM hides from players stay hidden.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "degreeHighlightEnabled");
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        |message: JsValue, html: JsValue| {
+            if let Err(err) = highlight_outcome(message.into(), html.into()) {
+                cprintln!("Error highlighting degree of success: {err}");
+            }
+        }
+    );
+}
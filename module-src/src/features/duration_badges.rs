@@ -0,0 +1,127 @@
+use crate::features::is_enabled;
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Seconds in a combat round
+const ROUND_SECONDS: f64 = 6.0;
+
+thread_local! {
+    /// Badges drawn on each token, by token ID
+    static BADGES: RefCell<HashMap<String, Graphics>> = RefCell::new(HashMap::new());
+}
+
+/// Rounds left on each of the actor's timed effects and the conditions they grant, by icon
+fn rounds_by_icon(actor: &Actor) -> Vec<(String, f64)> {
+    let rounds = |item: &Item| {
+        let remaining = item.remaining_duration()?;
+        Some((remaining / ROUND_SECONDS).ceil()).filter(|rounds| *rounds > 0.0)
+    };
+    let effects = actor
+        .effects()
+        .into_iter()
+        .filter_map(|effect| Some((effect.img()?, rounds(&effect)?)));
+    // a condition like slowed runs out with the effect that granted it
+    let conditions = actor.conditions().into_iter().filter_map(|condition| {
+        let granted_by = condition.granted_by()?;
+        Some((condition.img()?, rounds(&granted_by)?))
+    });
+    effects.chain(conditions).collect()
+}
+
+/// Redraw the rounds left in the corner of each of the token's timed effect icons
+fn draw_badges(token: &Token) -> Result<(), Error> {
+    let Some(id) = token.id() else {
+        return Ok(());
+    };
+    if let Some(badges) = BADGES.with_borrow_mut(|badges| badges.remove(&id)) {
+        badges.destroy();
+    }
+    if !is_enabled("durationBadges") {
+        return Ok(());
+    }
+    let Some(actor) = token.actor() else {
+        return Ok(());
+    };
+    let rounds = rounds_by_icon(&actor);
+    if rounds.is_empty() {
+        return Ok(());
+    }
+
+    let badges = Graphics::new()?;
+    let mut drawn = false;
+    for (src, x, y, size) in token.effect_icons() {
+        // the icon's texture is loaded from the full, URL-encoded path
+        let Some((_, rounds)) = rounds
+            .iter()
+            .find(|(img, _)| src.ends_with(&img.replace(' ', "%20")))
+        else {
+            continue;
+        };
+        let badge_size = size * 0.6;
+        badges.draw_badge(
+            x + size - badge_size / 2.0,
+            y + size - badge_size / 2.0,
+            badge_size,
+            &rounds.to_string(),
+        )?;
+        drawn = true;
+    }
+    if drawn {
+        token.add_child(&badges)?;
+        BADGES.with_borrow_mut(|all| all.insert(id, badges));
+    }
+    Ok(())
+}
+
+fn redraw_all() {
+    let Ok(game) = Game::instance() else {
+        return;
+    };
+    for token in game.scene_tokens() {
+        if let Err(err) = draw_badges(&token) {
+            cprintln!("Error drawing duration badges: {err}");
+        }
+    }
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Duration Badges")
+            .hint("Show how many rounds are left on timed effects, and the conditions they grant, in the corner of their icons on tokens.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "durationBadges");
+    });
+
+    hook!("canvasReady", || {
+        BADGES.with_borrow_mut(HashMap::clear);
+    });
+
+    // effect icons are drawn asynchronously, and the token refreshes its effects once they're done
+    hook!("refreshToken", |token: JsValue, flags: JsValue| {
+        if !get_property(&flags, "refreshEffects").is_ok_and(|flag| flag.is_truthy()) {
+            return;
+        }
+        if let Err(err) = draw_badges(&token.into()) {
+            cprintln!("Error drawing duration badges: {err}");
+        }
+    });
+
+    // durations count down without the effects changing, so nothing else redraws them
+    hook!("combatTurnChange", |_combat: JsValue, _prior: JsValue| {
+        redraw_all();
+    });
+
+    hook!("clientSettingChanged", |key: JsValue| {
+        if key.as_string() == Some(format!("{ID}.durationBadges")) {
+            redraw_all();
+        }
+    });
+}
@@ -0,0 +1,144 @@
+use crate::features::is_enabled;
+use crate::foundry::application;
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::{get_path, hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// Check if this client should prompt for the actor, i.e. it's a character the user plays
+fn should_prompt(actor: &Actor) -> bool {
+    is_enabled("dyingPrompts")
+        && actor.actor_type().as_deref() == Some("character")
+        && actor.is_owned_by_current_user(GMStrategy::IfNoPlayers, OwnershipLevel::Owner)
+}
+
+/// The Dying value a character ends up at after being knocked out, or after taking damage while dying
+fn dying_value(actor: &Actor, critical: bool) -> f64 {
+    let increase = if critical { 2.0 } else { 1.0 };
+    match actor.condition_value("dying") {
+        Some(dying) => dying + increase,
+        None => increase + actor.condition_value("wounded").unwrap_or_default(),
+    }
+}
+
+/// Make a dialog button that sets the actor's Dying value
+fn dying_button(actor: &Actor, value: f64) -> js_sys::Function {
+    let actor_js = actor.as_js_value().clone();
+    let apply_fn = Closure::wrap(Box::new(move || {
+        let actor = Actor::from(actor_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = actor.increase_condition("dying", value).await {
+                cprintln!("Error applying dying: {err}");
+                UI::notify_error(&format!("Unable to apply Dying: {err}"));
+            }
+        });
+    }) as Box<dyn Fn()>);
+    apply_fn.into_js_value().unchecked_into()
+}
+
+/// Offer to apply Dying when a character the user plays drops to 0 HP
+async fn prompt_dying(actor: Actor, changes: JsValue) -> Result<(), Error> {
+    let dropped_to_zero = get_path!(&changes, "system.attributes.hp.value")
+        .ok()
+        .and_then(|hp| hp.as_f64())
+        .is_some_and(|hp| hp <= 0.0);
+    if !dropped_to_zero || !should_prompt(&actor) {
+        return Ok(());
+    }
+
+    let name = actor.name();
+    let normal = dying_value(&actor, false);
+    let critical = dying_value(&actor, true);
+    let status = match (
+        actor.condition_value("dying"),
+        actor.condition_value("wounded"),
+    ) {
+        (Some(dying), _) => format!("is already Dying {dying}"),
+        (None, Some(wounded)) => format!("is Wounded {wounded}"),
+        (None, None) => "isn't wounded".to_string(),
+    };
+    let normal_label = format!("Dying {normal}");
+    let critical_label = format!("Critical hit: Dying {critical}");
+
+    application::show_dialog(
+        "Dropped to 0 HP",
+        format!("<p>{name} dropped to 0 Hit Points and {status}.</p>"),
+        vec![
+            ("dying", &normal_label, Some(dying_button(&actor, normal))),
+            (
+                "critical",
+                &critical_label,
+                Some(dying_button(&actor, critical)),
+            ),
+            ("skip", "Skip", None),
+        ],
+    )
+    .await
+}
+
+/// Offer the recovery check when a dying character's turn starts
+async fn prompt_recovery(combat: Combat) -> Result<(), Error> {
+    let Some(actor) = combat.combatant().and_then(|combatant| combatant.actor()) else {
+        return Ok(());
+    };
+    if !should_prompt(&actor) {
+        return Ok(());
+    }
+    let Some(dying) = actor.condition_value("dying") else {
+        return Ok(());
+    };
+
+    let name = actor.name();
+    let actor_js = actor.as_js_value().clone();
+    let roll_fn = Closure::wrap(Box::new(move || {
+        let actor = Actor::from(actor_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = actor.roll_recovery().await {
+                cprintln!("Error rolling recovery check: {err}");
+                UI::notify_error(&format!("Unable to roll recovery check: {err}"));
+            }
+        });
+    }) as Box<dyn Fn()>);
+
+    application::show_dialog(
+        "Recovery Check",
+        format!("<p>{name} starts their turn Dying {dying}. Roll a recovery check?</p>"),
+        vec![
+            (
+                "roll",
+                "Roll Recovery Check",
+                Some(roll_fn.into_js_value().unchecked_into()),
+            ),
+            ("skip", "Skip", None),
+        ],
+    )
+    .await
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Dying Prompts")
+            .hint("When a character you play drops to 0 HP, offer to apply Dying at the right value for its Wounded condition or a critical hit, and offer the recovery check at the start of its turn while dying.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "dyingPrompts");
+    });
+
+    hook!("updateActor", async |actor: JsValue, changes: JsValue| {
+        if let Err(err) = prompt_dying(actor.into(), changes).await {
+            cprintln!("Error prompting for dying: {err}");
+        }
+    });
+
+    hook!(
+        "combatTurnChange",
+        async |combat: JsValue, _prior: JsValue| {
+            if let Err(err) = prompt_recovery(combat.into()).await {
+                cprintln!("Error prompting for recovery check: {err}");
+            }
+        }
+    );
+}
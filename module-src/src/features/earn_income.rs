@@ -0,0 +1,172 @@
+use crate::features::{format_coins, level_dc, register_api, selected_actor};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::hook;
+use wasm_bindgen::prelude::*;
+
+/// Income per day in copper by task level, for a failure and then a success at trained, expert,
+/// master, and legendary; the last row is a critical success on a level 20 task
+const INCOME: [[f64; 5]; 22] = [
+    [1.0, 5.0, 5.0, 5.0, 5.0],
+    [2.0, 20.0, 20.0, 20.0, 20.0],
+    [4.0, 30.0, 30.0, 30.0, 30.0],
+    [8.0, 50.0, 50.0, 50.0, 50.0],
+    [10.0, 70.0, 80.0, 80.0, 80.0],
+    [20.0, 90.0, 100.0, 100.0, 100.0],
+    [30.0, 150.0, 200.0, 200.0, 200.0],
+    [40.0, 200.0, 250.0, 250.0, 250.0],
+    [50.0, 250.0, 300.0, 300.0, 300.0],
+    [60.0, 300.0, 400.0, 400.0, 400.0],
+    [70.0, 400.0, 500.0, 600.0, 600.0],
+    [80.0, 500.0, 600.0, 800.0, 800.0],
+    [90.0, 600.0, 800.0, 1000.0, 1000.0],
+    [100.0, 700.0, 1000.0, 1500.0, 1500.0],
+    [150.0, 800.0, 1500.0, 2000.0, 2000.0],
+    [200.0, 1000.0, 2000.0, 2800.0, 2800.0],
+    [250.0, 1300.0, 2500.0, 3600.0, 4000.0],
+    [300.0, 1500.0, 3000.0, 4500.0, 5500.0],
+    [400.0, 2000.0, 4500.0, 7000.0, 9000.0],
+    [600.0, 3000.0, 6000.0, 10000.0, 13000.0],
+    [800.0, 4000.0, 7500.0, 15000.0, 20000.0],
+    [0.0, 5000.0, 9000.0, 17500.0, 30000.0],
+];
+
+const PROFICIENCIES: [&str; 5] = ["Untrained", "Trained", "Expert", "Master", "Legendary"];
+
+/// Income per day for the task level, proficiency rank, and degree of success
+fn daily_income(level: usize, rank: u8, degree: DegreeOfSuccess) -> f64 {
+    let rank = usize::from(rank.clamp(1, 4));
+    match degree {
+        // a critical success earns as if the task were a level higher
+        DegreeOfSuccess::CriticalSuccess => INCOME[level + 1][rank],
+        DegreeOfSuccess::Success => INCOME[level][rank],
+        DegreeOfSuccess::Failure => INCOME[level][0],
+        DegreeOfSuccess::CriticalFailure => 0.0,
+    }
+}
+
+/// Roll the skill against the task's DC and post what the character earned
+async fn earn(actor: Actor, form: HtmlElement) -> Result<(), Error> {
+    let value = |name: &str| -> Result<Option<String>, Error> {
+        Ok(form
+            .query_selector(&format!("[name={name}]"))?
+            .and_then(|input| input.value()))
+    };
+    let level = value("level")?
+        .and_then(|level| level.trim().parse::<f64>().ok())
+        .ctx("Enter the task level")?
+        .clamp(0.0, 20.0);
+    let days = value("days")?
+        .and_then(|days| days.trim().parse::<f64>().ok())
+        .filter(|days| *days >= 1.0)
+        .ctx("Enter how many days were worked")?
+        .floor();
+    let skill = value("skill")?.ctx("Choose a skill")?;
+    let add_coins = form
+        .query_selector("[name=addCoins]")?
+        .is_some_and(|checkbox| checkbox.is_checked());
+    let (_, label, rank) = actor
+        .skills()
+        .into_iter()
+        .find(|(slug, _, _)| *slug == skill)
+        .ctx("Skill not found")?;
+
+    let dc = level_dc(level);
+    let Some(check) = actor.roll_skill(&skill, dc, None).await? else {
+        return Ok(());
+    };
+    let degree = check
+        .degree_of_success()
+        .ctx("Earn Income degree of success")?;
+    let per_day = daily_income(level as usize, rank, degree);
+    let total = per_day * days;
+
+    let mut content = format!(
+        r#"<p><strong>Earn Income</strong></p>
+        <p>{name} worked a level {level} task with {label} ({proficiency}) against DC {dc}: <span class="johnys-degree-badge {slug}">{degree_label}</span>.</p>
+        <p>Earned {per_day} a day, {total} over {days} days.</p>"#,
+        name = actor.name(),
+        proficiency = PROFICIENCIES[usize::from(rank.min(4))],
+        slug = degree.slug(),
+        degree_label = degree.label(),
+        per_day = format_coins(per_day),
+        total = format_coins(total),
+    );
+    if add_coins && total > 0.0 {
+        actor.add_coins(total).await?;
+        content.push_str("<p>The coins were added to their inventory.</p>");
+    }
+    Message::create(&format!(
+        r#"<div class="johnys-earn-income">{content}</div>"#
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Ask for the task and skill for the selected character's Earn Income
+async fn open() -> Result<(), Error> {
+    let actor = selected_actor().ctx("Select a character to earn income")?;
+    let options: String = actor
+        .skills()
+        .into_iter()
+        .filter(|(_, _, rank)| *rank >= 1)
+        .map(|(slug, label, rank)| {
+            format!(
+                r#"<option value="{slug}">{label} ({})</option>"#,
+                PROFICIENCIES[usize::from(rank.min(4))]
+            )
+        })
+        .collect();
+    if options.is_empty() {
+        return Err(format!("{} isn't trained in any skills", actor.name()).into());
+    }
+    let default_level = (actor.level().unwrap_or_default() - 2.0).max(0.0);
+
+    let actor_js = actor.as_js_value().clone();
+    let earn_fn = Closure::wrap(Box::new(move |html: JsValue| {
+        let form = HtmlElement::from(html);
+        let actor = Actor::from(actor_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = earn(actor, form).await {
+                cprintln!("Error earning income: {err}");
+                UI::notify_error(&err.to_string());
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+
+    application::show_dialog(
+        "Earn Income",
+        format!(
+            r#"<p>{name} spends downtime earning income.</p>
+            <div class="form-group"><label>Task level</label><input type="number" name="level" min="0" max="20" value="{default_level}"></div>
+            <div class="form-group"><label>Skill</label><select name="skill">{options}</select></div>
+            <div class="form-group"><label>Days worked</label><input type="number" name="days" min="1" value="1"></div>
+            <div class="form-group"><label>Add coins to inventory</label><input type="checkbox" name="addCoins" checked></div>"#,
+            name = actor.name(),
+        ),
+        vec![
+            (
+                "earn",
+                "Earn Income",
+                Some(earn_fn.into_js_value().unchecked_into()),
+            ),
+            ("cancel", "Cancel", None),
+        ],
+    )
+    .await
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.earnIncome()
+        let earn_income_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening earn income: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("earnIncome", &earn_income_fn.into_js_value());
+    });
+}
@@ -1,30 +1,375 @@
-use crate::features::is_enabled;
+use crate::features::{is_enabled, is_responsible_gm, register_api, setting_number};
 use crate::foundry::error::{ContextExt as _, Error};
 use crate::foundry::{application, cprintln, *};
-use crate::{hook, ID};
-use serde::Serialize;
+use crate::{get_path, hook, ID};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
 
 use wasm_bindgen::prelude::*;
 
+mod grip;
+mod perception_gate;
+
+use grip::Grip;
+
 #[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 struct EquipmentItemData {
     name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     img: Option<String>,
+    /// Set when the viewer may click through to the item's details
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uuid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rarity: Option<String>,
+    /// Held in one hand, but can't be used without a second
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    needs_two_hands: bool,
+    /// Set when the viewer may drag the item onto another sheet
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    draggable: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    runes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    contents: Vec<EquipmentItemData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ammo: Option<AmmoData>,
+}
+
+/// Ammunition loaded for a ranged weapon
+#[derive(Serialize, Clone)]
+struct AmmoData {
+    name: String,
+    quantity: f64,
+    /// Running low enough to be worth a warning
+    low: bool,
+}
+
+impl From<&Item> for AmmoData {
+    fn from(ammo: &Item) -> Self {
+        const LOW_AMMO: f64 = 5.0;
+
+        let quantity = ammo.quantity();
+        Self {
+            name: EquipmentItemData::from(ammo).name,
+            quantity,
+            low: quantity <= LOW_AMMO,
+        }
+    }
 }
 
 impl From<&Item> for EquipmentItemData {
     fn from(item: &Item) -> Self {
+        // players only get to see what an unidentified item looks like
+        if !item.is_identified() && !viewer_is_gm() {
+            return Self {
+                name: item
+                    .unidentified_name()
+                    .unwrap_or_else(|| localize("JOHNYS.Equipment.UnidentifiedItem")),
+                img: item.unidentified_img().or_else(|| item.img()),
+                uuid: None,
+                rarity: None,
+                needs_two_hands: false,
+                draggable: false,
+                runes: Vec::new(),
+                contents: Vec::new(),
+                ammo: None,
+            };
+        }
         Self {
             name: item.name(),
             img: item.img(),
+            uuid: item.uuid(),
+            rarity: item.rarity(),
+            needs_two_hands: false,
+            // only the GM can hand out other creatures' gear
+            draggable: viewer_is_gm(),
+            runes: rune_badges(&item.runes()),
+            contents: Vec::new(),
+            ammo: item.ammo().as_ref().map(Into::into),
+        }
+    }
+}
+
+/// How much of an inspected creature's gear the viewer can make out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum DetailLevel {
+    Icons,
+    Names,
+    Runes,
+}
+
+impl DetailLevel {
+    /// What the GM lets players see of creatures they don't own, or None when it takes a Perception check
+    fn fixed(actor: &Actor) -> Option<Self> {
+        if viewer_is_gm() || actor.is_owner() {
+            return Some(DetailLevel::Runes);
+        }
+        match get_setting(ID, "visibleEquipmentDetail")
+            .as_string()
+            .as_deref()
+        {
+            Some("icons") => Some(DetailLevel::Icons),
+            Some("names") => Some(DetailLevel::Names),
+            Some("perception") => None,
+            _ => Some(DetailLevel::Runes),
+        }
+    }
+
+    /// Find what the viewer can see, having the GM secretly roll their Perception if needed
+    async fn for_actor(actor: &Actor) -> Result<Self, Error> {
+        if let Some(detail) = Self::fixed(actor) {
+            return Ok(detail);
+        }
+        let game = Game::instance()?;
+        let viewer = game.user()?.character().or_else(|| {
+            game.controlled_tokens()
+                .into_iter()
+                .find_map(|token| token.actor())
+        });
+        let Some(viewer) = viewer else {
+            return Ok(DetailLevel::Icons);
+        };
+        perception_gate::roll(&viewer).await
+    }
+
+    /// What a Perception check total makes out against the GM's DCs
+    fn from_perception(total: f64) -> Self {
+        if total >= setting_number("visibleEquipmentRuneDC") {
+            DetailLevel::Runes
+        } else if total >= setting_number("visibleEquipmentNameDC") {
+            DetailLevel::Names
+        } else {
+            DetailLevel::Icons
+        }
+    }
+}
+
+impl EquipmentItemData {
+    /// Strip whatever the viewer can't make out at `detail`
+    fn limit(&mut self, detail: DetailLevel) {
+        if detail < DetailLevel::Runes {
+            self.runes.clear();
+        }
+        if detail < DetailLevel::Names {
+            self.name.clear();
+            self.uuid = None;
+            self.rarity = None;
+            self.draggable = false;
+            self.ammo = None;
+        }
+        for content in &mut self.contents {
+            content.limit(detail);
+        }
+    }
+}
+
+fn viewer_is_gm() -> bool {
+    Game::instance().is_ok_and(|game| game.user().is_ok_and(|user| user.is_gm()))
+}
+
+/// Check if the GM hides magic items like this one from players
+fn hidden_magic(item: &Item) -> bool {
+    is_enabled("visibleEquipmentHideMagic")
+        && item.is_magical()
+        && (item.is_invested() || item.carry_type().as_deref() == Some("worn"))
+}
+
+/// Check if the GM has hidden this item from players' equipment views
+fn hidden_from_players(item: &Item) -> bool {
+    hidden_magic(item) && !viewer_is_gm()
+}
+
+/// Check if players can make out the names of a creature's gear without a Perception check
+fn players_see_names(actor: &Actor) -> bool {
+    actor.has_player_owner()
+        || !matches!(
+            get_setting(ID, "visibleEquipmentDetail")
+                .as_string()
+                .as_deref(),
+            Some("icons" | "perception")
+        )
+}
+
+/// Short labels for each rune, e.g. "+1", "Greater Striking", "Flaming"
+fn rune_badges(runes: &ItemRunes) -> Vec<String> {
+    fn graded(grade: u8, name: &str) -> Option<String> {
+        match grade {
+            1 => Some(name.to_string()),
+            2 => Some(format!("Greater {name}")),
+            3 => Some(format!("Major {name}")),
+            _ => None,
+        }
+    }
+
+    let mut badges = Vec::new();
+    if runes.potency > 0 {
+        badges.push(format!("+{}", runes.potency));
+    }
+    badges.extend(graded(runes.striking, "Striking"));
+    badges.extend(graded(runes.resilient, "Resilient"));
+    badges.extend(runes.property.iter().map(|slug| {
+        slug.split('-')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }));
+    badges
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ShieldData {
+    name: String,
+    hp: f64,
+    max_hp: f64,
+    hardness: f64,
+    broken: bool,
+    destroyed: bool,
+}
+
+impl ShieldData {
+    fn from_item(item: &Item) -> Option<Self> {
+        let status = item.shield_status()?;
+        Some(Self {
+            name: EquipmentItemData::from(item).name,
+            hp: status.hp,
+            max_hp: status.max_hp,
+            hardness: status.hardness,
+            broken: status.broken,
+            destroyed: status.destroyed,
+        })
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BulkData {
+    carried: String,
+    encumbered_after: f64,
+    max: f64,
+    encumbered: bool,
+    over_max: bool,
+}
+
+impl BulkData {
+    /// Summarize `actor`'s Bulk, if the viewer owns them or the GM allows seeing everyone's
+    fn for_actor(actor: &Actor) -> Option<Self> {
+        if !actor.is_owner() && !is_enabled("visibleEquipmentBulkOthers") {
+            return None;
+        }
+        let bulk = actor.inventory_bulk()?;
+        let carried = match (bulk.normal, bulk.light) {
+            (normal, light) if light > 0.0 && normal > 0.0 => format!("{normal}, {light}L"),
+            (_, light) if light > 0.0 => format!("{light}L"),
+            (normal, _) => format!("{normal}"),
+        };
+        Some(Self {
+            carried,
+            encumbered_after: bulk.encumbered_after,
+            max: bulk.max,
+            encumbered: bulk.encumbered,
+            over_max: bulk.over_max,
+        })
+    }
+}
+
+/// Who the equipment belongs to, shown above it
+#[derive(Serialize, Clone)]
+struct HeaderData {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    img: Option<String>,
+    /// As text, so level 0 and -1 creatures still show theirs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ac: Option<f64>,
+}
+
+impl HeaderData {
+    /// Describe `actor` as far as the GM lets players see; the GM and owners always see everything
+    fn for_actor(actor: &Actor) -> Option<Self> {
+        let shown = if viewer_is_gm() || actor.is_owner() {
+            Some("full".to_string())
+        } else {
+            get_setting(ID, "visibleEquipmentHeader").as_string()
+        };
+        let stats = match shown.as_deref() {
+            Some("full") => true,
+            Some("portrait") => false,
+            _ => return None,
+        };
+        Some(Self {
+            name: actor.name(),
+            img: actor.img(),
+            level: actor
+                .level()
+                .filter(|_| stats)
+                .map(|level| level.to_string()),
+            ac: actor.armor_class().filter(|_| stats),
+        })
+    }
+}
+
+/// Stowed items in one container, or loose in the inventory when there's no container
+#[derive(Serialize, Clone)]
+struct StowedGroup {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    img: Option<String>,
+    items: Vec<EquipmentItemData>,
+}
+
+impl StowedGroup {
+    /// Group the actor's stowed items by the container they're in, in inventory order
+    fn for_items(items: &[Item]) -> Vec<Self> {
+        let mut groups: Vec<(Option<String>, StowedGroup)> = Vec::new();
+        for item in items.iter().filter(|item| {
+            item.is_physical_item() && item.carry_type().as_deref() == Some("stowed")
+        }) {
+            let container_id = item.container_id();
+            let index = match groups.iter().position(|(id, _)| *id == container_id) {
+                Some(index) => index,
+                None => {
+                    let container = container_id
+                        .as_ref()
+                        .and_then(|id| items.iter().find(|item| item.id().as_ref() == Some(id)));
+                    groups.push((
+                        container_id,
+                        StowedGroup {
+                            name: container
+                                .map_or_else(|| localize("JOHNYS.Equipment.Stowed"), Item::name),
+                            img: container.and_then(Item::img),
+                            items: Vec::new(),
+                        },
+                    ));
+                    groups.len() - 1
+                }
+            };
+            groups[index].1.items.push(item.into());
         }
+        groups.into_iter().map(|(_, group)| group).collect()
     }
 }
 
 #[derive(Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 struct EquipmentContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    header: Option<HeaderData>,
     #[serde(skip_serializing_if = "Option::is_none")]
     left_hand: Option<EquipmentItemData>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -39,17 +384,58 @@ struct EquipmentContext {
     extra_held_items: Vec<EquipmentItemData>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     worn_items: Vec<EquipmentItemData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shield: Option<ShieldData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bulk: Option<BulkData>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stowed: Vec<StowedGroup>,
+}
+
+impl EquipmentContext {
+    /// Build the equipment context for everything `actor` is wearing and holding
+    fn for_actor(actor: &Actor, detail: DetailLevel) -> Self {
+        let items = actor.items();
+        let mut context: EquipmentContext = items.as_slice().into();
+        context.header = HeaderData::for_actor(actor);
+        context.bulk = BulkData::for_actor(actor);
+        // only owners get the full inventory glance
+        if actor.is_owner() && is_enabled("visibleEquipmentStowed") {
+            context.stowed = StowedGroup::for_items(&items);
+        }
+        context.limit(detail);
+        context
+    }
+
+    fn limit(&mut self, detail: DetailLevel) {
+        let items = [&mut self.left_hand, &mut self.right_hand, &mut self.armor]
+            .into_iter()
+            .flatten()
+            .chain(&mut self.extra_held_items)
+            .chain(&mut self.worn_items)
+            .chain(self.stowed.iter_mut().flat_map(|group| &mut group.items));
+        for item in items {
+            item.limit(detail);
+        }
+        if detail < DetailLevel::Names {
+            if let Some(shield) = &mut self.shield {
+                shield.name.clear();
+            }
+        }
+    }
 }
 
 impl From<&[Item]> for EquipmentContext {
     fn from(items: &[Item]) -> Self {
         let mut context = EquipmentContext::default();
+        let mut held: Vec<(&Item, Grip)> = Vec::new();
         for item in items.iter().filter(|item| {
             item.is_physical_item()
                 && item
                     .carry_type()
                     .as_ref()
-                    .map_or(false, |ct| ct == "worn" || ct == "held")
+                    .is_some_and(|ct| ct == "worn" || ct == "held")
+                && !hidden_from_players(item)
         }) {
             let item_type = item.item_type().unwrap_or_default();
             let carry_type = item.carry_type().unwrap_or_default();
@@ -59,28 +445,29 @@ impl From<&[Item]> for EquipmentContext {
                     context.armor = Some(item.into());
                 }
                 ("weapon" | "shield", "held") => {
-                    if item.traits().iter().any(|t| t == "free-hand") {
-                        context.extra_held_items.push(item.into());
-                    } else if item.is_two_handed() {
-                        if let Some(item) = context.left_hand.take() {
-                            context.extra_held_items.push(item)
-                        }
-                        if let Some(item) = context.right_hand.take() {
-                            context.extra_held_items.push(item)
-                        }
-                        let item_data: EquipmentItemData = item.into();
-                        context.left_hand = Some(item_data.clone());
-                        context.right_hand = Some(item_data);
-                        context.right_hand_secondary = true;
-                    } else {
-                        if context.left_hand.is_none() {
-                            context.left_hand = Some(item.into());
-                        } else if context.right_hand.is_none() {
-                            context.right_hand = Some(item.into());
-                        } else {
-                            context.extra_held_items.push(item.into());
-                        }
+                    if context.shield.is_none() {
+                        context.shield = ShieldData::from_item(item);
                     }
+                    let grip = Grip::new(
+                        item.hands_required(),
+                        item.hands_held().clamp(0.0, 2.0) as u8,
+                        item.traits().iter().any(|t| t == "free-hand"),
+                    );
+                    held.push((item, grip));
+                }
+                ("backpack", "worn") if is_enabled("visibleEquipmentContainers") => {
+                    let mut container: EquipmentItemData = item.into();
+                    let container_id = item.id();
+                    container.contents = items
+                        .iter()
+                        .filter(|content| {
+                            container_id.is_some()
+                                && content.container_id() == container_id
+                                && !hidden_from_players(content)
+                        })
+                        .map(Into::into)
+                        .collect();
+                    context.worn_items.push(container);
                 }
                 (_, "worn") => {
                     context.worn_items.push(item.into());
@@ -89,17 +476,60 @@ impl From<&[Item]> for EquipmentContext {
             }
         }
 
+        let item_data = |index: usize| {
+            let (item, grip) = held[index];
+            let mut data = EquipmentItemData::from(item);
+            data.needs_two_hands = grip == Grip::OneHand { needs_two: true };
+            data
+        };
+        let hands = grip::layout(&held.iter().map(|(_, grip)| *grip).collect::<Vec<_>>());
+        context.left_hand = hands.left.map(item_data);
+        context.right_hand = hands.right.map(item_data);
+        context.right_hand_secondary = hands.both_hands;
+        context.extra_held_items = hands.extra.into_iter().map(item_data).collect();
+
         context
     }
 }
 
+/// Where to look for the tokens whose equipment to show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenSource {
+    Hovered,
+    Targeted,
+    Controlled,
+}
+
+impl TokenSource {
+    /// The order to try each source in, from the player's setting
+    fn priority() -> [Self; 3] {
+        use TokenSource::*;
+        match get_setting(ID, "visibleEquipmentTokenPriority")
+            .as_string()
+            .as_deref()
+        {
+            Some("hovered-controlled-targeted") => [Hovered, Controlled, Targeted],
+            Some("controlled-hovered-targeted") => [Controlled, Hovered, Targeted],
+            _ => [Hovered, Targeted, Controlled],
+        }
+    }
+
+    fn tokens(self, game: &Game) -> Vec<Token> {
+        match self {
+            TokenSource::Hovered => game.hovered_token().into_iter().collect(),
+            TokenSource::Targeted => game.user_targets(),
+            TokenSource::Controlled => game.controlled_tokens(),
+        }
+    }
+}
+
 /// Open the equipment screen for the selected actor
 /// Can be called from macros with: game.modules.get("johnys-module").api.openEquipmentScreen()
 #[wasm_bindgen]
 pub async fn open_equipment_screen() {
     if let Err(error_msg) = try_open_equipment_screen()
         .await
-        .ctx("Unable to view equipment")
+        .ctx(&localize("JOHNYS.Equipment.Errors.Unable"))
     {
         cprintln!("Error opening equipment screen: {}", error_msg);
         UI::notify_error(&error_msg.to_string());
@@ -108,82 +538,698 @@ pub async fn open_equipment_screen() {
 
 async fn try_open_equipment_screen() -> Result<(), Error> {
     if !is_enabled("visibleEquipmentEnabled") {
-        return Err("Equipment observation feature must be enabled by GM".into());
+        return Err(localize("JOHNYS.Equipment.Errors.Disabled").into());
     }
     let game = Game::instance()?;
-    let hovered = game.hovered_token();
-    let targeted_tokens = game.user_targets();
+    let tokens = TokenSource::priority()
+        .into_iter()
+        .map(|source| source.tokens(&game))
+        .find(|tokens| !tokens.is_empty())
+        .ok_or_else(|| localize("JOHNYS.Equipment.Errors.NoToken"))?;
+
+    show_equipment_window(tokens.iter().filter_map(Token::actor).collect()).await
+}
+
+/// Open the equipment screen for an actor or token UUID
+/// Can be called from macros with: game.modules.get("johnys-module").api.openEquipmentScreenFor(uuid)
+async fn open_equipment_screen_for(uuid: String) -> Result<(), Error> {
+    if !is_enabled("visibleEquipmentEnabled") {
+        return Err(localize("JOHNYS.Equipment.Errors.Disabled").into());
+    }
+    let actor = Game::actor_from_uuid(&uuid).await?;
+    show_equipment_window(vec![actor]).await
+}
+
+thread_local! {
+    /// Rendered equipment HTML by actor UUID and detail level, with the items revision it was built from
+    static RENDER_CACHE: RefCell<HashMap<(String, DetailLevel), (u64, String)>> =
+        RefCell::new(HashMap::new());
+}
 
-    let selected_token = hovered.as_ref().or_else(|| targeted_tokens.first());
+/// Hash the actor's item IDs and modification times, so any change to its items changes the hash
+fn items_revision(items: &[Item]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for item in items {
+        item.id().hash(&mut hasher);
+        item.modified_time().map(f64::to_bits).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Forget everything rendered for an actor
+fn invalidate_render_cache(actor_uuid: &str) {
+    RENDER_CACHE.with_borrow_mut(|cache| cache.retain(|(uuid, _), _| uuid != actor_uuid));
+}
 
-    let all_items: EquipmentContext = selected_token
-        .ctx("Please select or target a token")?
-        .actor_items()
-        .as_slice()
-        .into();
+/// Render the equipment template for an actor's current items, reusing the last render if nothing changed
+async fn render_actor_equipment(actor: &Actor, detail: DetailLevel) -> Result<String, Error> {
+    let items = actor.items();
+    let revision = items_revision(&items);
+    let key = actor.uuid().map(|uuid| (uuid, detail));
+    if let Some(key) = &key {
+        let cached = RENDER_CACHE.with_borrow(|cache| {
+            cache
+                .get(key)
+                .filter(|(cached_revision, _)| *cached_revision == revision)
+                .map(|(_, html)| html.clone())
+        });
+        if let Some(html) = cached {
+            return Ok(html);
+        }
+    }
 
     let html = application::render_template(
         "modules/johnys-module/templates/equipment-screen.hbs",
-        &all_items,
+        &EquipmentContext::for_actor(actor, detail),
+    )
+    .await?;
+    if let Some(key) = key {
+        RENDER_CACHE.with_borrow_mut(|cache| cache.insert(key, (revision, html.clone())));
+    }
+    Ok(html)
+}
+
+/// Render each actor's equipment, with a tab per actor when there's more than one
+async fn render_equipment_tabs(
+    actors: &[(Actor, DetailLevel)],
+    active: Rc<Cell<usize>>,
+) -> Result<JsValue, Error> {
+    if let [(actor, detail)] = actors {
+        return Ok(JsValue::from(render_actor_equipment(actor, *detail).await?));
+    }
+
+    let mut nav = String::new();
+    let mut panes = String::new();
+    for (index, (actor, detail)) in actors.iter().enumerate() {
+        let class = if index == active.get() { "active" } else { "" };
+        nav.push_str(&format!(
+            r#"<a class="item {class}" data-equipment-tab="{index}">{}</a>"#,
+            actor.name()
+        ));
+        panes.push_str(&format!(
+            r#"<div class="equipment-tab {class}" data-equipment-tab="{index}">{}</div>"#,
+            render_actor_equipment(actor, *detail).await?
+        ));
+    }
+
+    let container = Document::create_element("div")?;
+    container.set_class_name("equipment-tabs")?;
+    container.set_inner_html(&format!(r#"<nav class="tabs">{nav}</nav>{panes}"#))?;
+
+    let container_js = container.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |event: JsValue| {
+        let Some(index) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("a[data-equipment-tab]"))
+            .and_then(|tab| tab.get_attribute("data-equipment-tab"))
+        else {
+            return;
+        };
+        active.set(index.parse().unwrap_or_default());
+        let container = HtmlElement::from(container_js.clone());
+        for element in container.query_selector_all("[data-equipment-tab]") {
+            let selected = element.get_attribute("data-equipment-tab").as_ref() == Some(&index);
+            element.toggle_class("active", selected).ok();
+        }
+    }) as Box<dyn Fn(JsValue)>);
+    container.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+
+    Ok(container.as_js_value().clone())
+}
+
+/// What an open equipment window is showing
+#[derive(Default)]
+struct EquipmentWindowState {
+    /// Each shown actor with the detail level the viewer rolled for it
+    actors: RefCell<Vec<(JsValue, DetailLevel)>>,
+    /// Every roll made in this window, so hovering back to a creature doesn't reroll
+    rolled: RefCell<HashMap<String, DetailLevel>>,
+    /// Pinned windows stay on their creatures instead of following the hovered token
+    pinned: Cell<bool>,
+    active_tab: Rc<Cell<usize>>,
+}
+
+impl EquipmentWindowState {
+    fn actor_uuids(&self) -> Vec<String> {
+        self.actors
+            .borrow()
+            .iter()
+            .filter_map(|(actor, _)| Actor::from(actor.clone()).uuid())
+            .collect()
+    }
+
+    /// Show these actors instead, rolling for any this window hasn't seen before
+    async fn set_actors(&self, actors: Vec<Actor>) -> Result<(), Error> {
+        let mut shown = Vec::new();
+        for actor in actors {
+            let uuid = actor.uuid().unwrap_or_default();
+            let rolled = self.rolled.borrow().get(&uuid).copied();
+            let detail = match rolled {
+                Some(detail) => detail,
+                None => DetailLevel::for_actor(&actor).await?,
+            };
+            self.rolled.borrow_mut().insert(uuid, detail);
+            shown.push((actor.as_js_value().clone(), detail));
+        }
+        *self.actors.borrow_mut() = shown;
+        self.active_tab.set(0);
+        Ok(())
+    }
+}
+
+thread_local! {
+    /// Every open equipment window, so several can be open at once
+    static EQUIPMENT_WINDOWS: RefCell<Vec<(String, Rc<EquipmentWindowState>)>> =
+        const { RefCell::new(Vec::new()) };
+    static NEXT_WINDOW: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Open a window showing the actors' equipment, re-rendering it whenever their items change
+async fn show_equipment_window(actors: Vec<Actor>) -> Result<(), Error> {
+    const ITEM_HOOKS: [&str; 3] = ["createItem", "updateItem", "deleteItem"];
+
+    let actor_uuids: Vec<String> = actors.iter().filter_map(Actor::uuid).collect();
+    if actor_uuids.is_empty() {
+        return Err(localize("JOHNYS.Equipment.Errors.NoActors").into());
+    }
+    let existing = EQUIPMENT_WINDOWS.with_borrow(|windows| {
+        windows
+            .iter()
+            .find(|(_, state)| state.actor_uuids() == actor_uuids)
+            .map(|(id, _)| id.clone())
+    });
+    if let Some(existing) = existing.as_deref().and_then(Application::get) {
+        return existing.render().await;
+    }
+
+    // roll once per opening, so reopening is the only way to try again
+    let state = Rc::new(EquipmentWindowState::default());
+    // a window of several targets is about those targets, not whatever is hovered
+    state.pinned.set(actors.len() > 1);
+    state.set_actors(actors).await?;
+    let window_id = format!(
+        "johnys-equipment-{}",
+        NEXT_WINDOW.with(|next| next.replace(next.get() + 1))
+    );
+    let hook_ids: Rc<RefCell<Vec<(&str, i32)>>> = Rc::default();
+
+    let window = application::WindowBuilder::new(&window_id, &localize("JOHNYS.Equipment.Title"))
+        .icon("fa-solid fa-shield")
+        .classes(&["johnys-equipment-window"])
+        .position(&ApplicationPosition {
+            width: Some(320.0),
+            ..Default::default()
+        })
+        .render({
+            let state = state.clone();
+            move || {
+                let actors: Vec<(Actor, DetailLevel)> = state
+                    .actors
+                    .borrow()
+                    .iter()
+                    .map(|(actor, detail)| (actor.clone().into(), *detail))
+                    .collect();
+                let active_tab = state.active_tab.clone();
+                async move { render_equipment_tabs(&actors, active_tab).await }
+            }
+        })
+        .on_render({
+            let state = state.clone();
+            move |element| {
+                element.toggle_class("pinned", state.pinned.get()).ok();
+            }
+        })
+        .control(
+            "refresh",
+            &localize("JOHNYS.Equipment.Refresh"),
+            "fa-solid fa-rotate",
+            {
+                let state = state.clone();
+                let window_id = window_id.clone();
+                move || {
+                    for uuid in state.actor_uuids() {
+                        invalidate_render_cache(&uuid);
+                    }
+                    rerender_window(&window_id);
+                }
+            },
+        )
+        .control(
+            "pin",
+            &localize("JOHNYS.Equipment.PinToToken"),
+            "fa-solid fa-thumbtack",
+            {
+                let state = state.clone();
+                let window_id = window_id.clone();
+                move || {
+                    let pinned = !state.pinned.get();
+                    state.pinned.set(pinned);
+                    UI::notify_info(&localize(if pinned {
+                        "JOHNYS.Equipment.Pinned"
+                    } else {
+                        "JOHNYS.Equipment.Unpinned"
+                    }));
+                    rerender_window(&window_id);
+                }
+            },
+        )
+        .on_close({
+            let hook_ids = hook_ids.clone();
+            let window_id = window_id.clone();
+            move || {
+                for (hook, id) in hook_ids.borrow_mut().drain(..) {
+                    hooks_off(hook, id);
+                }
+                EQUIPMENT_WINDOWS
+                    .with_borrow_mut(|windows| windows.retain(|(id, _)| *id != window_id));
+            }
+        })
+        .build()?;
+    EQUIPMENT_WINDOWS.with_borrow_mut(|windows| windows.push((window_id.clone(), state.clone())));
+    window.render().await?;
+
+    for hook in ITEM_HOOKS {
+        let state = state.clone();
+        let window_id = window_id.clone();
+        let id = hook!(hook, |item: JsValue| {
+            let item: Item = item.into();
+            if item
+                .actor_uuid()
+                .is_some_and(|uuid| state.actor_uuids().contains(&uuid))
+            {
+                rerender_window(&window_id);
+            }
+        });
+        hook_ids.borrow_mut().push((hook, id));
+    }
+
+    Ok(())
+}
+
+/// Re-render an open window by ID
+fn rerender_window(window_id: &str) {
+    let Some(window) = Application::get(window_id) else {
+        return;
+    };
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = window.render().await {
+            cprintln!("Error refreshing equipment screen: {err}");
+        }
+    });
+}
+
+/// Switch every unpinned equipment window to the newly hovered token
+async fn follow_hovered_token(token: Token) -> Result<(), Error> {
+    let Some(uuid) = token.actor().and_then(|actor| actor.uuid()) else {
+        return Ok(());
+    };
+    let following: Vec<(String, Rc<EquipmentWindowState>)> =
+        EQUIPMENT_WINDOWS.with_borrow(|windows| {
+            windows
+                .iter()
+                .filter(|(_, state)| !state.pinned.get() && state.actor_uuids() != [uuid.clone()])
+                .cloned()
+                .collect()
+        });
+    for (window_id, state) in following {
+        state
+            .set_actors(token.actor().into_iter().collect())
+            .await?;
+        rerender_window(&window_id);
+    }
+    Ok(())
+}
+
+/// Show two actors' equipment side by side
+/// Can be called from macros with: game.modules.get("johnys-module").api.compareEquipment(uuidA, uuidB)
+async fn compare_equipment(uuid_a: String, uuid_b: String) -> Result<(), Error> {
+    if !is_enabled("visibleEquipmentEnabled") {
+        return Err(localize("JOHNYS.Equipment.Errors.Disabled").into());
+    }
+    let mut sides = String::new();
+    for uuid in [uuid_a, uuid_b] {
+        let actor = Game::actor_from_uuid(&uuid).await?;
+        let detail = DetailLevel::for_actor(&actor).await?;
+        let html = render_actor_equipment(&actor, detail).await?;
+        sides.push_str(&format!(
+            r#"<section class="equipment-compare-side"><h3>{}</h3>{html}</section>"#,
+            actor.name()
+        ));
+    }
+    let content = format!(r#"<div class="equipment-compare">{sides}</div>"#);
+    if let Some(existing) = Application::get("johnys-equipment-compare") {
+        existing.close().await?;
+    }
+
+    application::WindowBuilder::new(
+        "johnys-equipment-compare",
+        &localize("JOHNYS.Equipment.CompareTitle"),
     )
+    .icon("fa-solid fa-scale-balanced")
+    .classes(&["johnys-equipment-window"])
+    .position(&ApplicationPosition {
+        width: Some(640.0),
+        ..Default::default()
+    })
+    .render(move || {
+        let content = content.clone();
+        async move { Ok(JsValue::from(content)) }
+    })
+    .build()?
+    .render()
+    .await
+}
+
+/// Open a small window with an item's traits and, if the viewer may see it, its description
+async fn show_item_details(uuid: &str) -> Result<(), Error> {
+    let item: Item = from_uuid_raw(uuid).await?.into();
+    let traits: String = item
+        .traits()
+        .iter()
+        .map(|t| format!(r#"<span class="tag">{t}</span>"#))
+        .collect();
+    let description = if viewer_is_gm() || item.is_observable() {
+        application::enrich_html(&item.description()).await?
+    } else {
+        format!(
+            "<p><em>{}</em></p>",
+            localize("JOHNYS.Equipment.NoDescription")
+        )
+    };
+    let content = format!(
+        r#"<div class="item-details"><img src="{}" /><div class="tags">{traits}</div>{description}</div>"#,
+        item.img().unwrap_or_default()
+    );
+
+    let window_id = format!("johnys-item-{}", item.id().unwrap_or_default());
+    if let Some(existing) = Application::get(&window_id) {
+        return existing.render().await;
+    }
+    application::WindowBuilder::new(&window_id, &item.name())
+        .classes(&["johnys-item-details"])
+        .position(&ApplicationPosition {
+            width: Some(360.0),
+            ..Default::default()
+        })
+        .render(move || {
+            let content = content.clone();
+            async move { Ok(JsValue::from(content)) }
+        })
+        .build()?
+        .render()
+        .await
+}
+
+/// Open item details when an item in any rendered equipment screen is clicked
+fn listen_for_item_clicks() -> Result<(), Error> {
+    let click_fn = Closure::wrap(Box::new(|event: JsValue| {
+        let Some(uuid) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| {
+                HtmlElement::from(target).closest(".equipment-screen [data-item-uuid]")
+            })
+            .and_then(|slot| slot.get_attribute("data-item-uuid"))
+        else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = show_item_details(&uuid).await {
+                cprintln!("Error showing item details: {err}");
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    Document::body()?.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+/// Let items in any rendered equipment screen be dragged onto sheets as standard item drops
+fn listen_for_item_drags() -> Result<(), Error> {
+    let drag_fn = Closure::wrap(Box::new(|event: JsValue| {
+        let Some(uuid) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| {
+                HtmlElement::from(target).closest(".equipment-screen [draggable][data-item-uuid]")
+            })
+            .and_then(|slot| slot.get_attribute("data-item-uuid"))
+        else {
+            return;
+        };
+        let drag_data = format!(r#"{{"type":"Item","uuid":"{uuid}"}}"#);
+        let result = get_property(&event, "dataTransfer")
+            .map_err(Error::from)
+            .and_then(|transfer| {
+                call_method(
+                    &transfer,
+                    "setData",
+                    &[&"text/plain".into(), &drag_data.into()],
+                )
+            });
+        if let Err(err) = result {
+            cprintln!("Error starting item drag: {err}");
+        }
+    }) as Box<dyn Fn(JsValue)>);
+    Document::body()?.add_event_listener("dragstart", &drag_fn)?;
+    drag_fn.forget();
+    Ok(())
+}
+
+/// Post a chat line when a combatant draws, stows, or regrips an item, from the active GM's client only
+async fn announce_equipment_change(item: Item, changes: JsValue) -> Result<(), Error> {
+    if !is_enabled("visibleEquipmentAnnounce") {
+        return Ok(());
+    }
+    if !is_responsible_gm(&Game::instance()?)? {
+        return Ok(());
+    }
+    let Some(actor) = item.actor().filter(Actor::in_combat) else {
+        return Ok(());
+    };
+    // the line is public, so it mustn't give away hidden creatures or gear
+    if actor.active_token().is_some_and(|token| token.is_hidden()) || hidden_magic(&item) {
+        return Ok(());
+    }
+    let equipped = get_path!(&changes, "system.equipped").ok();
+    let changed = |key: &str| {
+        equipped
+            .as_ref()
+            .and_then(|equipped| get_property(equipped, key).ok())
+            .is_some_and(|value| !value.is_undefined())
+    };
+
+    let action = if changed("carryType") {
+        match item.carry_type().as_deref() {
+            Some("held") => "Draws",
+            Some("worn") => "PutsAway",
+            Some("stowed") => "Stows",
+            Some("dropped") => "Drops",
+            _ => return Ok(()),
+        }
+    } else if changed("handsHeld") && item.carry_type().as_deref() == Some("held") {
+        if item.hands_held() >= 2.0 {
+            "GripsTwo"
+        } else {
+            "GripsOne"
+        }
+    } else {
+        return Ok(());
+    };
+    let item_name = if !players_see_names(&actor) {
+        localize("JOHNYS.Equipment.Announce.Item")
+    } else if item.is_identified() {
+        item.name()
+    } else {
+        item.unidentified_name()
+            .unwrap_or_else(|| localize("JOHNYS.Equipment.Announce.UnidentifiedItem"))
+    };
+    let line = localize_format(
+        &format!("JOHNYS.Equipment.Announce.{action}"),
+        &[("actor", &actor.name()), ("item", &item_name)],
+    );
+
+    Message::create(&format!(
+        r#"<p class="johnys-equipment-announcement"><em>{line}</em></p>"#
+    ))
     .await?;
+    Ok(())
+}
 
-    application::show_dialog("Equipment", html, vec![("close", "Close", None)]).await?;
+thread_local! {
+    /// What each combatant held at the last turn, by combatant ID
+    static HELD_AT_TURN: RefCell<HashMap<String, u64>> = RefCell::new(HashMap::new());
+}
+
+/// Hash what the actor is holding and how, along with a description for the GM
+fn held_summary(actor: &Actor) -> (u64, String) {
+    let mut hasher = DefaultHasher::new();
+    let mut held = Vec::new();
+    for item in actor
+        .items()
+        .iter()
+        .filter(|item| item.is_physical_item() && item.carry_type().as_deref() == Some("held"))
+    {
+        item.id().hash(&mut hasher);
+        let hands = item.hands_held().clamp(0.0, 2.0) as u8;
+        hands.hash(&mut hasher);
+        let two_hands = Grip::new(item.hands_required(), hands, false) == Grip::BothHands;
+        held.push(localize_format(
+            if two_hands {
+                "JOHNYS.Equipment.HandChange.TwoHands"
+            } else {
+                "JOHNYS.Equipment.HandChange.OneHand"
+            },
+            &[("item", &item.name())],
+        ));
+    }
+    let key = if held.is_empty() {
+        "JOHNYS.Equipment.HandChange.EmptyHands"
+    } else {
+        "JOHNYS.Equipment.HandChange.Wielding"
+    };
+    let description = localize_format(
+        key,
+        &[
+            ("actor", &actor.name()),
+            (
+                "items",
+                &held.join(&localize("JOHNYS.Equipment.HandChange.And")),
+            ),
+        ],
+    );
+    (hasher.finish(), description)
+}
+
+/// On the GM's client, point out combatants whose hands changed since the last turn
+fn notify_hand_changes(combat: Combat) {
+    if !viewer_is_gm() || !is_enabled("visibleEquipmentHandChanges") {
+        return;
+    }
+    for combatant in combat.combatants() {
+        let (Some(id), Some(actor)) = (combatant.id(), combatant.actor()) else {
+            continue;
+        };
+        let (hash, description) = held_summary(&actor);
+        let previous = HELD_AT_TURN.with_borrow_mut(|held| held.insert(id, hash));
+        if previous.is_some_and(|previous| previous != hash) {
+            UI::notify_info(&description);
+        }
+    }
+}
+
+/// Add a button to the token HUD that opens the equipment screen for that token
+fn inject_hud_button(hud: JsValue, html: JsValue) -> Result<(), Error> {
+    if !is_enabled("visibleEquipmentEnabled") {
+        return Ok(());
+    }
+    let token_js = get_property(&hud, "object")?;
+
+    let html = HtmlElement::from(html);
+    let column = html.query_selector(".col.left")?.ctx("token HUD column")?;
+    column.insert_adjacent_html(
+        "beforeend",
+        &format!(
+            r#"<div class="control-icon johnys-equipment-button" data-tooltip="{}"><i class="fa-solid fa-shield-halved"></i></div>"#,
+            localize("JOHNYS.Equipment.ViewEquipment")
+        ),
+    )?;
+    let button = column
+        .query_selector(".johnys-equipment-button")?
+        .ctx("equipment button")?;
+
+    let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+        let token: Token = token_js.clone().into();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = show_equipment_window(token.actor().into_iter().collect())
+                .await
+                .ctx(&localize("JOHNYS.Equipment.Errors.Unable"))
+            {
+                cprintln!("Error opening equipment screen: {err}");
+                UI::notify_error(&err.to_string());
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
 
     Ok(())
 }
 
-/// render equipment data and inject it into the bestiary window
+/// render equipment data and inject it into the bestiary window, replacing any stale section
 async fn inject_equipment_ui_async(app: BestiaryApp, html: HtmlElement) -> Result<(), Error> {
+    let existing = html.query_selector(".equipment-data-section")?;
     let Some(uuid) = app.selected_monster_uuid() else {
+        if let Some(section) = existing {
+            section.remove()?;
+        }
         return Ok(());
     };
+    if existing
+        .as_ref()
+        .and_then(|section| section.get_attribute("data-actor-uuid"))
+        .as_ref()
+        == Some(&uuid)
+    {
+        return Ok(());
+    }
 
     if !is_enabled("visibleEquipmentEnabled") {
-        return Err("Equipment observation must be enabled by GM".into());
+        return Err(localize("JOHNYS.Equipment.Errors.Disabled").into());
     }
 
     let game = Game::instance()?;
-    let all_items: EquipmentContext = game
-        .find_token_by_actor_uuid(&uuid)
-        .map(|token| token.actor_items().as_slice().into())
-        .unwrap_or_default();
-
-    let equipment_html = application::render_template(
-        "modules/johnys-module/templates/equipment-screen.hbs",
-        &all_items,
-    )
-    .await?;
+    // prefer the token on the scene, otherwise show the world or compendium actor's gear
+    let actor = match game.find_token_by_actor_uuid(&uuid) {
+        Some(token) => token.actor(),
+        None => Game::actor_from_uuid(&uuid).await.ok(),
+    };
+    // the bestiary is what the party already knows, so it never asks for a check
+    let equipment_html = match actor {
+        Some(actor) => {
+            let detail = DetailLevel::fixed(&actor).unwrap_or(DetailLevel::Runes);
+            render_actor_equipment(&actor, detail).await?
+        }
+        None => {
+            application::render_template(
+                "modules/johnys-module/templates/equipment-screen.hbs",
+                &EquipmentContext::default(),
+            )
+            .await?
+        }
+    };
 
     let container_data = html
         .query_selector(".right-monster-container-data")?
         .ctx("Could not find right-monster-container-data")?;
 
-    // Check if equipment section already exists
-    if html.query_selector(".equipment-data-section")?.is_none() {
-        // Insert as the last section after passives
-        container_data.insert_adjacent_html(
-            "beforeend",
-            &format!(
-                r#"
-            <div class="data-section primary-container active equipment-data-section">
-                <div class="data-header primary-container">
-                    <div class="data-header-label">
-                        <div class="data-icon primary-icon">
-                            <i class="fa-solid fa-shield"></i>
-                        </div>
-                        <div class="flex-value">Equipment</div>
+    // the selection may have changed again while rendering, so replace whatever is there now
+    if let Some(section) = html.query_selector(".equipment-data-section")? {
+        section.remove()?;
+    }
+    // Insert as the last section after passives
+    container_data.insert_adjacent_html(
+        "beforeend",
+        &format!(
+            r#"
+        <div class="data-section primary-container active equipment-data-section" data-actor-uuid="{}">
+            <div class="data-header primary-container">
+                <div class="data-header-label">
+                    <div class="data-icon primary-icon">
+                        <i class="fa-solid fa-shield"></i>
                     </div>
-                </div>
-                <div class="data-body primary-border-container">
-                    {}
+                    <div class="flex-value">{}</div>
                 </div>
             </div>
-        "#,
-                equipment_html
-            ),
-        )?;
-    }
+            <div class="data-body primary-border-container">
+                {}
+            </div>
+        </div>
+    "#,
+            uuid,
+            localize("JOHNYS.Equipment.Title"),
+            equipment_html
+        ),
+    )?;
 
     Ok(())
 }
@@ -198,26 +1244,236 @@ pub fn init() {
             .type_boolean()
             .default_bool(true)
             .register(ID, "visibleEquipmentEnabled");
+
+        SettingConfig::new()
+            .name("Equipment Header")
+            .hint("How much players see about the creature at the top of the equipment preview for creatures they don't own. The GM and owners always see everything.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .default_string("portrait")
+            .choices(&[
+                ("none", "Nothing"),
+                ("portrait", "Portrait and name"),
+                ("full", "Portrait, name, level, and AC"),
+            ])
+            .register(ID, "visibleEquipmentHeader");
+
+        SettingConfig::new()
+            .name("Show Container Contents")
+            .hint("Include the contents of worn backpacks and other containers in the equipment preview, as a collapsible list under each container.")
+            .scope("world")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "visibleEquipmentContainers");
+
+        SettingConfig::new()
+            .name("Show Stowed Items on Your Creatures")
+            .hint("Include stowed items, grouped by container, when viewing the equipment of creatures you own, for a quick look at your inventory without opening the sheet.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "visibleEquipmentStowed");
+
+        SettingConfig::new()
+            .name("Show Bulk of Other Creatures")
+            .hint("Show carried Bulk and encumbrance in the equipment preview for creatures the player doesn't own. Players always see their own.")
+            .scope("world")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "visibleEquipmentBulkOthers");
+
+        SettingConfig::new()
+            .name("Hide Worn Magic Items")
+            .hint("Hide invested and worn magic items from players' equipment previews entirely. Unidentified items are always shown with their unidentified name and image.")
+            .scope("world")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "visibleEquipmentHideMagic");
+
+        SettingConfig::new()
+            .name("Announce Equipment Changes")
+            .hint("Post a short chat message when a creature in combat draws, stows, drops, or changes its grip on an item.")
+            .scope("world")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "visibleEquipmentAnnounce");
+
+        SettingConfig::new()
+            .name("Notify GM of Hand Changes")
+            .hint("At each turn in combat, tell the GM which creatures are holding something different than at the previous turn, to help count Interact actions.")
+            .scope("world")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "visibleEquipmentHandChanges");
+
+        SettingConfig::new()
+            .name("Equipment Detail")
+            .hint("What players see of creatures they don't own: only icons, item names, or everything including runes. Or have the GM's client secretly roll the viewing character's Perception when the equipment screen opens, which needs a GM connected: beating the name DC reveals item names, and beating the rune DC reveals runes too. The GM always sees everything.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .default_string("full")
+            .choices(&[
+                ("full", "Show everything"),
+                ("names", "Show item names, but not runes"),
+                ("icons", "Show icons only"),
+                ("perception", "Gate on a secret Perception check"),
+            ])
+            .register(ID, "visibleEquipmentDetail");
+
+        SettingConfig::new()
+            .name("Equipment Name DC")
+            .hint("Perception DC to make out item names when equipment detail is gated on Perception.")
+            .scope("world")
+            .config(true)
+            .type_number()
+            .range(0.0, 50.0, 1.0)
+            .default_number(15.0)
+            .register(ID, "visibleEquipmentNameDC");
+
+        SettingConfig::new()
+            .name("Equipment Rune DC")
+            .hint("Perception DC to make out runes when equipment detail is gated on Perception.")
+            .scope("world")
+            .config(true)
+            .type_number()
+            .range(0.0, 50.0, 1.0)
+            .default_number(25.0)
+            .register(ID, "visibleEquipmentRuneDC");
+
+        SettingConfig::new()
+            .name("Equipment Token Priority")
+            .hint("Which tokens the equipment screen shows when opened from the keybinding or macro. The first kind found wins.")
+            .scope("client")
+            .config(true)
+            .type_string()
+            .default_string("hovered-targeted-controlled")
+            .choices(&[
+                ("hovered-targeted-controlled", "Hovered, then targeted, then selected"),
+                ("hovered-controlled-targeted", "Hovered, then selected, then targeted"),
+                ("controlled-hovered-targeted", "Selected, then hovered, then targeted"),
+            ])
+            .register(ID, "visibleEquipmentTokenPriority");
+
+        Keybinding::new()
+            .name("View Hovered Token's Equipment")
+            .hint("Open the equipment screen for the token under the mouse, your targets, or your selected tokens, in the order set in the module settings.")
+            .key("KeyE", &["Shift"])
+            .on_down(|| {
+                wasm_bindgen_futures::spawn_local(open_equipment_screen());
+            })
+            .register(ID, "openEquipmentScreen");
+
         // Register API for macro access
-        if let Ok(game) = Game::instance() {
-            if let Ok(modules) = game.modules() {
-                if let Some(module) = modules.get(ID) {
-                    let equipment_fn = Closure::wrap(Box::new(move || {
-                        wasm_bindgen_futures::spawn_local(async move {
-                            open_equipment_screen().await;
-                        });
-                    }) as Box<dyn Fn()>);
-
-                    module
-                        .set_api_property("openEquipmentScreen", equipment_fn.as_ref())
-                        .ok();
-                    equipment_fn.forget();
+        let equipment_fn = Closure::wrap(Box::new(move || {
+            wasm_bindgen_futures::spawn_local(async move {
+                open_equipment_screen().await;
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openEquipmentScreen", &equipment_fn.into_js_value());
+
+        let compare_fn = Closure::wrap(Box::new(|uuid_a: JsValue, uuid_b: JsValue| {
+            let uuid_a = uuid_a.as_string().unwrap_or_default();
+            let uuid_b = uuid_b.as_string().unwrap_or_default();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = compare_equipment(uuid_a, uuid_b)
+                    .await
+                    .ctx(&localize("JOHNYS.Equipment.Errors.UnableCompare"))
+                {
+                    cprintln!("Error comparing equipment: {err}");
+                    UI::notify_error(&err.to_string());
                 }
+            });
+        }) as Box<dyn Fn(JsValue, JsValue)>);
+        register_api("compareEquipment", &compare_fn.into_js_value());
+
+        let equipment_for_fn = Closure::wrap(Box::new(|uuid: JsValue| {
+            let uuid = uuid.as_string().unwrap_or_default();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = open_equipment_screen_for(uuid)
+                    .await
+                    .ctx(&localize("JOHNYS.Equipment.Errors.Unable"))
+                {
+                    cprintln!("Error opening equipment screen: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn(JsValue)>);
+        register_api("openEquipmentScreenFor", &equipment_for_fn.into_js_value());
+    });
+
+    hook!("updateCombat", |combat: JsValue, changes: JsValue| {
+        let turn_changed = ["turn", "round"]
+            .iter()
+            .any(|key| get_property(&changes, key).is_ok_and(|value| !value.is_undefined()));
+        if turn_changed {
+            notify_hand_changes(combat.into());
+        }
+    });
+    hook!("deleteCombat", |_combat: JsValue| {
+        HELD_AT_TURN.with_borrow_mut(HashMap::clear);
+    });
+
+    hook!("updateItem", async |item: JsValue, changes: JsValue| {
+        if let Err(err) = announce_equipment_change(item.into(), changes).await {
+            cprintln!("Error announcing equipment change: {err}");
+        }
+    });
+
+    for hook in ["createItem", "updateItem", "deleteItem"] {
+        hook!(hook, |item: JsValue| {
+            if let Some(uuid) = Item::from(item).actor_uuid() {
+                invalidate_render_cache(&uuid);
             }
+        });
+    }
+    // the header shows the actor's level and AC
+    hook!("updateActor", |actor: JsValue| {
+        if let Some(uuid) = Actor::from(actor).uuid() {
+            invalidate_render_cache(&uuid);
+        }
+    });
+    // settings shape what's shown, so start over when any of them change
+    hook!("updateSetting", |_setting: JsValue| {
+        RENDER_CACHE.with_borrow_mut(HashMap::clear);
+    });
+    hook!("clientSettingChanged", |_key: JsValue| {
+        RENDER_CACHE.with_borrow_mut(HashMap::clear);
+    });
+
+    hook!("hoverToken", async |token: JsValue, hovered: JsValue| {
+        if hovered.as_bool() != Some(true) || !is_enabled("visibleEquipmentEnabled") {
+            return;
+        }
+        if let Err(err) = follow_hovered_token(token.into()).await {
+            cprintln!("Error following hovered token: {err}");
         }
     });
 
+    hook!(
+        "renderTokenHUD",
+        |hud: JsValue, html: JsValue, _data: JsValue| {
+            if let Err(err) = inject_hud_button(hud, html) {
+                cprintln!("Error adding equipment button to token HUD: {err}");
+            }
+        }
+    );
+
     hook!("ready", || {
+        perception_gate::init_socket();
+        if let Err(err) = listen_for_item_clicks() {
+            cprintln!("Error listening for equipment clicks: {err}");
+        }
+        if let Err(err) = listen_for_item_drags() {
+            cprintln!("Error listening for equipment drags: {err}");
+        }
         if Game::is_module_active("pf2e-bestiary-tracking") {
             cprintln!("PF2E Bestiary Tracking detected, registering equipment injection");
             hook!("renderPF2EBestiary", async |app: JsValue, html: JsValue| {
@@ -0,0 +1,182 @@
+/// How a held item occupies its wielder's hands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Grip {
+    /// Held without taking up a hand, like a weapon with the free-hand trait
+    Free,
+    /// Held in one hand; `needs_two` when the item can't be used without a second hand
+    OneHand { needs_two: bool },
+    /// Wielded in both hands
+    BothHands,
+}
+
+impl Grip {
+    /// Work out the grip from how many hands the item requires and how many it's held in
+    pub(super) fn new(hands_required: u8, hands_held: u8, free_hand: bool) -> Self {
+        if free_hand {
+            return Grip::Free;
+        }
+        if hands_held >= 2 {
+            Grip::BothHands
+        } else {
+            // held items with no hands recorded are still in a hand
+            Grip::OneHand {
+                needs_two: hands_required >= 2,
+            }
+        }
+    }
+}
+
+/// Which held item, by index, goes in each hand slot
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct HandLayout {
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+    /// The item in the left hand fills the right hand too
+    pub both_hands: bool,
+    /// Held items that don't fit in a hand
+    pub extra: Vec<usize>,
+}
+
+/// Put held items in hands: the first two-handed grip wins both hands, then one-handed items fill
+/// whatever is left, and everything else is listed separately
+pub(super) fn layout(grips: &[Grip]) -> HandLayout {
+    let mut hands = HandLayout::default();
+    if let Some(index) = grips.iter().position(|grip| *grip == Grip::BothHands) {
+        hands.left = Some(index);
+        hands.right = Some(index);
+        hands.both_hands = true;
+    }
+    for (index, grip) in grips.iter().enumerate() {
+        if hands.left == Some(index) {
+            continue;
+        }
+        match grip {
+            Grip::OneHand { .. } if hands.left.is_none() => hands.left = Some(index),
+            Grip::OneHand { .. } if hands.right.is_none() => hands.right = Some(index),
+            _ => hands.extra.push(index),
+        }
+    }
+    hands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE: Grip = Grip::OneHand { needs_two: false };
+
+    #[test]
+    fn two_hand_trait_weapon_in_one_hand() {
+        // two-hand weapons only require one hand to hold
+        assert_eq!(Grip::new(1, 1, false), ONE);
+    }
+
+    #[test]
+    fn two_hand_trait_weapon_in_both_hands() {
+        assert_eq!(Grip::new(1, 2, false), Grip::BothHands);
+    }
+
+    #[test]
+    fn two_handed_weapon_in_one_hand_needs_two() {
+        assert_eq!(Grip::new(2, 1, false), Grip::OneHand { needs_two: true });
+    }
+
+    #[test]
+    fn two_handed_weapon_in_both_hands() {
+        assert_eq!(Grip::new(2, 2, false), Grip::BothHands);
+    }
+
+    #[test]
+    fn free_hand_ignores_hands_held() {
+        assert_eq!(Grip::new(1, 1, true), Grip::Free);
+        assert_eq!(Grip::new(1, 0, true), Grip::Free);
+    }
+
+    #[test]
+    fn missing_hands_held_is_one_hand() {
+        assert_eq!(Grip::new(1, 0, false), ONE);
+    }
+
+    #[test]
+    fn empty_hands() {
+        assert_eq!(layout(&[]), HandLayout::default());
+    }
+
+    #[test]
+    fn one_handed_items_fill_left_then_right() {
+        assert_eq!(
+            layout(&[ONE, ONE, ONE]),
+            HandLayout {
+                left: Some(0),
+                right: Some(1),
+                both_hands: false,
+                extra: vec![2],
+            }
+        );
+    }
+
+    #[test]
+    fn two_handed_grip_takes_both_hands_wherever_it_is() {
+        let expected = |index, other| HandLayout {
+            left: Some(index),
+            right: Some(index),
+            both_hands: true,
+            extra: vec![other],
+        };
+        assert_eq!(layout(&[Grip::BothHands, ONE]), expected(0, 1));
+        assert_eq!(layout(&[ONE, Grip::BothHands]), expected(1, 0));
+    }
+
+    #[test]
+    fn second_two_handed_grip_is_extra() {
+        assert_eq!(
+            layout(&[Grip::BothHands, Grip::BothHands]),
+            HandLayout {
+                left: Some(0),
+                right: Some(0),
+                both_hands: true,
+                extra: vec![1],
+            }
+        );
+    }
+
+    #[test]
+    fn free_hand_items_never_take_a_hand() {
+        assert_eq!(
+            layout(&[Grip::Free, ONE, Grip::Free]),
+            HandLayout {
+                left: Some(1),
+                right: None,
+                both_hands: false,
+                extra: vec![0, 2],
+            }
+        );
+    }
+
+    #[test]
+    fn free_hand_item_with_two_handed_grip() {
+        assert_eq!(
+            layout(&[Grip::Free, Grip::BothHands]),
+            HandLayout {
+                left: Some(1),
+                right: Some(1),
+                both_hands: true,
+                extra: vec![0],
+            }
+        );
+    }
+
+    #[test]
+    fn item_needing_two_hands_held_in_one_still_takes_one() {
+        let needs_two = Grip::OneHand { needs_two: true };
+        assert_eq!(
+            layout(&[needs_two, ONE]),
+            HandLayout {
+                left: Some(0),
+                right: Some(1),
+                both_hands: false,
+                extra: vec![],
+            }
+        );
+    }
+}
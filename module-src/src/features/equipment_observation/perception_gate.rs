@@ -0,0 +1,133 @@
+use super::DetailLevel;
+use crate::features::is_responsible_gm;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::ID;
+use futures::channel::oneshot;
+use futures::future::{select, Either};
+use gloo_timers::future::TimeoutFuture;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use wasm_bindgen::JsValue;
+
+/// How long to wait for the GM to answer before only showing icons
+const RESPONSE_TIMEOUT_MS: u32 = 5000;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum PerceptionGateMessage {
+    /// Ask the GM to secretly roll a character's Perception for the player who sent it
+    #[serde(rename_all = "camelCase")]
+    PerceptionGateRequest {
+        request_id: String,
+        viewer_uuid: String,
+    },
+    /// The detail level the GM's roll earned
+    #[serde(rename_all = "camelCase")]
+    PerceptionGateResult {
+        request_id: String,
+        user_id: String,
+        detail: DetailLevel,
+    },
+}
+
+thread_local! {
+    /// Requests waiting on the GM's roll, by request ID
+    static PENDING: RefCell<HashMap<String, oneshot::Sender<DetailLevel>>> =
+        RefCell::new(HashMap::new());
+    static NEXT_REQUEST: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Have the GM roll `viewer`'s Perception, so players can't reroll it from their own client
+pub(super) async fn roll(viewer: &Actor) -> Result<DetailLevel, Error> {
+    let game = Game::instance()?;
+    if game.users()?.active_gm().is_none() {
+        UI::notify_warn("No GM is connected to roll your Perception, so only icons are shown.");
+        return Ok(DetailLevel::Icons);
+    }
+    let user_id = game.user()?.id().unwrap_or_default();
+    let request_id = format!("{user_id}-{}", NEXT_REQUEST.replace(NEXT_REQUEST.get() + 1));
+    let (sender, receiver) = oneshot::channel();
+    PENDING.with_borrow_mut(|pending| pending.insert(request_id.clone(), sender));
+    socket::emit(
+        ID,
+        &PerceptionGateMessage::PerceptionGateRequest {
+            request_id: request_id.clone(),
+            viewer_uuid: viewer.uuid().ctx("viewer UUID")?,
+        },
+    )?;
+
+    let answer = select(receiver, TimeoutFuture::new(RESPONSE_TIMEOUT_MS)).await;
+    PENDING.with_borrow_mut(|pending| pending.remove(&request_id));
+    match answer {
+        Either::Left((Ok(detail), _)) => Ok(detail),
+        _ => {
+            UI::notify_warn("The GM didn't roll your Perception in time, so only icons are shown.");
+            Ok(DetailLevel::Icons)
+        }
+    }
+}
+
+/// On the GM's client, roll for a player's character, as long as they own it
+async fn answer(request_id: String, viewer_uuid: String, sender_id: String) -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !is_responsible_gm(&game)? {
+        return Ok(());
+    }
+    let Some(user) = game.users()?.get(&sender_id) else {
+        return Ok(());
+    };
+    let viewer = Game::actor_from_uuid(&viewer_uuid).await?;
+    let detail = match viewer.perception_modifier() {
+        Some(modifier) if viewer.is_owned_by(&user, GMStrategy::Never, OwnershipLevel::Owner) => {
+            let total = Roll::evaluate(&format!("1d20 + {modifier}")).await?.total();
+            DetailLevel::from_perception(total)
+        }
+        _ => DetailLevel::Icons,
+    };
+    socket::emit(
+        ID,
+        &PerceptionGateMessage::PerceptionGateResult {
+            request_id,
+            user_id: sender_id,
+            detail,
+        },
+    )
+}
+
+async fn receive(data: JsValue, sender_id: String) -> Result<(), Error> {
+    match serde_wasm_bindgen::from_value(data) {
+        Ok(PerceptionGateMessage::PerceptionGateRequest {
+            request_id,
+            viewer_uuid,
+        }) => answer(request_id, viewer_uuid, sender_id).await?,
+        Ok(PerceptionGateMessage::PerceptionGateResult {
+            request_id,
+            user_id,
+            detail,
+        }) => {
+            if Game::instance()?.user()?.id().as_deref() != Some(user_id.as_str()) {
+                return Ok(());
+            }
+            if let Some(sender) = PENDING.with_borrow_mut(|pending| pending.remove(&request_id)) {
+                let _ = sender.send(detail);
+            }
+        }
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+pub(super) fn init_socket() {
+    let result = socket::on_with_sender(ID, |data, sender_id| {
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = receive(data, sender_id).await {
+                cprintln!("Error handling Perception gate: {err}");
+            }
+        });
+    });
+    if let Err(err) = result {
+        cprintln!("Error listening for Perception gate rolls: {err}");
+    }
+}
@@ -0,0 +1,174 @@
+use crate::features::{player_characters, register_api};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::hook;
+use wasm_bindgen::prelude::*;
+
+/// Experience needed to gain a level
+const LEVEL_UP_XP: f64 = 1000.0;
+
+/// Each character's award from an encounter's total XP at the party's actual size
+///
+/// Awards are given as though for a party of four, so bigger parties share out less each.
+fn adjusted_award(xp: f64, party_size: usize) -> f64 {
+    (xp * 4.0 / party_size.max(1) as f64).round()
+}
+
+/// Award the XP from the form to the checked characters and post who can level up
+async fn award(form: HtmlElement) -> Result<(), Error> {
+    let xp = form
+        .query_selector("[name=xp]")?
+        .and_then(|input| input.value())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|xp| *xp > 0.0)
+        .ctx("Enter how much XP to award")?;
+    let adjust = form
+        .query_selector("[name=adjust]")?
+        .is_some_and(|checkbox| checkbox.is_checked());
+    let chosen: Vec<String> = form
+        .query_selector_all("[name=actor]")
+        .into_iter()
+        .filter(|checkbox| checkbox.is_checked())
+        .filter_map(|checkbox| checkbox.value())
+        .collect();
+    let characters = player_characters()?;
+    let party_size = characters.len();
+    let actors: Vec<Actor> = characters
+        .into_iter()
+        .filter(|actor| actor.uuid().is_some_and(|uuid| chosen.contains(&uuid)))
+        .collect();
+    if actors.is_empty() {
+        return Err("Choose who gets the XP".into());
+    }
+    let each = if adjust {
+        adjusted_award(xp, party_size)
+    } else {
+        xp
+    };
+
+    let mut level_ups = Vec::new();
+    for actor in &actors {
+        let Some(current) = actor.experience() else {
+            continue;
+        };
+        actor.set_experience(current + each).await?;
+        if current < LEVEL_UP_XP && current + each >= LEVEL_UP_XP {
+            level_ups.push(actor.name());
+        }
+    }
+
+    let names: Vec<String> = actors.iter().map(Actor::name).collect();
+    let mut content = format!(
+        "<p><strong>{each} XP</strong> awarded to {}.</p>",
+        names.join(", ")
+    );
+    for name in level_ups {
+        content.push_str(&format!(
+            r#"<p class="johnys-level-up"><i class="fa-solid fa-angles-up"></i> {name} can level up!</p>"#
+        ));
+    }
+    Message::create(&format!(r#"<div class="johnys-xp-award">{content}</div>"#)).await?;
+    Ok(())
+}
+
+/// Ask how much XP to award and to whom: the selected characters, or else the whole party
+async fn open() -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !game.user()?.is_gm() {
+        return Err("Only the GM can award XP".into());
+    }
+    let characters = player_characters()?;
+    if characters.is_empty() {
+        return Err("There are no player characters to award XP to".into());
+    }
+    let selected: Vec<String> = game
+        .controlled_tokens()
+        .into_iter()
+        .filter_map(|token| token.actor()?.uuid())
+        .collect();
+    let any_selected = characters
+        .iter()
+        .any(|actor| actor.uuid().is_some_and(|uuid| selected.contains(&uuid)));
+    let options: String = characters
+        .iter()
+        .filter_map(|actor| {
+            let uuid = actor.uuid()?;
+            let checked = if !any_selected || selected.contains(&uuid) {
+                " checked"
+            } else {
+                ""
+            };
+            Some(format!(
+                r#"<label class="xp-actor"><input type="checkbox" name="actor" value="{uuid}"{checked}> {name} ({xp} XP)</label>"#,
+                name = actor.name(),
+                xp = actor.experience().unwrap_or_default(),
+            ))
+        })
+        .collect();
+
+    let award_fn = Closure::wrap(Box::new(|html: JsValue| {
+        let form = HtmlElement::from(html);
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = award(form).await {
+                cprintln!("Error awarding XP: {err}");
+                UI::notify_error(&err.to_string());
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+
+    application::show_dialog(
+        "Award XP",
+        format!(
+            r#"<div class="form-group"><label>Encounter XP for this party of {party_size}</label><input type="number" name="xp" min="0" value="0"></div>
+            <div class="form-group"><label>Convert to each character's award (&times; 4 &divide; {party_size})</label><input type="checkbox" name="adjust"{adjust}></div>
+            <div class="johnys-xp-actors">{options}</div>"#,
+            party_size = characters.len(),
+            // a party of four needs no adjustment
+            adjust = if characters.len() == 4 { "" } else { " checked" },
+        ),
+        vec![
+            (
+                "award",
+                "Award",
+                Some(award_fn.into_js_value().unchecked_into()),
+            ),
+            ("cancel", "Cancel", None),
+        ],
+    )
+    .await
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.awardXp()
+        let award_xp_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening XP award: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("awardXp", &award_xp_fn.into_js_value());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn party_of_four_award_unchanged() {
+        assert_eq!(adjusted_award(80.0, 4), 80.0);
+    }
+
+    #[test]
+    fn larger_party_award_scaled_down() {
+        assert_eq!(adjusted_award(100.0, 5), 80.0);
+    }
+
+    #[test]
+    fn smaller_party_award_scaled_up() {
+        assert_eq!(adjusted_award(60.0, 3), 80.0);
+    }
+}
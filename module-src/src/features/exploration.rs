@@ -0,0 +1,355 @@
+use crate::features::{is_responsible_gm, player_characters, register_api};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// Exploration activities and the statistic each suggests for initiative
+const ACTIVITIES: [(&str, &str, &str); 10] = [
+    ("", "None", "perception"),
+    ("avoid-notice", "Avoid Notice", "stealth"),
+    ("defend", "Defend", "perception"),
+    ("detect-magic", "Detect Magic", "perception"),
+    ("follow-the-expert", "Follow the Expert", "perception"),
+    ("hustle", "Hustle", "perception"),
+    ("investigate", "Investigate", "perception"),
+    ("repeat-a-spell", "Repeat a Spell", "perception"),
+    ("scout", "Scout", "perception"),
+    ("search", "Search", "perception"),
+];
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+}
+
+/// Get the character's current exploration activity, if they've picked one
+fn activity(actor: &Actor) -> Option<&'static (&'static str, &'static str, &'static str)> {
+    let slug = actor.get_flag(ID, "explorationActivity").as_string()?;
+    ACTIVITIES
+        .iter()
+        .find(|(activity, _, _)| !activity.is_empty() && *activity == slug)
+}
+
+/// Open the party panel, or refresh it if it's already open
+async fn open() -> Result<(), Error> {
+    application::SingletonWindow::open(&WINDOW, || {
+        application::WindowBuilder::new("johnys-exploration", "Exploration Activities")
+            .icon("fa-solid fa-compass")
+            .classes(&["johnys-exploration"])
+            .position(&ApplicationPosition {
+                width: Some(340.0),
+                ..Default::default()
+            })
+            .render(render_rows)
+    })
+    .await
+}
+
+/// List each player character with their activity, which their owners can change
+async fn render_rows() -> Result<JsValue, Error> {
+    let rows: String = player_characters()?
+        .iter()
+        .filter_map(|actor| {
+            let current = activity(actor)
+                .map(|(slug, _, _)| *slug)
+                .unwrap_or_default();
+            let options: String = ACTIVITIES
+                .iter()
+                .map(|(slug, label, _)| {
+                    let selected = if *slug == current { " selected" } else { "" };
+                    format!(r#"<option value="{slug}"{selected}>{label}</option>"#)
+                })
+                .collect();
+            let disabled = if actor.is_owner() { "" } else { " disabled" };
+            Some(format!(
+                r#"<li class="johnys-row">
+                    <span class="exploration-name">{name}</span>
+                    <select data-actor-uuid="{uuid}"{disabled}>{options}</select>
+                </li>"#,
+                name = escape_html(&actor.name()),
+                uuid = actor.uuid()?,
+            ))
+        })
+        .collect();
+
+    let container = Document::create_element("ol")?;
+    container.set_class_name("johnys-rows")?;
+    if rows.is_empty() {
+        container.insert_adjacent_html(
+            "afterbegin",
+            r#"<li class="johnys-empty">No player characters.</li>"#,
+        )?;
+    } else {
+        container.insert_adjacent_html("afterbegin", &rows)?;
+    }
+
+    let change_fn = Closure::wrap(Box::new(|event: JsValue| {
+        let Ok(target) = get_property(&event, "target") else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = set_activity(HtmlElement::from(target)).await {
+                cprintln!("Error setting exploration activity: {err}");
+                UI::notify_error(&format!("Unable to set exploration activity: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    container.add_event_listener("change", &change_fn)?;
+    change_fn.forget();
+
+    Ok(container.as_js_value().clone())
+}
+
+/// Save the activity picked in a row's select
+async fn set_activity(select: HtmlElement) -> Result<(), Error> {
+    let uuid = select
+        .get_attribute("data-actor-uuid")
+        .ctx("exploration actor")?;
+    let slug = select.value().unwrap_or_default();
+    let actor: Actor = Game::from_uuid(&uuid).await?;
+    actor
+        .set_flag(ID, "explorationActivity", &JsValue::from_str(&slug))
+        .await
+}
+
+/// Tell the GM which initiative statistic each combatant's activity suggests, with a button to use them
+async fn suggest_initiative(combat: &Combat) -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !is_responsible_gm(&game)? {
+        return Ok(());
+    }
+    let suggestions: Vec<String> = combat
+        .combatants()
+        .into_iter()
+        .filter_map(|combatant| {
+            let actor = combatant.actor()?;
+            let (_, label, statistic) = activity(&actor)?;
+            let note = if *statistic == "stealth" {
+                "Stealth"
+            } else {
+                "Perception"
+            };
+            Some(format!(
+                "<li>{}: {label}, roll {note}</li>",
+                escape_html(&actor.name())
+            ))
+        })
+        .collect();
+    if suggestions.is_empty() {
+        return Ok(());
+    }
+    let scouting = combat.combatants().into_iter().any(|combatant| {
+        combatant
+            .actor()
+            .and_then(|actor| activity(&actor))
+            .is_some_and(|(slug, _, _)| *slug == "scout")
+    });
+    let scout_note = if scouting {
+        "<p>Someone was scouting, so allies get a +1 circumstance bonus to initiative.</p>"
+    } else {
+        ""
+    };
+
+    Message::create_whisper(
+        &format!(
+            r#"<div class="johnys-exploration-initiative">
+                <p><strong>Initiative from exploration activities</strong></p>
+                <ul>{}</ul>
+                {scout_note}
+                <button type="button" data-johnys-initiative><i class="fa-solid fa-dice-d20"></i> Use suggested skills</button>
+            </div>"#,
+            suggestions.join("")
+        ),
+        &game.user()?.id().into_iter().collect::<Vec<_>>(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Compare an Avoid Notice character's Stealth initiative against each enemy's Perception DC
+fn stealth_result(combat: &Combat, combatant: &Combatant) -> Option<String> {
+    let actor = combatant.actor()?;
+    if activity(&actor).map(|(slug, _, _)| *slug) != Some("avoid-notice")
+        || actor.initiative_statistic().as_deref() != Some("stealth")
+    {
+        return None;
+    }
+    let stealth = combatant.initiative()?;
+
+    let mut undetected = Vec::new();
+    let mut noticed = Vec::new();
+    for enemy in combat.combatants() {
+        let is_hostile = enemy
+            .token()
+            .and_then(|token| token.disposition())
+            .is_some_and(|disposition| disposition == -1.0);
+        if !is_hostile {
+            continue;
+        }
+        let Some(dc) = enemy
+            .actor()
+            .and_then(|actor| actor.perception_modifier())
+            .map(|modifier| 10.0 + modifier)
+        else {
+            continue;
+        };
+        // beating an enemy's Perception DC leaves you undetected by it
+        let entry = format!("{} (DC {dc})", escape_html(&enemy.name()));
+        if stealth > dc {
+            undetected.push(entry);
+        } else {
+            noticed.push(entry);
+        }
+    }
+    if undetected.is_empty() && noticed.is_empty() {
+        return None;
+    }
+
+    let mut result = format!(
+        "<li><strong>{}</strong> (Stealth {stealth})",
+        escape_html(&actor.name())
+    );
+    if !undetected.is_empty() {
+        result.push_str(&format!("<br>Undetected by: {}", undetected.join(", ")));
+    }
+    if !noticed.is_empty() {
+        result.push_str(&format!("<br>Noticed by: {}", noticed.join(", ")));
+    }
+    result.push_str("</li>");
+    Some(result)
+}
+
+/// Tell the GM which enemies notice the given Avoid Notice characters
+async fn whisper_stealth_results(
+    game: &Game,
+    combat: &Combat,
+    combatants: &[Combatant],
+) -> Result<(), Error> {
+    let results: Vec<String> = combatants
+        .iter()
+        .filter_map(|combatant| stealth_result(combat, combatant))
+        .collect();
+    if results.is_empty() {
+        return Ok(());
+    }
+    Message::create_whisper(
+        &format!(
+            r#"<div class="johnys-avoid-notice">
+                <p><strong>Avoid Notice</strong></p>
+                <ul>{}</ul>
+            </div>"#,
+            results.join("")
+        ),
+        &game.user()?.id().into_iter().collect::<Vec<_>>(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Compare the Stealth rolls already made when the combat starts
+async fn compare_stealth(combat: &Combat) -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !is_responsible_gm(&game)? {
+        return Ok(());
+    }
+    whisper_stealth_results(&game, combat, &combat.combatants()).await
+}
+
+/// Compare a Stealth roll made after the combat started
+async fn compare_late_stealth(combatant: Combatant, changes: JsValue) -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !is_responsible_gm(&game)? {
+        return Ok(());
+    }
+    let rolled = get_property(&changes, "initiative").is_ok_and(|value| !value.is_undefined());
+    let Some(combat) = game.combat() else {
+        return Ok(());
+    };
+    if !rolled || combat.round() < 1.0 {
+        return Ok(());
+    }
+    whisper_stealth_results(&game, &combat, &[combatant]).await
+}
+
+/// Set each combatant's initiative statistic from their exploration activity
+async fn apply_suggestions() -> Result<(), Error> {
+    let combat = Game::instance()?.combat().ctx("no active combat")?;
+    for actor in combat
+        .combatants()
+        .into_iter()
+        .filter_map(|combatant| combatant.actor())
+    {
+        if let Some((_, _, statistic)) = activity(&actor) {
+            actor.set_initiative_statistic(statistic).await?;
+        }
+    }
+    UI::notify_info("Initiative skills set from exploration activities.");
+    Ok(())
+}
+
+/// Let the GM apply the suggestions from the initiative message
+fn wire_apply_button(html: HtmlElement) -> Result<(), Error> {
+    let Some(button) = html.query_selector("button[data-johnys-initiative]")? else {
+        return Ok(());
+    };
+    let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = apply_suggestions().await {
+                cprintln!("Error applying initiative skills: {err}");
+                UI::notify_error(&format!("Unable to set initiative skills: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.openExplorationActivities()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening exploration activities: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openExplorationActivities", &open_fn.into_js_value());
+    });
+
+    hook!("updateActor", async |_actor: JsValue| {
+        if let Err(err) = application::SingletonWindow::refresh(&WINDOW).await {
+            cprintln!("Error refreshing exploration activities: {err}");
+        }
+    });
+
+    hook!("combatStart", async |combat: JsValue| {
+        let combat = Combat::from(combat);
+        if let Err(err) = suggest_initiative(&combat).await {
+            cprintln!("Error suggesting initiative skills: {err}");
+        }
+        if let Err(err) = compare_stealth(&combat).await {
+            cprintln!("Error comparing Stealth against Perception: {err}");
+        }
+    });
+
+    hook!(
+        "updateCombatant",
+        async |combatant: JsValue, changes: JsValue| {
+            if let Err(err) = compare_late_stealth(combatant.into(), changes).await {
+                cprintln!("Error comparing Stealth against Perception: {err}");
+            }
+        }
+    );
+
+    hook!(
+        "renderChatMessageHTML",
+        |_message: JsValue, html: JsValue| {
+            if let Err(err) = wire_apply_button(HtmlElement::from(html)) {
+                cprintln!("Error wiring initiative button: {err}");
+            }
+        }
+    );
+}
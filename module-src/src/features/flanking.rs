@@ -0,0 +1,140 @@
+use crate::features::geometry::{orientation, segments_cross, Point};
+use crate::features::is_enabled;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// Check if the line between two flankers passes through opposite sides or corners of the target's space
+fn on_opposite_sides(a: Point, b: Point, (left, top, right, bottom): (f64, f64, f64, f64)) -> bool {
+    let line = (a, b);
+    let corners = [(left, top), (right, top), (right, bottom), (left, bottom)];
+    // a line running along one edge touches both ends of the sides beside it without entering the space
+    let sides: Vec<f64> = corners
+        .iter()
+        .map(|corner| orientation(a, b, *corner))
+        .collect();
+    let enters = sides.iter().any(|side| *side > 0.0) && sides.iter().any(|side| *side < 0.0);
+    let crosses = |from: Point, to: Point| segments_cross(line, (from, to));
+    enters
+        && ((crosses((left, top), (left, bottom)) && crosses((right, top), (right, bottom)))
+            || (crosses((left, top), (right, top)) && crosses((left, bottom), (right, bottom))))
+}
+
+/// Check if the token's creature can act and has the target in reach without a wall in the way
+fn threatens(token: &Token, target: &Token) -> bool {
+    let Some(actor) = token.actor() else {
+        return false;
+    };
+    if actor.hit_points().is_some_and(|hp| hp <= 0.0)
+        || actor.condition_value("unconscious").is_some()
+    {
+        return false;
+    }
+    let in_reach = token
+        .distance_to(target)
+        .is_some_and(|distance| distance <= actor.attack_reach());
+    let blocked = match (token.center(), target.center()) {
+        (Some(from), Some(to)) => Game::wall_between(from, to, "move"),
+        _ => true,
+    };
+    in_reach && !blocked
+}
+
+/// Remind the user that their target is off-guard when their token and an ally flank it
+fn check_flanking(user: User, target: Token, targeted: bool) -> Result<(), Error> {
+    if !targeted || !is_enabled("flankingReminders") {
+        return Ok(());
+    }
+    let game = Game::instance()?;
+    if user.id() != game.user()?.id() || game.combat().is_none() {
+        return Ok(());
+    }
+    let Some(attacker) = game.controlled_tokens().into_iter().next() else {
+        return Ok(());
+    };
+    if attacker.id() == target.id() || !threatens(&attacker, &target) {
+        return Ok(());
+    }
+    let (Some(from), Some(bounds)) = (attacker.center(), target.bounds()) else {
+        return Ok(());
+    };
+    let disposition = attacker.disposition();
+    let Some(ally) = game.scene_tokens().into_iter().find(|token| {
+        token.id() != attacker.id()
+            && token.id() != target.id()
+            && token.disposition() == disposition
+            && threatens(token, &target)
+            && token
+                .center()
+                .is_some_and(|to| on_opposite_sides(from, to, bounds))
+    }) else {
+        return Ok(());
+    };
+
+    let creature = target.actor().ctx("target actor")?;
+    let ally_name = ally.name().unwrap_or_default();
+    let ac = creature.armor_class().ctx("target AC")?;
+    if creature.condition_value("off-guard").is_some() {
+        UI::notify_info(&format!(
+            "{} is flanked by you and {ally_name}, and already off-guard (AC {ac}).",
+            creature.name()
+        ));
+    } else {
+        UI::notify_info(&format!(
+            "{} is flanked by you and {ally_name}: off-guard, AC {ac} → {}.",
+            creature.name(),
+            ac - 2.0
+        ));
+    }
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Flanking Reminders")
+            .hint("During combat, when you target a creature that your token and an ally flank, remind you that it's off-guard and show its adjusted AC.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "flankingReminders");
+    });
+
+    hook!("targetToken", |user: JsValue,
+                          token: JsValue,
+                          targeted: JsValue| {
+        if let Err(err) = check_flanking(user.into(), token.into(), targeted.is_truthy()) {
+            cprintln!("Error checking flanking: {err}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: (f64, f64, f64, f64) = (0.0, 0.0, 1.0, 1.0);
+
+    #[test]
+    fn opposite_sides_flank() {
+        assert!(on_opposite_sides((-1.0, 0.5), (2.0, 0.5), TARGET));
+    }
+
+    #[test]
+    fn opposite_corners_flank() {
+        // the line runs exactly through the top left and bottom right corners
+        assert!(on_opposite_sides((-1.0, -1.0), (2.0, 2.0), TARGET));
+    }
+
+    #[test]
+    fn adjacent_sides_dont_flank() {
+        assert!(!on_opposite_sides((-1.0, 1.5), (1.5, -1.0), TARGET));
+    }
+
+    #[test]
+    fn line_along_an_edge_doesnt_flank() {
+        assert!(!on_opposite_sides((0.0, -1.0), (0.0, 2.0), TARGET));
+    }
+}
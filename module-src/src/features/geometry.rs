@@ -0,0 +1,61 @@
+/// A point on the canvas
+pub(super) type Point = (f64, f64);
+
+/// Which side of the line from `a` to `b` the point is on
+pub(super) fn orientation(a: Point, b: Point, point: Point) -> f64 {
+    (b.0 - a.0) * (point.1 - a.1) - (b.1 - a.1) * (point.0 - a.0)
+}
+
+/// Check if two line segments cross each other, counting an end touching the other segment
+///
+/// Segments lying along the same line don't count, since neither passes through the other.
+pub(super) fn segments_cross((a1, a2): (Point, Point), (b1, b2): (Point, Point)) -> bool {
+    let (b1_side, b2_side) = (orientation(a1, a2, b1), orientation(a1, a2, b2));
+    let (a1_side, a2_side) = (orientation(b1, b2, a1), orientation(b1, b2, a2));
+    let collinear = b1_side == 0.0 && b2_side == 0.0;
+    !collinear && b1_side * b2_side <= 0.0 && a1_side * a2_side <= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orientation_sides() {
+        assert!(orientation((0.0, 0.0), (1.0, 0.0), (0.5, 1.0)) > 0.0);
+        assert!(orientation((0.0, 0.0), (1.0, 0.0), (0.5, -1.0)) < 0.0);
+        assert_eq!(orientation((0.0, 0.0), (1.0, 0.0), (2.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn crossing_segments() {
+        assert!(segments_cross(
+            ((0.0, 0.0), (2.0, 2.0)),
+            ((0.0, 2.0), (2.0, 0.0))
+        ));
+    }
+
+    #[test]
+    fn separate_segments() {
+        assert!(!segments_cross(
+            ((0.0, 0.0), (1.0, 0.0)),
+            ((0.0, 1.0), (1.0, 1.0))
+        ));
+    }
+
+    #[test]
+    fn segment_ending_on_another() {
+        assert!(segments_cross(
+            ((0.0, 0.0), (1.0, 1.0)),
+            ((1.0, 0.0), (1.0, 2.0))
+        ));
+    }
+
+    #[test]
+    fn collinear_segments() {
+        assert!(!segments_cross(
+            ((0.0, 0.0), (1.0, 0.0)),
+            ((0.5, 0.0), (2.0, 0.0))
+        ));
+    }
+}
@@ -0,0 +1,386 @@
+use crate::features::{player_characters, register_api};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Skills a group check can call for, as slug and label
+const SKILLS: [(&str, &str); 16] = [
+    ("acrobatics", "Acrobatics"),
+    ("arcana", "Arcana"),
+    ("athletics", "Athletics"),
+    ("crafting", "Crafting"),
+    ("deception", "Deception"),
+    ("diplomacy", "Diplomacy"),
+    ("intimidation", "Intimidation"),
+    ("medicine", "Medicine"),
+    ("nature", "Nature"),
+    ("occultism", "Occultism"),
+    ("performance", "Performance"),
+    ("religion", "Religion"),
+    ("society", "Society"),
+    ("stealth", "Stealth"),
+    ("survival", "Survival"),
+    ("thievery", "Thievery"),
+];
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum GroupCheckMessage {
+    /// Ask the owners of the listed characters to roll
+    #[serde(rename_all = "camelCase")]
+    GroupCheckRequest {
+        message_id: String,
+        skill: String,
+        dc: f64,
+        actor_uuids: Vec<String>,
+    },
+    /// A character's result, for the GM collating them
+    #[serde(rename_all = "camelCase")]
+    GroupCheckResult {
+        message_id: String,
+        actor_uuid: String,
+        total: f64,
+        degree: String,
+    },
+}
+
+/// A group check this GM requested, waiting on results
+struct GroupCheck {
+    label: &'static str,
+    dc: f64,
+    rollers: Vec<Roller>,
+}
+
+/// A character asked to roll, with their total and degree of success once they have
+struct Roller {
+    uuid: String,
+    name: String,
+    result: Option<(f64, DegreeOfSuccess)>,
+}
+
+thread_local! {
+    /// Group checks requested by this client, by results card message ID
+    static CHECKS: RefCell<HashMap<String, GroupCheck>> = RefCell::new(HashMap::new());
+}
+
+fn skill_label(skill: &str) -> Option<&'static str> {
+    SKILLS
+        .iter()
+        .find(|(slug, _)| *slug == skill)
+        .map(|(_, label)| *label)
+}
+
+/// The results card: who's rolled, their results, and how many succeeded
+fn render_card(check: &GroupCheck) -> String {
+    let rows: String = check
+        .rollers
+        .iter()
+        .map(|Roller { name, result, .. }| match result {
+            Some((total, degree)) => format!(
+                r#"<li><strong>{name}</strong> {total} <span class="johnys-degree-badge {}">{}</span></li>"#,
+                degree.slug(),
+                degree.label()
+            ),
+            None => format!(r#"<li><strong>{name}</strong> <em>waiting…</em></li>"#),
+        })
+        .collect();
+    let rolled: Vec<DegreeOfSuccess> = check
+        .rollers
+        .iter()
+        .filter_map(|roller| roller.result.map(|(_, degree)| degree))
+        .collect();
+    let successes = rolled
+        .iter()
+        .filter(|degree| {
+            matches!(
+                degree,
+                DegreeOfSuccess::Success | DegreeOfSuccess::CriticalSuccess
+            )
+        })
+        .count();
+    format!(
+        r#"<div class="johnys-group-check">
+            <p><strong>Group {label} check, DC {dc}</strong></p>
+            <ul>{rows}</ul>
+            <p>{successes} succeeded, {failures} failed, {waiting} still to roll</p>
+        </div>"#,
+        label = check.label,
+        dc = check.dc,
+        failures = rolled.len() - successes,
+        waiting = check.rollers.len() - rolled.len(),
+    )
+}
+
+/// Post the results card and ask each chosen character's owner to roll
+async fn request(form: HtmlElement) -> Result<(), Error> {
+    let skill = form
+        .query_selector("[name=skill]")?
+        .and_then(|select| select.value())
+        .ctx("Choose a skill")?;
+    let label = skill_label(&skill).ctx("Choose a skill")?;
+    let dc = form
+        .query_selector("[name=dc]")?
+        .and_then(|input| input.value())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|dc| *dc > 0.0)
+        .ctx("Enter a DC")?;
+    let chosen: Vec<String> = form
+        .query_selector_all("[name=actor]")
+        .into_iter()
+        .filter(|checkbox| checkbox.is_checked())
+        .filter_map(|checkbox| checkbox.value())
+        .collect();
+    let rollers: Vec<Roller> = player_characters()?
+        .into_iter()
+        .filter_map(|actor| {
+            Some(Roller {
+                uuid: actor.uuid().filter(|uuid| chosen.contains(uuid))?,
+                name: actor.name(),
+                result: None,
+            })
+        })
+        .collect();
+    if rollers.is_empty() {
+        return Err("Choose who should roll".into());
+    }
+    let actor_uuids: Vec<String> = rollers.iter().map(|roller| roller.uuid.clone()).collect();
+
+    let game = Game::instance()?;
+    let check = GroupCheck { label, dc, rollers };
+    let message = Message::create_whisper(
+        &render_card(&check),
+        &game.user()?.id().into_iter().collect::<Vec<_>>(),
+    )
+    .await?;
+    let message_id = message.id();
+    CHECKS.with_borrow_mut(|checks| checks.insert(message_id.clone(), check));
+
+    socket::emit(
+        ID,
+        &GroupCheckMessage::GroupCheckRequest {
+            message_id: message_id.clone(),
+            skill: skill.clone(),
+            dc,
+            actor_uuids: actor_uuids.clone(),
+        },
+    )?;
+    // the socket doesn't echo back to the sender, so prompt for the GM's own characters here
+    prompt(message_id, skill, dc, actor_uuids).await
+}
+
+/// Show a roll button for each requested character this user rolls for
+async fn prompt(
+    message_id: String,
+    skill: String,
+    dc: f64,
+    actor_uuids: Vec<String>,
+) -> Result<(), Error> {
+    let label = skill_label(&skill).ctx("group check skill")?;
+    for uuid in actor_uuids {
+        let actor: Actor = Game::from_uuid(&uuid).await?;
+        if !actor.is_owned_by_current_user(GMStrategy::IfNoPlayers, OwnershipLevel::Owner) {
+            continue;
+        }
+        let name = actor.name();
+        let message_id = message_id.clone();
+        let skill = skill.clone();
+        let roll_fn = Closure::wrap(Box::new(move |_html: JsValue| {
+            let actor = Actor::from(actor.as_js_value().clone());
+            let message_id = message_id.clone();
+            let skill = skill.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = roll(actor, message_id, skill, dc).await {
+                    cprintln!("Error rolling group check: {err}");
+                    UI::notify_error(&format!("Unable to roll the group check: {err}"));
+                }
+            });
+        }) as Box<dyn Fn(JsValue)>);
+
+        application::show_dialog(
+            "Group Check",
+            format!(
+                r#"<p class="johnys-group-check-prompt">The GM asks <strong>{name}</strong> for a <strong>{label}</strong> check, DC {dc}.</p>"#
+            ),
+            vec![
+                (
+                    "roll",
+                    "Roll",
+                    Some(roll_fn.into_js_value().unchecked_into()),
+                ),
+                ("cancel", "Skip", None),
+            ],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Roll the character's check and send the result to the GM collating them
+async fn roll(actor: Actor, message_id: String, skill: String, dc: f64) -> Result<(), Error> {
+    let Some(check) = actor.roll_skill(&skill, dc, None).await? else {
+        return Ok(());
+    };
+    let degree = check
+        .degree_of_success()
+        .ctx("group check degree of success")?;
+    let actor_uuid = actor.uuid().ctx("actor UUID")?;
+    let total = check.total();
+    if CHECKS.with_borrow(|checks| checks.contains_key(&message_id)) {
+        return record(message_id, actor_uuid, total, degree).await;
+    }
+    socket::emit(
+        ID,
+        &GroupCheckMessage::GroupCheckResult {
+            message_id,
+            actor_uuid,
+            total,
+            degree: degree.to_pf2e().to_string(),
+        },
+    )
+}
+
+/// Add a character's result to the card
+async fn record(
+    message_id: String,
+    actor_uuid: String,
+    total: f64,
+    degree: DegreeOfSuccess,
+) -> Result<(), Error> {
+    let Some(content) = CHECKS.with_borrow_mut(|checks| {
+        let check = checks.get_mut(&message_id)?;
+        let roller = check
+            .rollers
+            .iter_mut()
+            .find(|roller| roller.uuid == actor_uuid)?;
+        roller.result = Some((total, degree));
+        Some(render_card(check))
+    }) else {
+        return Ok(());
+    };
+    let message = Game::instance()?
+        .get_message(&message_id)?
+        .ctx("The group check card was deleted")?;
+    message.set_content(&content).await
+}
+
+async fn receive(data: JsValue) -> Result<(), Error> {
+    match serde_wasm_bindgen::from_value(data) {
+        Ok(GroupCheckMessage::GroupCheckRequest {
+            message_id,
+            skill,
+            dc,
+            actor_uuids,
+        }) => prompt(message_id, skill, dc, actor_uuids).await?,
+        Ok(GroupCheckMessage::GroupCheckResult {
+            message_id,
+            actor_uuid,
+            total,
+            degree,
+        }) => {
+            if let Some(degree) = DegreeOfSuccess::from_pf2e(&degree) {
+                record(message_id, actor_uuid, total, degree).await?;
+            }
+        }
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+/// Ask which skill, the DC, and who rolls: the selected characters, or else the whole party
+async fn open() -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !game.user()?.is_gm() {
+        return Err("Only the GM can request group checks".into());
+    }
+    let characters = player_characters()?;
+    if characters.is_empty() {
+        return Err("There are no player characters to roll".into());
+    }
+    let selected: Vec<String> = game
+        .controlled_tokens()
+        .into_iter()
+        .filter_map(|token| token.actor()?.uuid())
+        .collect();
+    let any_selected = characters
+        .iter()
+        .any(|actor| actor.uuid().is_some_and(|uuid| selected.contains(&uuid)));
+    let actors: String = characters
+        .iter()
+        .filter_map(|actor| {
+            let uuid = actor.uuid()?;
+            let checked = if !any_selected || selected.contains(&uuid) {
+                " checked"
+            } else {
+                ""
+            };
+            Some(format!(
+                r#"<label class="group-check-actor"><input type="checkbox" name="actor" value="{uuid}"{checked}> {name}</label>"#,
+                name = actor.name(),
+            ))
+        })
+        .collect();
+    let skills: String = SKILLS
+        .iter()
+        .map(|(slug, label)| format!(r#"<option value="{slug}">{label}</option>"#))
+        .collect();
+
+    let request_fn = Closure::wrap(Box::new(|html: JsValue| {
+        let form = HtmlElement::from(html);
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = request(form).await {
+                cprintln!("Error requesting group check: {err}");
+                UI::notify_error(&err.to_string());
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+
+    application::show_dialog(
+        "Group Check",
+        format!(
+            r#"<div class="form-group"><label>Skill</label><select name="skill">{skills}</select></div>
+            <div class="form-group"><label>DC</label><input type="number" name="dc" min="0" value="15"></div>
+            <div class="johnys-group-check-actors">{actors}</div>"#
+        ),
+        vec![
+            (
+                "request",
+                "Request",
+                Some(request_fn.into_js_value().unchecked_into()),
+            ),
+            ("cancel", "Cancel", None),
+        ],
+    )
+    .await
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.requestGroupCheck()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening group check: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("requestGroupCheck", &open_fn.into_js_value());
+    });
+
+    hook!("ready", || {
+        let result = socket::on(ID, |data| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = receive(data).await {
+                    cprintln!("Error handling group check: {err}");
+                }
+            });
+        });
+        if let Err(err) = result {
+            cprintln!("Error listening for group checks: {err}");
+        }
+    });
+}
@@ -0,0 +1,294 @@
+use crate::features::{
+    is_enabled, is_responsible_gm, player_characters, register_api, setting_number,
+};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use gloo_timers::future::TimeoutFuture;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+}
+
+/// Open the tracker, or refresh it if it's already open
+async fn open() -> Result<(), Error> {
+    if !Game::instance()?.user()?.is_gm() {
+        return Err("Only the GM can track hero points".into());
+    }
+    application::SingletonWindow::open(&WINDOW, || {
+        application::WindowBuilder::new("johnys-hero-points", "Hero Points")
+            .icon("fa-solid fa-star")
+            .classes(&["johnys-hero-points"])
+            .position(&ApplicationPosition {
+                width: Some(280.0),
+                ..Default::default()
+            })
+            .render(render_rows)
+            .control(
+                "johnysSessionRefresh",
+                "New Session: Reset to 1",
+                "fa-solid fa-rotate",
+                || spawn_update(refresh_all(RefreshKind::Session)),
+            )
+            .control(
+                "johnysAwardAll",
+                "Award Everyone a Hero Point",
+                "fa-solid fa-hourglass-half",
+                || spawn_update(refresh_all(RefreshKind::Award)),
+            )
+    })
+    .await
+}
+
+fn spawn_update(update: impl std::future::Future<Output = Result<(), Error>> + 'static) {
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = update.await {
+            cprintln!("Error updating hero points: {err}");
+            UI::notify_error(&format!("Unable to update hero points: {err}"));
+        }
+    });
+}
+
+/// List each player character with buttons to adjust their hero points
+async fn render_rows() -> Result<JsValue, Error> {
+    let rows: String = player_characters()?
+        .iter()
+        .filter_map(|actor| {
+            let (value, max) = actor.hero_points()?;
+            let uuid = actor.uuid()?;
+            Some(format!(
+                r#"<li class="johnys-row">
+                    <span class="hero-points-name">{name}</span>
+                    <a data-hero-delta="-1" data-actor-uuid="{uuid}" data-tooltip="Spend"><i class="fa-solid fa-minus"></i></a>
+                    <span class="hero-points-value">{value} / {max}</span>
+                    <a data-hero-delta="1" data-actor-uuid="{uuid}" data-tooltip="Award"><i class="fa-solid fa-plus"></i></a>
+                </li>"#,
+                name = escape_html(&actor.name()),
+            ))
+        })
+        .collect();
+
+    let container = Document::create_element("ol")?;
+    container.set_class_name("johnys-rows")?;
+    if rows.is_empty() {
+        container.insert_adjacent_html(
+            "afterbegin",
+            r#"<li class="johnys-empty">No player characters.</li>"#,
+        )?;
+    } else {
+        container.insert_adjacent_html("afterbegin", &rows)?;
+    }
+
+    let adjust_fn = Closure::wrap(Box::new(|event: JsValue| {
+        let Some(button) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("[data-hero-delta]"))
+        else {
+            return;
+        };
+        spawn_update(adjust(button));
+    }) as Box<dyn Fn(JsValue)>);
+    container.add_event_listener("click", &adjust_fn)?;
+    adjust_fn.forget();
+
+    Ok(container.as_js_value().clone())
+}
+
+/// Add or spend a hero point for the character on the clicked row
+async fn adjust(button: HtmlElement) -> Result<(), Error> {
+    let delta: f64 = button
+        .get_attribute("data-hero-delta")
+        .and_then(|delta| delta.parse().ok())
+        .ctx("hero point change")?;
+    let uuid = button
+        .get_attribute("data-actor-uuid")
+        .ctx("hero point actor")?;
+    let actor: Actor = Game::from_uuid(&uuid).await?;
+    let (value, max) = actor.hero_points().ctx("actor has no hero points")?;
+    actor.set_hero_points((value + delta).clamp(0.0, max)).await
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RefreshKind {
+    /// Everyone starts a session with 1 hero point
+    Session,
+    /// Hand out a hero point to everyone, e.g. each hour of play
+    Award,
+}
+
+async fn refresh_all(kind: RefreshKind) -> Result<(), Error> {
+    for actor in player_characters()? {
+        let Some((value, max)) = actor.hero_points() else {
+            continue;
+        };
+        let new_value = match kind {
+            RefreshKind::Session => 1.0_f64.min(max),
+            RefreshKind::Award => (value + 1.0).min(max),
+        };
+        if new_value != value {
+            actor.set_hero_points(new_value).await?;
+        }
+    }
+    let message = match kind {
+        RefreshKind::Session => "Everyone's hero points were reset to 1 for the new session.",
+        RefreshKind::Award => "Everyone was awarded a hero point.",
+    };
+    UI::notify_info(message);
+    Ok(())
+}
+
+/// Add a reroll button to a failed check made by a character you own who has a hero point left
+fn inject_reroll_button(message: Message, html: HtmlElement) -> Result<(), Error> {
+    if !is_enabled("heroPointRerollButtons") {
+        return Ok(());
+    }
+    let Some(context) = message.pf2e_context() else {
+        return Ok(());
+    };
+    let failed = matches!(
+        context.outcome(),
+        Some(DegreeOfSuccess::Failure | DegreeOfSuccess::CriticalFailure)
+    );
+    if !failed || context.is_reroll() || context.roll_type().as_deref() == Some("damage-roll") {
+        return Ok(());
+    }
+    let Some(actor) = message.speaker_actor() else {
+        return Ok(());
+    };
+    if !actor.is_owned_by_current_user(GMStrategy::IfNoPlayers, OwnershipLevel::Owner)
+        || !actor.hero_points().is_some_and(|(value, _)| value > 0.0)
+    {
+        return Ok(());
+    }
+    let content = html
+        .query_selector(".message-content")?
+        .ctx("message content")?;
+    content.insert_adjacent_html(
+        "beforeend",
+        r#"<button type="button" class="johnys-hero-reroll"><i class="fa-solid fa-hospital-symbol"></i> Spend Hero Point to Reroll</button>"#,
+    )?;
+    let button = content
+        .query_selector("button.johnys-hero-reroll")?
+        .ctx("reroll button")?;
+
+    let button_js = button.as_js_value().clone();
+    let message_js = message.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+        let button = HtmlElement::from(button_js.clone());
+        let message = Message::from(message_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            button.set_attribute("disabled", "true").ok();
+            if let Err(err) = reroll(&message).await {
+                cprintln!("Error rerolling with a hero point: {err}");
+                UI::notify_error(&format!("Unable to reroll: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+/// Spend one of the speaker's hero points and reroll the check
+///
+/// PF2e's hero point reroll takes the point itself, the same as rerolling from the chat card's menu.
+async fn reroll(message: &Message) -> Result<(), Error> {
+    let actor = message.speaker_actor().ctx("reroll actor")?;
+    if !actor.hero_points().is_some_and(|(value, _)| value >= 1.0) {
+        return Err(format!("{} has no hero points left", actor.name()).into());
+    }
+    message.reroll_with_hero_point().await
+}
+
+/// Periodically remind the GM to hand out hero points
+async fn remind() {
+    loop {
+        let minutes = setting_number("heroPointReminderMinutes");
+        if minutes <= 0.0 {
+            // check again later in case it's turned on
+            TimeoutFuture::new(60_000).await;
+            continue;
+        }
+        TimeoutFuture::new((minutes * 60_000.0) as u32).await;
+        // the setting may have been turned off while waiting
+        if setting_number("heroPointReminderMinutes") > 0.0 {
+            UI::notify_info(&format!(
+                "Another {minutes} minutes have passed. Consider awarding hero points."
+            ));
+        }
+    }
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Hero Point Reminder (minutes)")
+            .hint("Remind the GM to award hero points at this interval. Set to 0 to turn off. Open the tracker with game.modules.get(\"johnys-module\").api.openHeroPoints().")
+            .scope("world")
+            .config(true)
+            .type_number()
+            .range(0.0, 120.0, 5.0)
+            .default_number(0.0)
+            .register(ID, "heroPointReminderMinutes");
+
+        SettingConfig::new()
+            .name("Hero Point Reroll Buttons")
+            .hint("Add a button to your characters' failed checks in chat that spends a hero point and rerolls the check.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "heroPointRerollButtons");
+
+        SettingsMenu::new()
+            .name("Hero Points")
+            .label("Open Tracker")
+            .hint("List each player character's hero points with buttons to award or spend them, or refresh them for a new session.")
+            .icon("fa-solid fa-star")
+            .restricted(true)
+            .on_open(|| {
+                wasm_bindgen_futures::spawn_local(async {
+                    if let Err(err) = open().await {
+                        cprintln!("Error opening hero points: {err}");
+                    }
+                });
+            })
+            .register(ID, "heroPointsMenu");
+
+        // game.modules.get("johnys-module").api.openHeroPoints()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening hero points: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openHeroPoints", &open_fn.into_js_value());
+    });
+
+    hook!("ready", || {
+        let is_gm = Game::instance()
+            .and_then(|game| is_responsible_gm(&game))
+            .unwrap_or_default();
+        if is_gm {
+            wasm_bindgen_futures::spawn_local(remind());
+        }
+    });
+
+    hook!("updateActor", async |_actor: JsValue| {
+        if let Err(err) = application::SingletonWindow::refresh(&WINDOW).await {
+            cprintln!("Error refreshing hero points: {err}");
+        }
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        |message: JsValue, html: JsValue| {
+            if let Err(err) = inject_reroll_button(message.into(), html.into()) {
+                cprintln!("Error adding hero point reroll button: {err}");
+            }
+        }
+    );
+}
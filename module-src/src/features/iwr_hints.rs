@@ -0,0 +1,157 @@
+use crate::features::dedup_uuids;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+const PHYSICAL: [&str; 3] = ["bludgeoning", "piercing", "slashing"];
+const ENERGY: [&str; 8] = [
+    "acid",
+    "cold",
+    "electricity",
+    "fire",
+    "force",
+    "sonic",
+    "vitality",
+    "void",
+];
+
+/// Check if this user should see the targets' immunities, weaknesses, and resistances
+fn hints_visible() -> bool {
+    let is_gm = Game::instance().is_ok_and(|game| game.user().is_ok_and(|user| user.is_gm()));
+    match get_setting(ID, "iwrHints").as_string().as_deref() {
+        Some("everyone") => true,
+        Some("gm") => is_gm,
+        _ => false,
+    }
+}
+
+/// The IWR types a damage roll can run into: its damage types, their categories, and extras
+/// like precision
+fn damage_types(message: &Message, roll: &Roll) -> Vec<String> {
+    let mut types = vec!["all-damage".to_string()];
+    for instance in roll.damage_instances() {
+        let kind = instance.damage_type();
+        if PHYSICAL.contains(&kind.as_str()) {
+            types.push("physical".to_string());
+        }
+        if ENERGY.contains(&kind.as_str()) {
+            types.push("energy".to_string());
+        }
+        if instance.is_persistent() {
+            types.push("persistent-damage".to_string());
+        }
+        if instance.component_total("precision") > 0.0 {
+            types.push("precision".to_string());
+        }
+        if instance.component_total("splash") > 0.0 {
+            types.push("splash-damage".to_string());
+        }
+        types.push(kind);
+    }
+    let critical = message.pf2e_context().and_then(|context| context.outcome())
+        == Some(DegreeOfSuccess::CriticalSuccess);
+    if critical {
+        types.push("critical-hits".to_string());
+    }
+    types.sort_unstable();
+    types.dedup();
+    types
+}
+
+/// The creature's immunities, weaknesses, and resistances that apply to the damage
+fn relevant_iwr(actor: &Actor, types: &[String]) -> Vec<String> {
+    // weaknesses and resistances read like "fire 5"
+    let applies = |entry: &String| {
+        let kind = entry
+            .rsplit_once(' ')
+            .map_or(entry.as_str(), |(kind, _)| kind);
+        types.iter().any(|t| t == kind)
+    };
+    let mut hints = Vec::new();
+    for (label, entries) in [
+        ("Immune", actor.immunities()),
+        ("Weak", actor.weaknesses_or_resistances("weaknesses")),
+        ("Resists", actor.weaknesses_or_resistances("resistances")),
+    ] {
+        let entries: Vec<String> = entries.into_iter().filter(applies).collect();
+        if !entries.is_empty() {
+            hints.push(format!(
+                r#"<span class="iwr-{kind}">{label} {}</span>"#,
+                entries.join(", "),
+                kind = label.to_lowercase(),
+            ));
+        }
+    }
+    hints
+}
+
+/// Note each target's immunities, weaknesses, and resistances against the rolled damage types
+async fn annotate_damage(message: Message, html: HtmlElement) -> Result<(), Error> {
+    if message.pf2e_type().as_deref() != Some("damage-roll") || !hints_visible() {
+        return Ok(());
+    }
+    let Some(roll) = message.first_roll() else {
+        return Ok(());
+    };
+    if roll.is_healing() {
+        return Ok(());
+    }
+    let types = damage_types(&message, &roll);
+    let mut targets = message.target_uuids().await;
+    if message.is_persistent_damage() {
+        // persistent damage is rolled by the affected actor rather than targeting it
+        targets.extend(message.speaker_actor().and_then(|actor| actor.uuid()));
+    }
+    dedup_uuids(&mut targets);
+
+    let mut rows = String::new();
+    for uuid in targets {
+        let Ok(actor) = Game::from_uuid(&uuid).await else {
+            continue;
+        };
+        let hints = relevant_iwr(&actor, &types);
+        if hints.is_empty() {
+            continue;
+        }
+        rows.push_str(&format!(
+            r#"<div class="johnys-iwr-hint"><strong>{}</strong>: {}</div>"#,
+            actor.name(),
+            hints.join(r#"<span class="separator">·</span>"#)
+        ));
+    }
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let content = html
+        .query_selector(".message-content")?
+        .ctx("message content")?;
+    content.insert_adjacent_html("beforeend", &rows)
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Immunity, Weakness, and Resistance Hints")
+            .hint("Note on damage rolls and their popups which of the targets' immunities, weaknesses, and resistances apply to the damage.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .choices(&[
+                ("off", "Off"),
+                ("gm", "GM only"),
+                ("everyone", "Everyone"),
+            ])
+            .default_string("gm")
+            .register(ID, "iwrHints");
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        async |message: JsValue, html: JsValue| {
+            if let Err(err) = annotate_damage(message.into(), html.into()).await {
+                cprintln!("Error adding IWR hints: {err}");
+            }
+        }
+    );
+}
@@ -0,0 +1,240 @@
+use crate::features::{register_api, treasure};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::hook;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+    /// UUIDs of the actors being looted
+    static SOURCE_UUIDS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Check if the actor's items are up for grabs: a loot actor or a defeated creature
+fn is_lootable(actor: &Actor) -> bool {
+    match actor.actor_type().as_deref() {
+        Some("loot") => true,
+        Some("npc") => actor.hit_points().is_some_and(|hp| hp <= 0.0),
+        _ => false,
+    }
+}
+
+/// Who loot can be handed to: the party stash, if there is one, then each player character
+fn recipients() -> Result<Vec<Actor>, Error> {
+    let (stash, characters): (Vec<Actor>, Vec<Actor>) = Game::instance()?
+        .actors()
+        .into_iter()
+        .filter(|actor| match actor.actor_type().as_deref() {
+            Some("party") => true,
+            Some("character") => actor.has_player_owner(),
+            _ => false,
+        })
+        .partition(|actor| actor.actor_type().as_deref() == Some("party"));
+    Ok(stash.into_iter().chain(characters).collect())
+}
+
+/// Open the loot window for the selected defeated creatures and loot actors
+async fn open() -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !game.user()?.is_gm() {
+        return Err("Only the GM can distribute loot".into());
+    }
+    let sources: Vec<String> = game
+        .controlled_tokens()
+        .into_iter()
+        .filter_map(|token| token.actor())
+        .filter(is_lootable)
+        .filter_map(|actor| actor.uuid())
+        .collect();
+    if sources.is_empty() {
+        return Err("Select defeated creatures or loot actors to distribute their items".into());
+    }
+    SOURCE_UUIDS.with_borrow_mut(|uuids| *uuids = sources);
+    application::SingletonWindow::open(&WINDOW, || {
+        application::WindowBuilder::new("johnys-loot", "Distribute Loot")
+            .icon("fa-solid fa-sack-dollar")
+            .classes(&["johnys-loot"])
+            .position(&ApplicationPosition {
+                width: Some(480.0),
+                ..Default::default()
+            })
+            .render(render_loot)
+    })
+    .await
+}
+
+/// List every item on the looted actors with a choice of who gets it
+async fn render_loot() -> Result<JsValue, Error> {
+    let options: String = recipients()?
+        .iter()
+        .filter_map(|actor| {
+            let name = escape_html(&actor.name());
+            let label = if actor.actor_type().as_deref() == Some("party") {
+                format!("{name} (stash)")
+            } else {
+                name
+            };
+            Some(format!(
+                r#"<option value="{}">{label}</option>"#,
+                actor.uuid()?
+            ))
+        })
+        .collect();
+
+    let mut rows = String::new();
+    for uuid in SOURCE_UUIDS.with_borrow(Clone::clone) {
+        let actor = Game::from_uuid(&uuid).await?;
+        for item in actor.items().iter().filter(|item| item.is_physical_item()) {
+            let Some(id) = item.id() else {
+                continue;
+            };
+            let quantity = item.quantity();
+            let quantity = if quantity == 1.0 {
+                String::new()
+            } else {
+                format!(" ×{quantity}")
+            };
+            rows.push_str(&format!(
+                r#"<li class="johnys-row" data-source-uuid="{uuid}" data-item-id="{id}">
+                    <img src="{img}" width="24" height="24">
+                    <span class="loot-name">{name}{quantity}</span>
+                    <span class="loot-source">{source}</span>
+                    <select name="recipient"><option value="">Leave</option>{options}</select>
+                </li>"#,
+                img = item.img().unwrap_or_default(),
+                name = escape_html(&item.name()),
+                source = escape_html(&actor.name()),
+            ));
+        }
+    }
+    if rows.is_empty() {
+        rows = r#"<li class="johnys-empty">Nothing left to loot.</li>"#.to_string();
+    }
+
+    let container = Document::create_element("div")?;
+    container.set_class_name("loot-distribution")?;
+    container.insert_adjacent_html(
+        "afterbegin",
+        &format!(
+            r#"<ol class="johnys-rows">{rows}</ol>
+            <button type="button" data-loot-action="distribute"><i class="fa-solid fa-people-arrows"></i> Distribute</button>"#
+        ),
+    )?;
+
+    let container_js = container.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |event: JsValue| {
+        let is_distribute = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("[data-loot-action]"))
+            .is_some();
+        if !is_distribute {
+            return;
+        }
+        let container = HtmlElement::from(container_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = distribute(container).await {
+                cprintln!("Error distributing loot: {err}");
+                UI::notify_error(&format!("Unable to distribute loot: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    container.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+
+    Ok(container.as_js_value().clone())
+}
+
+/// Move every assigned item to its new owner and post who got what
+///
+/// Each recipient's items are created in one update and each source's removed in another.
+async fn distribute(container: HtmlElement) -> Result<(), Error> {
+    // recipient UUID to (source UUID, item ID) pairs
+    let mut assignments: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for row in container.query_selector_all(".johnys-row") {
+        let Some(recipient) = row
+            .query_selector("[name=recipient]")?
+            .and_then(|select| select.value())
+            .filter(|value| !value.is_empty())
+        else {
+            continue;
+        };
+        let source = row.get_attribute("data-source-uuid").ctx("loot source")?;
+        let id = row.get_attribute("data-item-id").ctx("loot item")?;
+        assignments.entry(recipient).or_default().push((source, id));
+    }
+    if assignments.is_empty() {
+        return Err("Choose who gets at least one item".into());
+    }
+
+    let mut taken: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut coins = 0.0;
+    let mut summary = String::new();
+    for (recipient_uuid, items) in assignments {
+        let recipient = Game::from_uuid(&recipient_uuid).await?;
+        let mut data = Vec::new();
+        let mut names = Vec::new();
+        for (source_uuid, id) in items {
+            let source = Game::from_uuid(&source_uuid).await?;
+            let Some(item) = source
+                .items()
+                .into_iter()
+                .find(|item| item.id().as_deref() == Some(id.as_str()))
+            else {
+                continue;
+            };
+            if item.is_coins() {
+                coins += item.total_price();
+            }
+            data.push(item.transfer_data()?);
+            let name = escape_html(&item.name());
+            names.push(if item.quantity() == 1.0 {
+                name
+            } else {
+                format!("{name} ×{}", item.quantity())
+            });
+            taken.entry(source_uuid).or_default().push(id);
+        }
+        if data.is_empty() {
+            continue;
+        }
+        recipient.create_items(&data).await?;
+        summary.push_str(&format!(
+            "<li><strong>{}</strong> received {}.</li>",
+            escape_html(&recipient.name()),
+            names.join(", ")
+        ));
+    }
+    for (source_uuid, ids) in taken {
+        Game::from_uuid(&source_uuid)
+            .await?
+            .delete_items(&ids)
+            .await?;
+    }
+    treasure::record(coins).await?;
+
+    Message::create(&format!(
+        r#"<div class="johnys-loot-summary">
+            <p><strong>Loot distributed</strong></p>
+            <ul>{summary}</ul>
+        </div>"#
+    ))
+    .await?;
+    application::SingletonWindow::refresh(&WINDOW).await
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.distributeLoot()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening loot distribution: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("distributeLoot", &open_fn.into_js_value());
+    });
+}
@@ -1,14 +1,214 @@
-use crate::{ID, foundry::get_setting};
+use crate::{
+    foundry::{
+        error::{ContextExt as _, Error},
+        get_setting, Actor, Game, Message,
+    },
+    ID,
+};
+use std::collections::HashSet;
+use wasm_bindgen::JsValue;
 
+pub mod adjustments;
+pub mod ammo;
+pub mod aura_rings;
 pub mod auto_popout;
+pub mod consumables;
+pub mod counteract;
+pub mod cover;
+pub mod crafting;
+pub mod critical_specialization;
+pub mod currency;
+pub mod d20_stats;
+pub mod daily_preparations;
+pub mod damage_stats;
+pub mod damage_workflow;
+pub mod defense_tooltip;
+pub mod degree_highlight;
+pub mod duration_badges;
+pub mod dying;
+pub mod earn_income;
 pub mod equipment_observation;
+pub mod experience;
+pub mod exploration;
+pub mod flanking;
+mod geometry;
+pub mod group_checks;
+pub mod hero_points;
+pub mod iwr_hints;
+pub mod loot;
+pub mod party_overview;
+pub mod persistent_damage;
+pub mod range_penalty;
+pub mod recall_knowledge;
+pub mod refocus;
+pub mod rest;
+pub mod secret_rolls;
+pub mod spell_saves;
+pub mod spell_slots;
+pub mod template_targeting;
+pub mod treasure;
+pub mod treat_wounds;
+pub mod turn_conditions;
+pub mod turn_summary;
 
 fn is_enabled(key: &str) -> bool {
     let value = get_setting(ID, key);
     value.as_bool().unwrap_or(true)
 }
 
+/// Expose a function on `game.modules.get(ID).api` for macros and other modules
+fn register_api(name: &str, function: &JsValue) {
+    let Ok(game) = Game::instance() else {
+        return;
+    };
+    if let Some(module) = game.modules().ok().and_then(|modules| modules.get(ID)) {
+        module.set_api_property(name, function).ok();
+    }
+}
+
+/// Check if this client is the one GM that should act on everyone's behalf
+fn is_responsible_gm(game: &Game) -> Result<bool, Error> {
+    let user = game.user()?;
+    let active_gm = game.users()?.active_gm();
+    Ok(user.is_gm() && active_gm.and_then(|gm| gm.id()) == user.id())
+}
+
+/// The actor the user is acting as: their controlled token's, or else their assigned character
+fn selected_actor() -> Option<Actor> {
+    let game = Game::instance().ok()?;
+    game.controlled_tokens()
+        .into_iter()
+        .find_map(|token| token.actor())
+        .or_else(|| game.user().ok()?.character())
+}
+
+/// Characters played by someone, in the order they appear in the actors directory
+fn player_characters() -> Result<Vec<Actor>, Error> {
+    Ok(Game::instance()?
+        .actors()
+        .into_iter()
+        .filter(|actor| {
+            actor.actor_type().as_deref() == Some("character") && actor.has_player_owner()
+        })
+        .collect())
+}
+
+/// Drop repeated UUIDs, keeping the first of each in order
+fn dedup_uuids(uuids: &mut Vec<String>) {
+    let mut seen = HashSet::new();
+    uuids.retain(|uuid| seen.insert(uuid.clone()));
+}
+
+/// DCs by level from the Level-Based DCs table, starting at level -1
+const LEVEL_DCS: [f64; 27] = [
+    13.0, 14.0, 15.0, 16.0, 18.0, 19.0, 20.0, 22.0, 23.0, 24.0, 26.0, 27.0, 28.0, 30.0, 31.0, 32.0,
+    34.0, 35.0, 36.0, 38.0, 39.0, 40.0, 42.0, 44.0, 46.0, 48.0, 50.0,
+];
+
+/// The level-based DC for a creature or task of the given level
+fn level_dc(level: f64) -> f64 {
+    LEVEL_DCS[(level.clamp(-1.0, 25.0) + 1.0) as usize]
+}
+
+/// Show copper as gold, silver, and copper pieces
+fn format_coins(copper: f64) -> String {
+    let coins: Vec<String> = [
+        ((copper / 100.0).floor(), "gp"),
+        ((copper % 100.0 / 10.0).floor(), "sp"),
+        (copper % 10.0, "cp"),
+    ]
+    .into_iter()
+    .filter(|(amount, _)| *amount > 0.0)
+    .map(|(amount, denomination)| format!("{amount} {denomination}"))
+    .collect();
+    if coins.is_empty() {
+        "nothing".to_string()
+    } else {
+        coins.join(", ")
+    }
+}
+
+/// Uppercase the first letter, like a save or skill slug in a label
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn setting_number(key: &str) -> f64 {
+    let value = get_setting(ID, key);
+    value.as_f64().unwrap_or_default()
+}
+
+/// Apply the message's damage roll to the actor with the given multiplier
+///
+/// Healing (negative multipliers) is applied as a flat number, bypassing IWR like the PF2e chat card does.
+async fn apply_message_damage(
+    message: &Message,
+    actor: &Actor,
+    multiplier: f64,
+    shield_block: bool,
+) -> Result<(), Error> {
+    let roll = message.first_roll().ctx("damage roll")?;
+    let damage = if multiplier < 0.0 {
+        JsValue::from(multiplier * roll.total())
+    } else if multiplier == 1.0 {
+        roll.as_js_value().clone()
+    } else {
+        roll.alter(multiplier, 0.0)?.as_js_value().clone()
+    };
+    actor
+        .apply_damage(
+            &damage,
+            actor.active_token().as_ref(),
+            multiplier <= 0.0,
+            shield_block,
+        )
+        .await
+}
+
 pub fn init_features() {
     equipment_observation::init();
+    experience::init();
+    exploration::init();
+    flanking::init();
     auto_popout::init();
-}
\ No newline at end of file
+    adjustments::init();
+    ammo::init();
+    aura_rings::init();
+    consumables::init();
+    counteract::init();
+    cover::init();
+    currency::init();
+    crafting::init();
+    critical_specialization::init();
+    d20_stats::init();
+    daily_preparations::init();
+    damage_stats::init();
+    damage_workflow::init();
+    defense_tooltip::init();
+    degree_highlight::init();
+    duration_badges::init();
+    dying::init();
+    earn_income::init();
+    group_checks::init();
+    hero_points::init();
+    iwr_hints::init();
+    loot::init();
+    party_overview::init();
+    persistent_damage::init();
+    range_penalty::init();
+    recall_knowledge::init();
+    refocus::init();
+    rest::init();
+    secret_rolls::init();
+    spell_saves::init();
+    spell_slots::init();
+    template_targeting::init();
+    treasure::init();
+    treat_wounds::init();
+    turn_conditions::init();
+    turn_summary::init();
+}
@@ -0,0 +1,151 @@
+use crate::features::{player_characters, register_api};
+use crate::foundry::error::Error;
+use crate::foundry::{application, cprintln, *};
+use crate::hook;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+}
+
+/// Open the overview, or refresh it if it's already open
+async fn open() -> Result<(), Error> {
+    if !Game::instance()?.user()?.is_gm() {
+        return Err("Only the GM can open the party overview".into());
+    }
+    application::SingletonWindow::open(&WINDOW, || {
+        application::WindowBuilder::new("johnys-party-overview", "Party Overview")
+            .icon("fa-solid fa-users")
+            .classes(&["johnys-party-overview"])
+            .position(&ApplicationPosition {
+                width: Some(720.0),
+                ..Default::default()
+            })
+            .render(render_table)
+    })
+    .await
+}
+
+/// Show a number, or a dash if the actor doesn't have it
+fn stat(value: Option<f64>) -> String {
+    value.map_or_else(|| "—".to_string(), |value| value.to_string())
+}
+
+fn modifier(value: Option<f64>) -> String {
+    value.map_or_else(|| "—".to_string(), |value| format!("{value:+}"))
+}
+
+/// One row for the character: their defenses, speeds, hero points, and conditions
+fn render_row(actor: &Actor) -> String {
+    let hp = match (actor.hit_points(), actor.max_hit_points()) {
+        (Some(hp), Some(max_hp)) => format!("{hp} / {max_hp}"),
+        _ => "—".to_string(),
+    };
+    let speeds: Vec<String> = actor
+        .speeds()
+        .into_iter()
+        .map(|(kind, feet)| {
+            if kind == "land" {
+                format!("{feet} ft")
+            } else {
+                format!("{kind} {feet} ft")
+            }
+        })
+        .collect();
+    let hero_points = actor.hero_points().map_or_else(
+        || "—".to_string(),
+        |(value, max)| format!("{value} / {max}"),
+    );
+    let conditions: Vec<String> = actor
+        .conditions()
+        .iter()
+        .map(|condition| {
+            let name = escape_html(&condition.name());
+            match condition.condition_value() {
+                Some(value) => format!("{name} {value}"),
+                None => name,
+            }
+        })
+        .collect();
+    format!(
+        r#"<tr>
+            <td class="party-overview-name">{name}</td>
+            <td>{hp}</td>
+            <td>{ac}</td>
+            <td>{fortitude}</td>
+            <td>{reflex}</td>
+            <td>{will}</td>
+            <td>{perception}</td>
+            <td>{speeds}</td>
+            <td>{hero_points}</td>
+            <td class="party-overview-conditions">{conditions}</td>
+        </tr>"#,
+        name = escape_html(&actor.name()),
+        ac = stat(actor.armor_class()),
+        fortitude = modifier(actor.save_modifier("fortitude")),
+        reflex = modifier(actor.save_modifier("reflex")),
+        will = modifier(actor.save_modifier("will")),
+        perception = modifier(actor.perception_modifier()),
+        speeds = speeds.join(", "),
+        conditions = conditions.join(", "),
+    )
+}
+
+/// A table of every player character, kept current as they change
+async fn render_table() -> Result<JsValue, Error> {
+    let characters = player_characters()?;
+    let container = Document::create_element("div")?;
+    container.set_class_name("party-overview")?;
+    if characters.is_empty() {
+        container.insert_adjacent_html(
+            "afterbegin",
+            r#"<p class="johnys-empty">No player characters.</p>"#,
+        )?;
+        return Ok(container.as_js_value().clone());
+    }
+
+    let rows: String = characters.iter().map(render_row).collect();
+    container.insert_adjacent_html(
+        "afterbegin",
+        &format!(
+            r#"<table>
+                <thead><tr>
+                    <th>Name</th><th>HP</th><th>AC</th><th>Fort</th><th>Ref</th><th>Will</th>
+                    <th>Perc</th><th>Speed</th><th>Hero</th><th>Conditions</th>
+                </tr></thead>
+                <tbody>{rows}</tbody>
+            </table>"#
+        ),
+    )?;
+    Ok(container.as_js_value().clone())
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.openPartyOverview()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening party overview: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openPartyOverview", &open_fn.into_js_value());
+    });
+
+    hook!("updateActor", async |_actor: JsValue| {
+        if let Err(err) = application::SingletonWindow::refresh(&WINDOW).await {
+            cprintln!("Error refreshing party overview: {err}");
+        }
+    });
+
+    // conditions and effects change the numbers without updating the actor
+    for hook in ["createItem", "updateItem", "deleteItem"] {
+        hook!(hook, async |_item: JsValue| {
+            if let Err(err) = application::SingletonWindow::refresh(&WINDOW).await {
+                cprintln!("Error refreshing party overview: {err}");
+            }
+        });
+    }
+}
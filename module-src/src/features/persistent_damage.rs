@@ -0,0 +1,203 @@
+use crate::features::{is_enabled, is_responsible_gm};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Flag on the chat message once its damage has been applied
+const APPLIED_FLAG: &str = "persistentApplied";
+
+/// Sent to the GM when a player applies damage from a message only the GM can update
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum PersistentDamageMessage {
+    #[serde(rename_all = "camelCase")]
+    PersistentDamageApplied { message_id: String },
+}
+
+/// Roll each persistent damage condition on the combatant whose turn just ended, with its flat check
+async fn roll_persistent_damage(combat: Combat, prior: JsValue) -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !is_enabled("persistentDamageAutomation") || !is_responsible_gm(&game)? {
+        return Ok(());
+    }
+    let Some(combatant_id) = get_string_property(&prior, "combatantId") else {
+        return Ok(());
+    };
+    let Some(actor) = combat
+        .get_combatant(&combatant_id)
+        .and_then(|combatant| combatant.actor())
+    else {
+        return Ok(());
+    };
+
+    for condition in actor.conditions() {
+        let Some(persistent) = condition.persistent_damage() else {
+            continue;
+        };
+        let damage = Roll::evaluate(&persistent.formula).await?.total();
+        let flat_check = Roll::evaluate("1d20").await?.total();
+        let recovered = flat_check >= persistent.dc;
+        let recovery = if recovered {
+            format!(
+                r#"<span class="johnys-persistent-recovered">Flat check {flat_check} vs DC {}: recovered</span>"#,
+                persistent.dc
+            )
+        } else {
+            format!(
+                r#"<span class="johnys-persistent-ongoing">Flat check {flat_check} vs DC {}: still taking damage</span>"#,
+                persistent.dc
+            )
+        };
+        Message::create(&format!(
+            r#"<div class="johnys-persistent-damage">
+                <p><strong>{name}</strong> takes <strong>{damage}</strong> persistent {kind} damage.</p>
+                <p>{recovery}</p>
+                <button type="button" data-johnys-persistent="{damage}" data-damage-type="{kind}" data-actor-uuid="{uuid}"><i class="fa-solid fa-heart-broken"></i> Apply {damage} {kind} damage</button>
+            </div>"#,
+            name = actor.name(),
+            kind = persistent.damage_type,
+            uuid = actor.uuid().unwrap_or_default(),
+        ))
+        .await?;
+        if recovered {
+            condition.delete().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Let the owner apply the damage from a persistent damage message, once
+fn wire_apply_button(message: Message, html: HtmlElement) -> Result<(), Error> {
+    let Some(button) = html.query_selector("button[data-johnys-persistent]")? else {
+        return Ok(());
+    };
+    if message.get_flag(ID, APPLIED_FLAG).as_bool() == Some(true) {
+        return button.set_attribute("disabled", "true");
+    }
+    let message = Rc::new(message);
+    let button_js = button.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+        let message = message.clone();
+        let button = HtmlElement::from(button_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = apply_persistent_damage(&message, &button).await {
+                cprintln!("Error applying persistent damage: {err}");
+                UI::notify_error(&format!("Unable to apply damage: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+async fn apply_persistent_damage(message: &Message, button: &HtmlElement) -> Result<(), Error> {
+    if message.get_flag(ID, APPLIED_FLAG).as_bool() == Some(true) {
+        return button.set_attribute("disabled", "true");
+    }
+    let damage: f64 = button
+        .get_attribute("data-johnys-persistent")
+        .and_then(|damage| damage.parse().ok())
+        .ctx("persistent damage amount")?;
+    let damage_type = button
+        .get_attribute("data-damage-type")
+        .ctx("persistent damage type")?;
+    let uuid = button
+        .get_attribute("data-actor-uuid")
+        .ctx("persistent damage actor")?;
+    let actor: Actor = Game::from_uuid(&uuid).await?;
+    if !actor.is_owner() {
+        return Err("Only the creature's owner can apply its damage".into());
+    }
+    // a typed roll rather than a bare number so the actor's immunities, weaknesses and resistances apply
+    let roll = Roll::evaluate_damage(&format!("{damage}[{damage_type}]")).await?;
+    button.set_attribute("disabled", "true")?;
+    actor
+        .apply_damage(
+            roll.as_js_value(),
+            actor.active_token().as_ref(),
+            false,
+            false,
+        )
+        .await?;
+    if message.is_owner() {
+        mark_applied(message).await
+    } else {
+        socket::emit(
+            ID,
+            &PersistentDamageMessage::PersistentDamageApplied {
+                message_id: message.id(),
+            },
+        )
+    }
+}
+
+/// Record that the message's damage was applied, so re-rendering it doesn't offer the button again
+async fn mark_applied(message: &Message) -> Result<(), Error> {
+    message
+        .set_flag(ID, APPLIED_FLAG, &JsValue::from(true))
+        .await
+}
+
+async fn receive(data: JsValue) -> Result<(), Error> {
+    let Ok(PersistentDamageMessage::PersistentDamageApplied { message_id }) =
+        serde_wasm_bindgen::from_value(data)
+    else {
+        return Ok(());
+    };
+    let game = Game::instance()?;
+    if !is_responsible_gm(&game)? {
+        return Ok(());
+    }
+    if let Some(message) = game.get_message(&message_id)? {
+        mark_applied(&message).await?;
+    }
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Roll Persistent Damage")
+            .hint("When a combatant's turn ends, roll each of its persistent damage conditions and the flat check to end it, post the result to chat with a button for the owner to apply the damage, and remove conditions that were recovered from.")
+            .scope("world")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "persistentDamageAutomation");
+    });
+
+    hook!(
+        "combatTurnChange",
+        async |combat: JsValue, prior: JsValue| {
+            if let Err(err) = roll_persistent_damage(combat.into(), prior).await {
+                cprintln!("Error rolling persistent damage: {err}");
+            }
+        }
+    );
+
+    hook!(
+        "renderChatMessageHTML",
+        |message: JsValue, html: JsValue| {
+            if let Err(err) = wire_apply_button(message.into(), html.into()) {
+                cprintln!("Error wiring persistent damage button: {err}");
+            }
+        }
+    );
+
+    hook!("ready", || {
+        let result = socket::on(ID, |data| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = receive(data).await {
+                    cprintln!("Error recording applied persistent damage: {err}");
+                }
+            });
+        });
+        if let Err(err) = result {
+            cprintln!("Error listening for applied persistent damage: {err}");
+        }
+    });
+}
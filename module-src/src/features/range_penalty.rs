@@ -0,0 +1,94 @@
+use crate::features::is_enabled;
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 100, n % 10) {
+        (11..=13, _) => "th",
+        (_, 1) => "st",
+        (_, 2) => "nd",
+        (_, 3) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// Whisper whoever made a ranged strike the range penalty for how far away the target is
+async fn remind_range(message: Message) -> Result<(), Error> {
+    if !is_enabled("rangePenaltyReminders") {
+        return Ok(());
+    }
+    let Some(context) = message.pf2e_context() else {
+        return Ok(());
+    };
+    let user = Game::instance()?.user()?;
+    if context.roll_type().as_deref() != Some("attack-roll")
+        || message.author().and_then(|author| author.id()) != user.id()
+    {
+        return Ok(());
+    }
+    let (Some(actor), Some(weapon)) = (message.speaker_actor(), message.item()) else {
+        return Ok(());
+    };
+    let Some((increment, max)) = weapon.range() else {
+        return Ok(());
+    };
+    let Some(attacker) = actor.active_token() else {
+        return Ok(());
+    };
+    let Some(uuid) = context.target_token_uuid() else {
+        return Ok(());
+    };
+    let Some(target) = Game::token_from_uuid(&uuid).await? else {
+        return Ok(());
+    };
+    let Some(distance) = attacker.distance_to(&target) else {
+        return Ok(());
+    };
+    let target_name = target.name().unwrap_or_default();
+
+    let reminder = if distance > max {
+        format!(
+            r#"<p class="johnys-range-warning"><i class="fa-solid fa-triangle-exclamation"></i> {target_name} is {distance} ft away, beyond the {max} ft maximum range of {weapon_name}.</p>"#,
+            weapon_name = weapon.name(),
+        )
+    } else {
+        let increments = (distance / increment).ceil().max(1.0);
+        if increments <= 1.0 {
+            return Ok(());
+        }
+        format!(
+            "<p>{target_name} is {distance} ft away, in the {nth} range increment of {weapon_name} ({increment} ft): {penalty} to hit.</p>",
+            nth = ordinal(increments as u32),
+            weapon_name = weapon.name(),
+            penalty = -2.0 * (increments - 1.0),
+        )
+    };
+    Message::create_whisper(
+        &format!(r#"<div class="johnys-range-penalty">{reminder}</div>"#),
+        &user.id().into_iter().collect::<Vec<_>>(),
+    )
+    .await?;
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Range Penalty Reminders")
+            .hint("When you Strike with a ranged weapon, whisper yourself the range increment penalty for the distance to the target, or a warning if it's beyond the weapon's maximum range.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "rangePenaltyReminders");
+    });
+
+    hook!("createChatMessage", async |message: JsValue| {
+        if let Err(err) = remind_range(message.into()).await {
+            cprintln!("Error checking range penalty: {err}");
+        }
+    });
+}
@@ -0,0 +1,259 @@
+use crate::features::{capitalize, level_dc, register_api};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// Skills for Recall Knowledge about each creature trait
+const TRAIT_SKILLS: [(&str, &[&str]); 18] = [
+    ("aberration", &["occultism"]),
+    ("animal", &["nature"]),
+    ("astral", &["occultism"]),
+    ("beast", &["arcana", "nature"]),
+    ("celestial", &["religion"]),
+    ("construct", &["arcana", "crafting"]),
+    ("dragon", &["arcana"]),
+    ("elemental", &["arcana", "nature"]),
+    ("ethereal", &["occultism"]),
+    ("fey", &["nature"]),
+    ("fiend", &["religion"]),
+    ("fungus", &["nature"]),
+    ("humanoid", &["society"]),
+    ("monitor", &["religion"]),
+    ("ooze", &["occultism"]),
+    ("plant", &["nature"]),
+    ("spirit", &["occultism"]),
+    ("undead", &["religion"]),
+];
+
+/// The Recall Knowledge DC for a creature, adjusted for rarity
+fn creature_dc(creature: &Actor) -> f64 {
+    let base = level_dc(creature.level().unwrap_or_default());
+    let adjustment = match creature.rarity().as_deref() {
+        Some("uncommon") => 2.0,
+        Some("rare") => 5.0,
+        Some("unique") => 10.0,
+        _ => 0.0,
+    };
+    base + adjustment
+}
+
+/// Skills that can identify the creature, from its traits
+fn creature_skills(creature: &Actor) -> Vec<&'static str> {
+    let traits = creature.traits();
+    let mut skills: Vec<&'static str> = TRAIT_SKILLS
+        .iter()
+        .filter(|(creature_trait, _)| traits.iter().any(|t| t == creature_trait))
+        .flat_map(|(_, skills)| skills.iter().copied())
+        .collect();
+    if skills.is_empty() {
+        // any knowledge skill can try when the creature's type isn't covered
+        skills = vec!["arcana", "nature", "occultism", "religion", "society"];
+    }
+    skills.sort_unstable();
+    skills.dedup();
+    skills
+}
+
+/// What a character learns about the creature, more with `defenses` like on a critical success
+fn creature_info(creature: &Actor, defenses: bool) -> String {
+    let mut info = format!(
+        "<p><strong>{name}</strong>, level {level}</p><p>Traits: {traits}</p>",
+        name = creature.name(),
+        level = creature.level().unwrap_or_default(),
+        traits = creature.traits().join(", "),
+    );
+    if !defenses {
+        return info;
+    }
+
+    let saves: Vec<(&str, f64)> = ["fortitude", "reflex", "will"]
+        .into_iter()
+        .filter_map(|save| Some((save, creature.save_modifier(save)?)))
+        .collect();
+    let best = saves.iter().max_by(|a, b| a.1.total_cmp(&b.1));
+    let worst = saves.iter().min_by(|a, b| a.1.total_cmp(&b.1));
+    if let (Some((best, _)), Some((worst, _))) = (best, worst) {
+        info.push_str(&format!(
+            "<p>Best save: {}. Worst save: {}.</p>",
+            capitalize(best),
+            capitalize(worst)
+        ));
+    }
+    if let Some(ac) = creature.armor_class() {
+        info.push_str(&format!("<p>AC {ac}</p>"));
+    }
+    for (label, entries) in [
+        (
+            "Weaknesses",
+            creature.weaknesses_or_resistances("weaknesses"),
+        ),
+        (
+            "Resistances",
+            creature.weaknesses_or_resistances("resistances"),
+        ),
+        ("Immunities", creature.immunities()),
+    ] {
+        if !entries.is_empty() {
+            info.push_str(&format!("<p>{label}: {}</p>", entries.join(", ")));
+        }
+    }
+    info
+}
+
+/// Roll the character's Recall Knowledge in secret and whisper the player what they learn
+async fn recall(creature: Actor, user: User, skill: String) -> Result<(), Error> {
+    let character = user.character().ctx("player has no character")?;
+    let dc = creature_dc(&creature);
+    let Some(check) = character.roll_skill(&skill, dc, Some("blindroll")).await? else {
+        return Ok(());
+    };
+    let degree = check
+        .degree_of_success()
+        .ctx("Recall Knowledge degree of success")?;
+    let reveal_defenses = get_setting(ID, "recallKnowledgeDetail")
+        .as_string()
+        .as_deref()
+        == Some("defenses");
+
+    let player_ids: Vec<String> = user.id().into_iter().collect();
+    let gm_ids: Vec<String> = Game::instance()?.user()?.id().into_iter().collect();
+    let player_message = match degree {
+        DegreeOfSuccess::CriticalSuccess => creature_info(&creature, true),
+        DegreeOfSuccess::Success => creature_info(&creature, reveal_defenses),
+        DegreeOfSuccess::Failure | DegreeOfSuccess::CriticalFailure => format!(
+            "<p>{} doesn't recall anything useful about this creature.</p>",
+            character.name()
+        ),
+    };
+    Message::create_whisper(
+        &format!("<div class=\"johnys-recall-knowledge\">{player_message}</div>"),
+        &player_ids,
+    )
+    .await?;
+
+    let mut summary = format!(
+        "<p>{} got a {} recalling knowledge about {} (DC {dc}).</p>",
+        character.name(),
+        degree.label(),
+        creature.name()
+    );
+    if degree == DegreeOfSuccess::CriticalFailure {
+        summary.push_str("<p>On a critical failure, you may tell them something false.</p>");
+    } else if degree != DegreeOfSuccess::Failure && Game::is_module_active("pf2e-bestiary-tracking")
+    {
+        summary.push_str("<p>Remember to reveal what they learned in the bestiary.</p>");
+    }
+    Message::create_whisper(&summary, &gm_ids).await?;
+    Ok(())
+}
+
+/// Ask the GM whose character is recalling knowledge about the targeted creature, and with which skill
+async fn open() -> Result<(), Error> {
+    let game = Game::instance()?;
+    let gm = game.user()?;
+    if !gm.is_gm() {
+        return Err("Only the GM can roll Recall Knowledge for players".into());
+    }
+    let creature = gm
+        .targets()
+        .into_iter()
+        .find_map(|token| token.actor())
+        .ctx("Target the creature to recall knowledge about")?;
+
+    let options: String = game
+        .users()?
+        .iter()
+        .filter(|user| !user.is_gm())
+        .filter_map(|user| {
+            let character = user.character()?;
+            Some(format!(
+                r#"<option value="{}">{} ({})</option>"#,
+                user.id()?,
+                character.name(),
+                user.name()?
+            ))
+        })
+        .collect();
+    if options.is_empty() {
+        return Err("No players have characters assigned".into());
+    }
+
+    let skills = creature_skills(&creature);
+    let labels: Vec<String> = skills.iter().map(|skill| capitalize(skill)).collect();
+    let creature_js = creature.as_js_value().clone();
+    let buttons = skills
+        .iter()
+        .zip(&labels)
+        .map(|(&id, label)| {
+            let skill = id.to_string();
+            let creature_js = creature_js.clone();
+            let roll_fn = Closure::wrap(Box::new(move |html: JsValue| {
+                let form = HtmlElement::from(html);
+                let Some(user) = form
+                    .query_selector("select[name=user]")
+                    .ok()
+                    .flatten()
+                    .and_then(|select| select.value())
+                    .and_then(|id| Game::instance().ok()?.users().ok()?.get(&id))
+                else {
+                    return;
+                };
+                let creature = Actor::from(creature_js.clone());
+                let skill = skill.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Err(err) = recall(creature, user, skill).await {
+                        cprintln!("Error recalling knowledge: {err}");
+                        UI::notify_error(&err.to_string());
+                    }
+                });
+            }) as Box<dyn Fn(JsValue)>);
+            (
+                id,
+                label.as_str(),
+                Some(roll_fn.into_js_value().unchecked_into()),
+            )
+        })
+        .chain([("cancel", "Cancel", None)])
+        .collect();
+
+    application::show_dialog(
+        "Recall Knowledge",
+        format!(
+            r#"<p>Recall Knowledge about <strong>{name}</strong>, DC {dc}.</p>
+            <div class="form-group"><label>Character</label><select name="user">{options}</select></div>"#,
+            name = creature.name(),
+            dc = creature_dc(&creature),
+        ),
+        buttons,
+    )
+    .await
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Recall Knowledge Detail")
+            .hint("What players learn about a creature on a success with the Recall Knowledge tool. A critical success always reveals its defenses.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .choices(&[
+                ("basic", "Name, level, and traits"),
+                ("defenses", "Also AC, saves, weaknesses, resistances, and immunities"),
+            ])
+            .default_string("basic")
+            .register(ID, "recallKnowledgeDetail");
+
+        // game.modules.get("johnys-module").api.recallKnowledge()
+        let recall_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening recall knowledge: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("recallKnowledge", &recall_fn.into_js_value());
+    });
+}
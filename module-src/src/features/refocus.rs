@@ -0,0 +1,104 @@
+use crate::features::{is_responsible_gm, register_api, selected_actor};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+const TEN_MINUTES: f64 = 600.0;
+
+/// Feats that let a Refocus restore more than 1 focus point
+const REFOCUS_FEATS: [(&str, f64); 2] = [("meditative-wellspring", 3.0), ("meditative-focus", 2.0)];
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum RefocusMessage {
+    /// Ask the GM to move the clock forward for a player's Refocus
+    AdvanceRefocusTime,
+}
+
+/// Move the clock forward 10 minutes, asking the GM to do it when a player refocuses
+async fn spend_ten_minutes() -> Result<(), Error> {
+    let game = Game::instance()?;
+    if game.user()?.is_gm() {
+        return game.advance_time(TEN_MINUTES).await;
+    }
+    // only the GM can move the clock
+    if game.users()?.active_gm().is_none() {
+        UI::notify_warn("No GM is connected, so the 10 minutes didn't pass on the clock.");
+        return Ok(());
+    }
+    socket::emit(ID, &RefocusMessage::AdvanceRefocusTime)
+}
+
+async fn receive(data: JsValue) -> Result<(), Error> {
+    match serde_wasm_bindgen::from_value(data) {
+        Ok(RefocusMessage::AdvanceRefocusTime) => {
+            let game = Game::instance()?;
+            if is_responsible_gm(&game)? {
+                game.advance_time(TEN_MINUTES).await?;
+            }
+        }
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+/// Spend 10 minutes to restore focus points to the selected actor
+async fn refocus() -> Result<(), Error> {
+    let actor = selected_actor().ctx("Select a token or assign a character to refocus")?;
+    let (value, max) = actor
+        .focus_points()
+        .filter(|(_, max)| *max > 0.0)
+        .ctx("This creature doesn't have a focus pool")?;
+    if value >= max {
+        UI::notify_info(&format!("{}'s focus pool is already full.", actor.name()));
+        return Ok(());
+    }
+
+    let restored = REFOCUS_FEATS
+        .iter()
+        .find(|(slug, _)| actor.has_feat(slug))
+        .map(|(_, points)| *points)
+        .unwrap_or(1.0);
+    let new_value = (value + restored).min(max);
+    actor.set_focus_points(new_value).await?;
+
+    spend_ten_minutes().await?;
+    Message::create(&format!(
+        "<p><strong>{name}</strong> spends 10 minutes refocusing and regains {points} focus {unit} ({new_value}/{max}).</p>",
+        name = escape_html(&actor.name()),
+        points = new_value - value,
+        unit = if new_value - value == 1.0 { "point" } else { "points" },
+    ))
+    .await?;
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.refocus()
+        let refocus_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = refocus().await {
+                    cprintln!("Error refocusing: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("refocus", &refocus_fn.into_js_value());
+    });
+
+    hook!("ready", || {
+        let result = socket::on(ID, |data| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = receive(data).await {
+                    cprintln!("Error handling refocus: {err}");
+                }
+            });
+        });
+        if let Err(err) = result {
+            cprintln!("Error listening for refocus: {err}");
+        }
+    });
+}
@@ -0,0 +1,146 @@
+use crate::features::player_characters;
+use crate::foundry::error::Error;
+use crate::foundry::{application, cprintln, *};
+use crate::hook;
+use wasm_bindgen::prelude::*;
+
+const EIGHT_HOURS: f64 = 8.0 * 3_600.0;
+
+/// Rest one character for the night, returning what changed for the summary
+async fn rest(actor: &Actor) -> Result<Vec<String>, Error> {
+    let mut changes = Vec::new();
+
+    // regain Con modifier (minimum 1) times level in hit points
+    if let (Some(hp), Some(max_hp)) = (actor.hit_points(), actor.max_hit_points()) {
+        let con = actor.attribute_modifier("con").unwrap_or_default().max(1.0);
+        let level = actor.level().unwrap_or(1.0).max(1.0);
+        let healed = (hp + con * level).min(max_hp);
+        if healed > hp {
+            actor.set_hit_points(healed).await?;
+            changes.push(format!("regained {} HP", healed - hp));
+        }
+    }
+
+    if let Some((focus, max_focus)) = actor.focus_points() {
+        if focus < max_focus {
+            actor.set_focus_points(max_focus).await?;
+            changes.push("refilled their focus pool".to_string());
+        }
+    }
+
+    for condition in actor.conditions() {
+        if condition.slug().as_deref() == Some("fatigued") {
+            condition.delete().await?;
+            changes.push("is no longer fatigued".to_string());
+        }
+    }
+
+    let mut refreshed = 0;
+    for item in actor.items() {
+        let Some((value, max, per)) = item.frequency() else {
+            continue;
+        };
+        if per == "day" && value < max {
+            item.set_frequency_uses(max).await?;
+            refreshed += 1;
+        }
+    }
+    if refreshed > 0 {
+        changes.push(format!("refreshed {refreshed} daily abilities"));
+    }
+
+    Ok(changes)
+}
+
+/// Rest the whole party, advance the clock, and post a summary
+async fn rest_for_the_night() -> Result<(), Error> {
+    let game = Game::instance()?;
+    if !game.user()?.is_gm() {
+        return Err("Only the GM can rest the party".into());
+    }
+    let mut summary = String::new();
+    for actor in player_characters()? {
+        let changes = rest(&actor).await?;
+        let changes = if changes.is_empty() {
+            "was already rested".to_string()
+        } else {
+            changes.join(", ")
+        };
+        summary.push_str(&format!(
+            "<li><strong>{}</strong> {changes}.</li>",
+            actor.name()
+        ));
+    }
+    game.advance_time(EIGHT_HOURS).await?;
+    Message::create(&format!(
+        r#"<div class="johnys-rest-summary">
+            <p><strong>The party rests for the night.</strong> 8 hours pass.</p>
+            <ul>{summary}</ul>
+        </div>"#
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Confirm before resting, since it can't be undone
+async fn confirm_rest() -> Result<(), Error> {
+    let rest_fn = Closure::wrap(Box::new(|| {
+        wasm_bindgen_futures::spawn_local(async {
+            if let Err(err) = rest_for_the_night().await {
+                cprintln!("Error resting for the night: {err}");
+                UI::notify_error(&format!("Unable to rest: {err}"));
+            }
+        });
+    }) as Box<dyn Fn()>);
+    application::show_dialog(
+        "Rest for the Night",
+        "<p>Restore every player character's hit points, focus points, and daily abilities, remove fatigued, and advance the clock 8 hours?</p>".to_string(),
+        vec![
+            (
+                "rest",
+                "Rest",
+                Some(rest_fn.into_js_value().unchecked_into()),
+            ),
+            ("cancel", "Cancel", None),
+        ],
+    )
+    .await
+}
+
+/// Add a Rest for the Night button to the GM's actors directory
+fn inject_button(html: HtmlElement) -> Result<(), Error> {
+    if !Game::instance()?.user()?.is_gm() {
+        return Ok(());
+    }
+    let Some(actions) = html.query_selector(".header-actions")? else {
+        return Ok(());
+    };
+    if actions.query_selector("[data-johnys-rest]")?.is_some() {
+        return Ok(());
+    }
+    actions.insert_adjacent_html(
+        "beforeend",
+        r#"<button type="button" data-johnys-rest><i class="fa-solid fa-campground"></i> Rest for the Night</button>"#,
+    )?;
+    let button = actions
+        .query_selector("[data-johnys-rest]")?
+        .ok_or("rest button")?;
+    let click_fn = Closure::wrap(Box::new(|_event: JsValue| {
+        wasm_bindgen_futures::spawn_local(async {
+            if let Err(err) = confirm_rest().await {
+                cprintln!("Error confirming rest: {err}");
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+pub fn init() {
+    hook!("renderActorDirectory", |_app: JsValue, html: JsValue| {
+        if let Err(err) = inject_button(HtmlElement::from(html)) {
+            cprintln!("Error adding rest button: {err}");
+        }
+    });
+}
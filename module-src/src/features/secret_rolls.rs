@@ -0,0 +1,176 @@
+use crate::features::register_api;
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::hook;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// How many of the latest secret rolls the panel lists
+const MAX_ROLLS: usize = 20;
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+    /// Messages whose roll this client already revealed
+    static REVEALED: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+/// Check if the message has a roll the players can't see, like a blind or GM-only check
+fn is_secret_roll(message: &Message) -> bool {
+    message.first_roll().is_some() && (message.is_blind() || message.is_whisper())
+}
+
+/// The name to show for who made the roll
+fn roller_name(message: &Message) -> String {
+    message
+        .speaker_actor()
+        .map(|actor| actor.name())
+        .or_else(|| message.author()?.name())
+        .unwrap_or_default()
+}
+
+/// A badge for the check's degree of success, if it had a DC
+fn degree_badge(message: &Message) -> String {
+    message
+        .pf2e_context()
+        .and_then(|context| context.outcome())
+        .map(|degree| {
+            format!(
+                r#" <span class="johnys-degree-badge {}">{}</span>"#,
+                degree.slug(),
+                degree.label()
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Open the panel, or refresh it if it's already open
+async fn open() -> Result<(), Error> {
+    if !Game::instance()?.user()?.is_gm() {
+        return Err("Only the GM can reveal secret rolls".into());
+    }
+    application::SingletonWindow::open(&WINDOW, || {
+        application::WindowBuilder::new("johnys-secret-rolls", "Secret Rolls")
+            .icon("fa-solid fa-eye-slash")
+            .classes(&["johnys-secret-rolls"])
+            .position(&ApplicationPosition {
+                width: Some(360.0),
+                ..Default::default()
+            })
+            .render(render_rolls)
+    })
+    .await
+}
+
+/// List the latest secret rolls, newest first, with a button to reveal each
+async fn render_rolls() -> Result<JsValue, Error> {
+    let rows: String = Game::instance()?
+        .messages()
+        .iter()
+        .rev()
+        .filter(|message| is_secret_roll(message))
+        .take(MAX_ROLLS)
+        .filter_map(|message| {
+            let roll = message.first_roll()?;
+            let id = message.id();
+            let action = if REVEALED.with_borrow(|revealed| revealed.contains(&id)) {
+                r#"<span class="secret-roll-revealed"><i class="fa-solid fa-check"></i> Revealed</span>"#.to_string()
+            } else {
+                format!(
+                    r#"<a data-reveal-message="{id}" data-tooltip="Reveal to Players"><i class="fa-solid fa-eye"></i></a>"#
+                )
+            };
+            let check = message
+                .pf2e_context()
+                .and_then(|context| context.item_name())
+                .unwrap_or_else(|| roll.formula());
+            Some(format!(
+                r#"<li class="johnys-row">
+                    <span class="secret-roll-name"><strong>{name}</strong> {check}</span>
+                    <span class="secret-roll-total">{total}{degree}</span>
+                    {action}
+                </li>"#,
+                name = roller_name(message),
+                total = roll.total(),
+                degree = degree_badge(message),
+            ))
+        })
+        .collect();
+
+    let container = Document::create_element("ol")?;
+    container.set_class_name("johnys-rows")?;
+    if rows.is_empty() {
+        container.insert_adjacent_html(
+            "afterbegin",
+            r#"<li class="johnys-empty">No secret rolls in the chat log.</li>"#,
+        )?;
+    } else {
+        container.insert_adjacent_html("afterbegin", &rows)?;
+    }
+
+    let reveal_fn = Closure::wrap(Box::new(|event: JsValue| {
+        let Some(id) = get_property(&event, "target")
+            .ok()
+            .and_then(|target| HtmlElement::from(target).closest("[data-reveal-message]"))
+            .and_then(|button| button.get_attribute("data-reveal-message"))
+        else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = reveal(id).await {
+                cprintln!("Error revealing roll: {err}");
+                UI::notify_error(&format!("Unable to reveal the roll: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    container.add_event_listener("click", &reveal_fn)?;
+    reveal_fn.forget();
+
+    Ok(container.as_js_value().clone())
+}
+
+/// Post the roll's result publicly
+async fn reveal(id: String) -> Result<(), Error> {
+    let message = Game::instance()?
+        .get_message(&id)?
+        .ctx("The message was deleted")?;
+    let roll = message.first_roll().ctx("roll")?;
+    Message::create(&format!(
+        r#"<div class="johnys-revealed-roll">
+            <p><strong>{name}</strong> rolled in secret:</p>
+            {flavor}
+            <p>Result: <strong>{total}</strong>{degree}</p>
+        </div>"#,
+        name = roller_name(&message),
+        flavor = message.flavor().unwrap_or_default(),
+        total = roll.total(),
+        degree = degree_badge(&message),
+    ))
+    .await?;
+    REVEALED.with_borrow_mut(|revealed| revealed.insert(id));
+    application::SingletonWindow::refresh(&WINDOW).await
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.openSecretRolls()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening secret rolls: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openSecretRolls", &open_fn.into_js_value());
+    });
+
+    hook!("createChatMessage", async |message: JsValue| {
+        if !is_secret_roll(&message.into()) {
+            return;
+        }
+        if let Err(err) = application::SingletonWindow::refresh(&WINDOW).await {
+            cprintln!("Error refreshing secret rolls: {err}");
+        }
+    });
+}
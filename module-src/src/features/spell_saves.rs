@@ -0,0 +1,158 @@
+use crate::features::{capitalize, dedup_uuids, is_enabled};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// What a basic save does to the damage at each degree of success
+fn basic_damage(degree: DegreeOfSuccess) -> &'static str {
+    match degree {
+        DegreeOfSuccess::CriticalSuccess => "no damage",
+        DegreeOfSuccess::Success => "half damage",
+        DegreeOfSuccess::Failure => "full damage",
+        DegreeOfSuccess::CriticalFailure => "double damage",
+    }
+}
+
+/// The creatures the spell targets that the GM rolls for: the message's targets, or else whoever
+/// the caster's player is targeting now, or else the GM's own targets
+async fn npc_targets(message: &Message) -> Result<Vec<Actor>, Error> {
+    let target_uuids = |user: User| -> Vec<String> {
+        user.targets()
+            .into_iter()
+            .filter_map(|token| token.actor()?.uuid())
+            .collect()
+    };
+    let mut uuids = message.target_uuids().await;
+    if uuids.is_empty() {
+        uuids = message.author().map(target_uuids).unwrap_or_default();
+    }
+    if uuids.is_empty() {
+        uuids = target_uuids(Game::instance()?.user()?);
+    }
+    dedup_uuids(&mut uuids);
+    let mut actors = Vec::new();
+    for uuid in uuids {
+        let Ok(actor) = Game::from_uuid(&uuid).await else {
+            continue;
+        };
+        if !actor.has_player_owner() {
+            actors.push(actor);
+        }
+    }
+    Ok(actors)
+}
+
+/// Roll the spell's save for every targeted NPC and whisper the GM how each did
+async fn roll_all_saves(message: Message) -> Result<(), Error> {
+    let spell = message.item().ctx("The spell is no longer on the caster")?;
+    let (save, basic) = spell.spell_save().ctx("The spell has no save")?;
+    let dc = spell.spell_dc().ctx("spell DC")?;
+    let targets = npc_targets(&message).await?;
+    if targets.is_empty() {
+        return Err("Target the creatures that need to save".into());
+    }
+
+    let mut rows = String::new();
+    for actor in targets {
+        let Some(roll) = actor.roll_save_against(&save, dc, Some(&spell)).await? else {
+            continue;
+        };
+        let Some(degree) = roll.degree_of_success() else {
+            continue;
+        };
+        let effect = if basic {
+            format!(" ({})", basic_damage(degree))
+        } else {
+            String::new()
+        };
+        rows.push_str(&format!(
+            r#"<li><strong>{name}</strong> {total} <span class="johnys-degree-badge {slug}">{label}</span>{effect}</li>"#,
+            name = actor.name(),
+            total = roll.total(),
+            slug = degree.slug(),
+            label = degree.label(),
+        ));
+    }
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let user_ids: Vec<String> = Game::instance()?.user()?.id().into_iter().collect();
+    Message::create_whisper(
+        &format!(
+            r#"<div class="johnys-spell-saves">
+                <p><strong>{spell_name}</strong>: DC {dc} {basic}{save} save</p>
+                <ul>{rows}</ul>
+            </div>"#,
+            spell_name = spell.name(),
+            basic = if basic { "basic " } else { "" },
+            save = capitalize(&save),
+        ),
+        &user_ids,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Add a button to roll everyone's saves to a spell with a save, for the GM
+fn inject_button(message: Message, html: HtmlElement) -> Result<(), Error> {
+    if !is_enabled("rollAllSavesButton")
+        || !Game::instance()?.user()?.is_gm()
+        || message.pf2e_type().as_deref() != Some("spell-cast")
+    {
+        return Ok(());
+    }
+    let Some((save, _)) = message.item().and_then(|spell| spell.spell_save()) else {
+        return Ok(());
+    };
+    let content = html
+        .query_selector(".message-content")?
+        .ctx("message content")?;
+    content.insert_adjacent_html(
+        "beforeend",
+        &format!(
+            r#"<button type="button" class="johnys-roll-all-saves" data-tooltip="Roll for every NPC the caster targeted, or else every NPC you target"><i class="fa-solid fa-dice-d20"></i> Roll All {} Saves</button>"#,
+            capitalize(&save)
+        ),
+    )?;
+    let button = content
+        .query_selector(".johnys-roll-all-saves")?
+        .ctx("roll all saves button")?;
+
+    let message_js = message.as_js_value().clone();
+    let click_fn = Closure::wrap(Box::new(move |_event: JsValue| {
+        let message = Message::from(message_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = roll_all_saves(message).await {
+                cprintln!("Error rolling saves: {err}");
+                UI::notify_error(&format!("Unable to roll saves: {err}"));
+            }
+        });
+    }) as Box<dyn Fn(JsValue)>);
+    button.add_event_listener("click", &click_fn)?;
+    click_fn.forget();
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Roll All Saves Button")
+            .hint("Add a button for the GM to spells with a save that rolls it for every targeted NPC at once and whispers the GM how each did.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(true)
+            .register(ID, "rollAllSavesButton");
+    });
+
+    hook!(
+        "renderChatMessageHTML",
+        |message: JsValue, html: JsValue| {
+            if let Err(err) = inject_button(message.into(), html.into()) {
+                cprintln!("Error adding roll all saves button: {err}");
+            }
+        }
+    );
+}
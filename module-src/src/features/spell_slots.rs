@@ -0,0 +1,239 @@
+use crate::features::is_enabled;
+use crate::foundry::error::Error;
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+const MAX_RANK: u8 = 10;
+
+/// Slots left by actor UUID, spellcasting entry ID, and rank
+type SlotKey = (String, String, u8);
+
+thread_local! {
+    /// Slots left when each actor's slots were last checked, to tell whether a cast used one
+    static REMAINING: RefCell<HashMap<SlotKey, f64>> = RefCell::new(HashMap::new());
+}
+
+/// Check if this client looks after the actor's spells
+fn is_caster_owner(actor: &Actor) -> bool {
+    actor.is_owned_by_current_user(GMStrategy::IfNoPlayers, OwnershipLevel::Owner)
+}
+
+/// Remember the actor's remaining slots; with `only_increases`, only record slots that went up,
+/// e.g. from resting, so a cast that's about to be announced still shows up as a decrease
+fn record_slots(actor: &Actor, only_increases: bool) {
+    let Some(uuid) = actor.uuid() else {
+        return;
+    };
+    REMAINING.with_borrow_mut(|remaining| {
+        for entry in actor.spellcasting_entries() {
+            let Some(entry_id) = entry.id().filter(|_| entry.uses_slots()) else {
+                continue;
+            };
+            for rank in 1..=MAX_RANK {
+                let Some((left, _)) = entry.slots(rank) else {
+                    continue;
+                };
+                let key = (uuid.clone(), entry_id.clone(), rank);
+                let previous = remaining.get(&key).copied();
+                if !only_increases || previous.is_none_or(|previous| left > previous) {
+                    remaining.insert(key, left);
+                }
+            }
+        }
+    });
+}
+
+/// Ask the owner to mark a slot if a spell was cast without using one, e.g. from pf2e-toolbelt or by
+/// posting it to chat
+async fn check_cast(message: Message) -> Result<(), Error> {
+    if !is_enabled("spellSlotPrompts") {
+        return Ok(());
+    }
+    let (Some(actor), Some(spell), Some(rank)) =
+        (message.speaker_actor(), message.item(), message.cast_rank())
+    else {
+        return Ok(());
+    };
+    if spell.item_type().as_deref() != Some("spell")
+        || spell.is_cantrip()
+        || !is_caster_owner(&actor)
+    {
+        return Ok(());
+    }
+    let Some(entry) = spell.spellcasting_entry_id().and_then(|entry_id| {
+        actor
+            .spellcasting_entries()
+            .into_iter()
+            .find(|entry| entry.id().as_deref() == Some(entry_id.as_str()) && entry.uses_slots())
+    }) else {
+        return Ok(());
+    };
+    let (Some(uuid), Some(entry_id), Some((left, _))) =
+        (actor.uuid(), entry.id(), entry.slots(rank))
+    else {
+        return Ok(());
+    };
+
+    let key = (uuid, entry_id, rank);
+    let before = REMAINING.with_borrow(|remaining| remaining.get(&key).copied());
+    REMAINING.with_borrow_mut(|remaining| remaining.insert(key, left));
+    // a slot was used, or we don't know what there was before
+    if before.is_none_or(|before| left < before) {
+        return Ok(());
+    }
+    if left <= 0.0 {
+        UI::notify_warn(&format!(
+            "{} cast {} without a rank {rank} slot left in {}.",
+            actor.name(),
+            spell.name(),
+            entry.name()
+        ));
+        return Ok(());
+    }
+
+    let actor_js = actor.as_js_value().clone();
+    let spell_js = spell.as_js_value().clone();
+    let entry_id = entry.id().unwrap_or_default();
+    let expend_fn = Closure::wrap(Box::new(move || {
+        let actor = Actor::from(actor_js.clone());
+        let spell = Item::from(spell_js.clone());
+        let entry_id = entry_id.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let Some(entry) = actor
+                .spellcasting_entries()
+                .into_iter()
+                .find(|entry| entry.id().as_deref() == Some(entry_id.as_str()))
+            else {
+                return;
+            };
+            if let Err(err) = entry.consume(&spell, rank).await {
+                cprintln!("Error expending spell slot: {err}");
+                UI::notify_error(&format!("Unable to expend the slot: {err}"));
+            }
+            record_slots(&actor, false);
+        });
+    }) as Box<dyn Fn()>);
+
+    application::show_dialog(
+        "Spell Slot",
+        format!(
+            "<p>{actor_name} cast {spell_name} at rank {rank}, but no slot was used. Expend a rank {rank} slot from {entry_name}?</p>",
+            actor_name = actor.name(),
+            spell_name = spell.name(),
+            entry_name = entry.name(),
+        ),
+        vec![
+            (
+                "expend",
+                "Expend Slot",
+                Some(expend_fn.into_js_value().unchecked_into()),
+            ),
+            ("skip", "Skip", None),
+        ],
+    )
+    .await
+}
+
+/// Whisper the owner a summary of the slots left when their caster's turn starts
+async fn summarize_slots(combat: Combat) -> Result<(), Error> {
+    if !is_enabled("spellSlotSummary") {
+        return Ok(());
+    }
+    let Some(actor) = combat.combatant().and_then(|combatant| combatant.actor()) else {
+        return Ok(());
+    };
+    if !is_caster_owner(&actor) {
+        return Ok(());
+    }
+    let lines: Vec<String> = actor
+        .spellcasting_entries()
+        .iter()
+        .filter(|entry| entry.uses_slots())
+        .filter_map(|entry| {
+            let ranks: Vec<String> = (1..=MAX_RANK)
+                .filter_map(|rank| {
+                    let (left, max) = entry.slots(rank)?;
+                    Some(format!("rank {rank}: {left}/{max}"))
+                })
+                .collect();
+            (!ranks.is_empty()).then(|| format!("<li>{}: {}</li>", entry.name(), ranks.join(", ")))
+        })
+        .collect();
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let focus = actor
+        .focus_points()
+        .filter(|(_, max)| *max > 0.0)
+        .map(|(value, max)| format!("<p>Focus points: {value}/{max}</p>"))
+        .unwrap_or_default();
+    let user_ids: Vec<String> = Game::instance()?.user()?.id().into_iter().collect();
+    Message::create_whisper(
+        &format!(
+            r#"<div class="johnys-spell-slots"><p><strong>{}'s spell slots</strong></p><ul>{}</ul>{focus}</div>"#,
+            actor.name(),
+            lines.join("")
+        ),
+        &user_ids,
+    )
+    .await?;
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Spell Slot Prompts")
+            .hint("When one of your casters casts a spell without using a slot, like from pf2e-toolbelt or by posting it to chat, offer to expend the slot.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "spellSlotPrompts");
+
+        SettingConfig::new()
+            .name("Spell Slot Summary")
+            .hint("At the start of your caster's turn, whisper yourself the spell slots and focus points they have left.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "spellSlotSummary");
+    });
+
+    hook!("ready", || {
+        let Ok(game) = Game::instance() else {
+            return;
+        };
+        for actor in game.actors().iter().filter(|actor| is_caster_owner(actor)) {
+            record_slots(actor, false);
+        }
+    });
+
+    hook!("updateItem", |item: JsValue| {
+        let item = Item::from(item);
+        if item.item_type().as_deref() == Some("spellcastingEntry") {
+            if let Some(actor) = item.actor() {
+                record_slots(&actor, true);
+            }
+        }
+    });
+
+    hook!("createChatMessage", async |message: JsValue| {
+        if let Err(err) = check_cast(message.into()).await {
+            cprintln!("Error checking spell slot: {err}");
+        }
+    });
+
+    hook!(
+        "combatTurnChange",
+        async |combat: JsValue, _prior: JsValue| {
+            if let Err(err) = summarize_slots(combat.into()).await {
+                cprintln!("Error summarizing spell slots: {err}");
+            }
+        }
+    );
+}
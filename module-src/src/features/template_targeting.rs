@@ -0,0 +1,129 @@
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// PF2e grid squares are 5 feet across
+const SQUARE_FEET: f64 = 5.0;
+
+/// Check if a canvas point falls inside the template's area
+fn template_contains(
+    template: &MeasuredTemplate,
+    (x, y): (f64, f64),
+    pixels_per_foot: f64,
+) -> bool {
+    let Some((origin_x, origin_y)) = template.origin() else {
+        return false;
+    };
+    let (dx, dy) = (x - origin_x, y - origin_y);
+    let length = template.distance() * pixels_per_foot;
+    let direction = template.direction().to_radians();
+    match template.shape().as_deref() {
+        Some("circle") => dx.hypot(dy) <= length,
+        Some("cone") => {
+            let offset = (dy.atan2(dx) - direction).to_degrees().rem_euclid(360.0);
+            let offset = offset.min(360.0 - offset);
+            dx.hypot(dy) <= length && offset <= template.angle() / 2.0
+        }
+        Some("ray") => {
+            let along = dx * direction.cos() + dy * direction.sin();
+            let across = (dy * direction.cos() - dx * direction.sin()).abs();
+            (0.0..=length).contains(&along) && across <= template.width() * pixels_per_foot / 2.0
+        }
+        Some("rect") => {
+            // the distance is the rectangle's diagonal
+            let (corner_x, corner_y) = (length * direction.cos(), length * direction.sin());
+            (corner_x.min(0.0)..=corner_x.max(0.0)).contains(&dx)
+                && (corner_y.min(0.0)..=corner_y.max(0.0)).contains(&dy)
+        }
+        _ => false,
+    }
+}
+
+/// Check if any square the token occupies is inside the template
+fn token_inside(template: &MeasuredTemplate, token: &Token, pixels_per_foot: f64) -> bool {
+    let Some((left, top, right, bottom)) = token.bounds() else {
+        return false;
+    };
+    let square = SQUARE_FEET * pixels_per_foot;
+    let columns = ((right - left) / square).round().max(1.0) as u32;
+    let rows = ((bottom - top) / square).round().max(1.0) as u32;
+    (0..columns).any(|column| {
+        (0..rows).any(|row| {
+            let x = left + (column as f64 + 0.5) * (right - left) / columns as f64;
+            let y = top + (row as f64 + 0.5) * (bottom - top) / rows as f64;
+            template_contains(template, (x, y), pixels_per_foot)
+        })
+    })
+}
+
+/// Target the creatures inside a spell's template for the player who placed it
+fn target_inside(template: MeasuredTemplate) -> Result<(), Error> {
+    let filter = get_setting(ID, "templateTargeting")
+        .as_string()
+        .unwrap_or_default();
+    if filter.is_empty() || filter == "off" {
+        return Ok(());
+    }
+    let game = Game::instance()?;
+    if template.author_id() != game.user()?.id()
+        || template.origin_type().as_deref() != Some("spell")
+    {
+        return Ok(());
+    }
+    let Some(pixels_per_foot) = game.pixels_per_foot() else {
+        return Ok(());
+    };
+    let caster = template
+        .origin_actor_uuid()
+        .and_then(|uuid| game.find_token_by_actor_uuid(&uuid));
+    let caster_disposition = caster.as_ref().and_then(|caster| caster.disposition());
+
+    let inside: Vec<Token> = game
+        .scene_tokens()
+        .into_iter()
+        .filter(|token| token.actor().is_some())
+        // don't reveal creatures the caster can't see by targeting them
+        .filter(|token| token.is_visible() && !token.is_hidden())
+        // without a caster token there's no side to compare against, so take everyone
+        .filter(|token| match (filter.as_str(), caster_disposition) {
+            ("enemies", Some(_)) => token.disposition() != caster_disposition,
+            ("allies", Some(_)) => token.disposition() == caster_disposition,
+            _ => true,
+        })
+        .filter(|token| token_inside(&template, token, pixels_per_foot))
+        .collect();
+    if inside.is_empty() {
+        // a template over nobody shouldn't leave the last spell's targets in place
+        return game.user()?.update_token_targets(&[]);
+    }
+    for (index, token) in inside.iter().enumerate() {
+        token.set_target(true, index == 0)?;
+    }
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Target Creatures in Spell Templates")
+            .hint("When you place a spell's template, target the creatures inside it so the spell's damage and saves go to the right creatures. Enemies and allies are relative to the caster's token, so everyone inside is targeted when the caster has none.")
+            .scope("client")
+            .config(true)
+            .type_string()
+            .choices(&[
+                ("off", "Off"),
+                ("all", "Everyone inside"),
+                ("enemies", "Enemies inside"),
+                ("allies", "Allies inside"),
+            ])
+            .default_string("off")
+            .register(ID, "templateTargeting");
+    });
+
+    hook!("createMeasuredTemplate", |template: JsValue| {
+        if let Err(err) = target_inside(template.into()) {
+            cprintln!("Error targeting creatures in template: {err}");
+        }
+    });
+}
@@ -0,0 +1,338 @@
+use crate::features::{format_coins, player_characters, register_api};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::{get_path, hook, ID};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// How many recorded items to remember for matching against transfers out of another party member
+const RECENT_ITEMS: usize = 20;
+
+thread_local! {
+    static WINDOW: application::SingletonWindow = const { application::SingletonWindow::new() };
+    /// Copper recorded but not yet saved
+    static PENDING: Cell<f64> = const { Cell::new(0.0) };
+    /// Whether a save is running, which picks up anything recorded meanwhile
+    static SAVING: Cell<bool> = const { Cell::new(false) };
+    /// Items recently counted, in case they turn out to have come from another party member
+    static RECENT: RefCell<Vec<RecordedItem>> = const { RefCell::new(Vec::new()) };
+}
+
+/// An item counted as treasure when it was created on a party member
+struct RecordedItem {
+    key: String,
+    actor_uuid: Option<String>,
+    quantity: f64,
+    copper: f64,
+}
+
+/// Treasure by Level in gold pieces, starting at level 1: the total for a party of four, and the
+/// extra currency for each character beyond that
+const TREASURE_BY_LEVEL: [(f64, f64); 20] = [
+    (175.0, 10.0),
+    (300.0, 18.0),
+    (500.0, 30.0),
+    (850.0, 50.0),
+    (1_350.0, 80.0),
+    (2_000.0, 125.0),
+    (2_900.0, 180.0),
+    (4_000.0, 250.0),
+    (5_700.0, 350.0),
+    (8_000.0, 500.0),
+    (11_500.0, 700.0),
+    (16_500.0, 1_000.0),
+    (25_000.0, 1_500.0),
+    (36_500.0, 2_250.0),
+    (54_500.0, 3_250.0),
+    (82_500.0, 5_000.0),
+    (128_000.0, 7_500.0),
+    (208_000.0, 12_000.0),
+    (355_000.0, 20_000.0),
+    (490_000.0, 35_000.0),
+];
+
+/// The treasure budget in copper for a level, adjusted for the size of the party
+fn budget(level: f64, party_size: usize) -> f64 {
+    let (total, per_character) = TREASURE_BY_LEVEL[(level.clamp(1.0, 20.0) - 1.0) as usize];
+    (total + per_character * (party_size as f64 - 4.0)).max(0.0) * 100.0
+}
+
+/// The party's level, the average of the player characters' levels, and how many there are
+fn party() -> Result<(f64, usize), Error> {
+    let levels: Vec<f64> = player_characters()?
+        .iter()
+        .filter_map(Actor::level)
+        .collect();
+    if levels.is_empty() {
+        return Ok((1.0, 0));
+    }
+    let average = levels.iter().sum::<f64>() / levels.len() as f64;
+    Ok((average.round().clamp(1.0, 20.0), levels.len()))
+}
+
+/// Value handed out in copper, keyed by the party level at the time
+fn awarded() -> HashMap<String, f64> {
+    serde_wasm_bindgen::from_value(get_setting(ID, "treasureAwarded")).unwrap_or_default()
+}
+
+async fn save_awarded(awarded: &HashMap<String, f64>) -> Result<(), Error> {
+    let value = awarded
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| Error::Custom(format!("Failed to save treasure awarded: {e}")))?;
+    set_setting(ID, "treasureAwarded", &value).await
+}
+
+/// Count treasure handed out at the party's current level
+///
+/// Coins are recorded by the tools that hand them out, since adding coins to an existing stack
+/// doesn't create an item.
+pub(super) async fn record(copper: f64) -> Result<(), Error> {
+    if copper <= 0.0 {
+        return Ok(());
+    }
+    adjust(copper).await
+}
+
+/// Add to (or take from) the treasure at the party's current level
+///
+/// Items often arrive several at a time, so changes are queued and saved one after another rather
+/// than each reading the setting before the others have written it.
+async fn adjust(copper: f64) -> Result<(), Error> {
+    PENDING.set(PENDING.get() + copper.round());
+    if SAVING.replace(true) {
+        return Ok(());
+    }
+    let result = save_pending().await;
+    SAVING.set(false);
+    result?;
+    application::SingletonWindow::refresh(&WINDOW).await
+}
+
+async fn save_pending() -> Result<(), Error> {
+    loop {
+        let copper = PENDING.take();
+        if copper == 0.0 {
+            return Ok(());
+        }
+        let (level, _) = party()?;
+        let mut all = awarded();
+        let given = all.entry(level.to_string()).or_default();
+        *given = (*given + copper).max(0.0);
+        save_awarded(&all).await?;
+    }
+}
+
+/// Check if the actor is a player character or the party stash, whose items count as treasure
+fn receives_treasure(actor: &Actor) -> bool {
+    match actor.actor_type().as_deref() {
+        Some("party") => true,
+        Some("character") => actor.has_player_owner(),
+        _ => false,
+    }
+}
+
+/// What identifies the same item being moved from one actor to another
+fn item_key(item: &Item) -> String {
+    item.slug().unwrap_or_else(|| item.name())
+}
+
+/// Count an item the GM gave to a player character or the party stash
+async fn record_item(item: Item, user_id: JsValue) -> Result<(), Error> {
+    let user = Game::instance()?.user()?;
+    if !user.is_gm() || user.id() != user_id.as_string() {
+        return Ok(());
+    }
+    if !item.is_physical_item() || item.is_coins() {
+        return Ok(());
+    }
+    let Some(actor) = item.actor().filter(receives_treasure) else {
+        return Ok(());
+    };
+    let copper = item.total_price();
+    RECENT.with_borrow_mut(|recent| {
+        recent.push(RecordedItem {
+            key: item_key(&item),
+            actor_uuid: actor.uuid(),
+            quantity: item.quantity(),
+            copper,
+        });
+        let excess = recent.len().saturating_sub(RECENT_ITEMS);
+        recent.drain(..excess);
+    });
+    record(copper).await
+}
+
+/// Take back an item counted as treasure if it just came out of another party member
+///
+/// Moving an item between characters or to and from the stash creates it on the receiver before
+/// removing it from the giver, so the removal is what shows it wasn't new treasure.
+async fn unrecord_transfer(item: &Item, removed: f64) -> Result<(), Error> {
+    if removed <= 0.0 || !Game::instance()?.user()?.is_gm() {
+        return Ok(());
+    }
+    let Some(actor) = item.actor().filter(receives_treasure) else {
+        return Ok(());
+    };
+    let (key, giver) = (item_key(item), actor.uuid());
+    let transferred = RECENT.with_borrow_mut(|recent| {
+        let index = recent.iter().rposition(|recorded| {
+            recorded.key == key && recorded.actor_uuid != giver && recorded.quantity == removed
+        })?;
+        Some(recent.remove(index).copper)
+    });
+    match transferred {
+        Some(copper) => adjust(-copper).await,
+        None => Ok(()),
+    }
+}
+
+/// Take back a deleted item if this user just moved it to another party member
+async fn unrecord_deleted(item: Item, user_id: JsValue) -> Result<(), Error> {
+    if Game::instance()?.user()?.id() != user_id.as_string() {
+        return Ok(());
+    }
+    unrecord_transfer(&item, item.quantity()).await
+}
+
+/// Open the budget, or refresh it if it's already open
+async fn open() -> Result<(), Error> {
+    if !Game::instance()?.user()?.is_gm() {
+        return Err("Only the GM can see the treasure budget".into());
+    }
+    application::SingletonWindow::open(&WINDOW, || {
+        application::WindowBuilder::new("johnys-treasure", "Treasure Budget")
+            .icon("fa-solid fa-gem")
+            .classes(&["johnys-treasure"])
+            .position(&ApplicationPosition {
+                width: Some(320.0),
+                ..Default::default()
+            })
+            .render(render_budget)
+            .control(
+                "johnysResetTreasure",
+                "Reset This Level",
+                "fa-solid fa-rotate",
+                || {
+                    wasm_bindgen_futures::spawn_local(async {
+                        if let Err(err) = reset_level().await {
+                            cprintln!("Error resetting treasure: {err}");
+                            UI::notify_error(&format!("Unable to reset treasure: {err}"));
+                        }
+                    });
+                },
+            )
+    })
+    .await
+}
+
+/// Show the treasure handed out at the party's level against its budget
+async fn render_budget() -> Result<JsValue, Error> {
+    let (level, party_size) = party()?;
+    let budget = budget(level, party_size.max(1));
+    let given = awarded()
+        .get(&level.to_string())
+        .copied()
+        .unwrap_or_default();
+    let percent = (given / budget * 100.0).round();
+    let remaining = if given > budget {
+        format!("{} over budget", format_coins(given - budget))
+    } else {
+        format!("{} left", format_coins(budget - given))
+    };
+    Ok(JsValue::from_str(&format!(
+        r#"<div class="treasure-budget">
+            <p>Level {level} party of {party_size}</p>
+            <p><strong>{given}</strong> of {budget} handed out ({percent}%)</p>
+            <div class="treasure-bar"><div class="treasure-bar-fill{over}" style="width: {width}%"></div></div>
+            <p>{remaining}</p>
+        </div>"#,
+        given = format_coins(given),
+        budget = format_coins(budget),
+        over = if given > budget { " over" } else { "" },
+        width = percent.min(100.0),
+    )))
+}
+
+/// Forget the treasure handed out at the party's current level
+async fn reset_level() -> Result<(), Error> {
+    let (level, _) = party()?;
+    let mut all = awarded();
+    all.remove(&level.to_string())
+        .ctx("No treasure recorded at this level")?;
+    save_awarded(&all).await?;
+    application::SingletonWindow::refresh(&WINDOW).await
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .scope("world")
+            .config(false)
+            .type_object()
+            .default_value(&js_sys::Object::new())
+            .register(ID, "treasureAwarded");
+
+        SettingsMenu::new()
+            .name("Treasure Budget")
+            .label("Open Budget")
+            .hint("Compare the treasure given to the party with the Treasure by Level table for their level.")
+            .icon("fa-solid fa-gem")
+            .restricted(true)
+            .on_open(|| {
+                wasm_bindgen_futures::spawn_local(async {
+                    if let Err(err) = open().await {
+                        cprintln!("Error opening treasure budget: {err}");
+                    }
+                });
+            })
+            .register(ID, "treasureBudgetMenu");
+
+        // game.modules.get("johnys-module").api.openTreasureBudget()
+        let open_fn = Closure::wrap(Box::new(|| {
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = open().await {
+                    cprintln!("Error opening treasure budget: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            });
+        }) as Box<dyn Fn()>);
+        register_api("openTreasureBudget", &open_fn.into_js_value());
+    });
+
+    hook!(
+        "createItem",
+        async |item: JsValue, _options: JsValue, user_id: JsValue| {
+            if let Err(err) = record_item(Item::from(item), user_id).await {
+                cprintln!("Error recording treasure: {err}");
+            }
+        }
+    );
+
+    // only the client making the change runs pre-update hooks, before the quantity changes
+    hook!("preUpdateItem", |item: JsValue, changes: JsValue| {
+        let Some(quantity) = get_path!(&changes, "system.quantity")
+            .ok()
+            .and_then(|quantity| quantity.as_f64())
+        else {
+            return;
+        };
+        let item = Item::from(item);
+        let removed = item.quantity() - quantity;
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = unrecord_transfer(&item, removed).await {
+                cprintln!("Error checking for a treasure transfer: {err}");
+            }
+        });
+    });
+
+    hook!(
+        "deleteItem",
+        async |item: JsValue, _options: JsValue, user_id: JsValue| {
+            if let Err(err) = unrecord_deleted(Item::from(item), user_id).await {
+                cprintln!("Error checking for a treasure transfer: {err}");
+            }
+        }
+    );
+}
@@ -0,0 +1,307 @@
+use crate::features::{is_responsible_gm, register_api, selected_actor};
+use crate::foundry::error::{ContextExt as _, Error};
+use crate::foundry::{application, cprintln, *};
+use crate::{hook, ID};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+mod battle_medicine;
+
+const HOUR: f64 = 3_600.0;
+
+/// Sent to the GM to record immunity on a medic this user can't update
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ImmunityMessage {
+    #[serde(rename_all = "camelCase")]
+    MedicImmunity {
+        medic_uuid: String,
+        key: String,
+        until: f64,
+    },
+    #[serde(rename_all = "camelCase")]
+    TreatedEffect {
+        patient_uuid: String,
+        medic_name: String,
+    },
+}
+
+const TREATED_SLUG: &str = "johnys-treated-recently";
+
+/// DCs for each proficiency rank and the healing bonus that comes with them
+const DIFFICULTIES: [(&str, f64, f64); 4] = [
+    ("Trained", 15.0, 0.0),
+    ("Expert", 20.0, 10.0),
+    ("Master", 30.0, 30.0),
+    ("Legendary", 40.0, 50.0),
+];
+
+/// The character doing the treating
+fn medic() -> Result<Actor, Error> {
+    selected_actor().ctx("Select a token or assign a character to treat wounds")
+}
+
+/// The creature being treated: the first targeted token, or the medic themselves
+fn patient(medic: &Actor) -> Result<Actor, Error> {
+    let user = Game::instance()?.user()?;
+    Ok(user
+        .targets()
+        .into_iter()
+        .find_map(|token| token.actor())
+        .unwrap_or_else(|| Actor::from(medic.as_js_value().clone())))
+}
+
+/// Flag key on the medic for when a patient stops being immune to one of their actions
+///
+/// Immunity is to a particular medic, so it's tracked by them. UUIDs have dots, which flags would
+/// treat as nesting.
+fn immunity_flag(action: &str, patient: &Actor) -> Option<String> {
+    Some(format!("{action}.{}", patient.uuid()?.replace('.', "_")))
+}
+
+/// When the patient's immunity to the medic's action ends, in world time, if it hasn't yet
+fn immune_until(action: &str, medic: &Actor, patient: &Actor) -> Result<Option<f64>, Error> {
+    let now = Game::instance()?.world_time();
+    Ok(immunity_flag(action, patient)
+        .and_then(|key| medic.get_flag(ID, &key).as_f64())
+        .filter(|until| *until > now))
+}
+
+/// Make the patient immune to the medic's action for the duration, asking the GM to record it if
+/// this user can't update the medic
+async fn record_immunity(
+    action: &str,
+    medic: &Actor,
+    patient: &Actor,
+    duration: f64,
+) -> Result<(), Error> {
+    let Some(key) = immunity_flag(action, patient) else {
+        return Ok(());
+    };
+    let until = Game::instance()?.world_time() + duration;
+    if medic.is_owner() {
+        return medic.set_flag(ID, &key, &JsValue::from(until)).await;
+    }
+    socket::emit(
+        ID,
+        &ImmunityMessage::MedicImmunity {
+            medic_uuid: medic.uuid().ctx("medic UUID")?,
+            key,
+            until,
+        },
+    )
+}
+
+/// Source data for the effect marking that the medic treated the patient in the last hour
+fn treated_effect(medic_name: &str) -> Result<JsValue, Error> {
+    let effect = format!(
+        r#"{{
+            "type": "effect",
+            "name": "Treated Recently ({medic_name})",
+            "img": "icons/svg/regen.svg",
+            "system": {{
+                "slug": "{TREATED_SLUG}",
+                "duration": {{ "value": 1, "unit": "hours", "expiry": "turn-start", "sustained": false }},
+                "tokenIcon": {{ "show": true }}
+            }}
+        }}"#
+    );
+    Ok(js_sys::JSON::parse(&effect)?)
+}
+
+/// Show on the patient that they were treated, asking the GM to add it if this user can't
+async fn mark_treated(medic: &Actor, patient: &Actor) -> Result<(), Error> {
+    if patient.is_owner() {
+        patient
+            .create_items(&[treated_effect(&medic.name())?])
+            .await?;
+        return Ok(());
+    }
+    socket::emit(
+        ID,
+        &ImmunityMessage::TreatedEffect {
+            patient_uuid: patient.uuid().ctx("patient UUID")?,
+            medic_name: medic.name(),
+        },
+    )
+}
+
+async fn receive(data: JsValue) -> Result<(), Error> {
+    let Ok(message) = serde_wasm_bindgen::from_value(data) else {
+        return Ok(());
+    };
+    if !is_responsible_gm(&Game::instance()?)? {
+        return Ok(());
+    }
+    match message {
+        ImmunityMessage::MedicImmunity {
+            medic_uuid,
+            key,
+            until,
+        } => {
+            let medic: Actor = Game::from_uuid(&medic_uuid).await?;
+            medic.set_flag(ID, &key, &JsValue::from(until)).await
+        }
+        ImmunityMessage::TreatedEffect {
+            patient_uuid,
+            medic_name,
+        } => {
+            let patient: Actor = Game::from_uuid(&patient_uuid).await?;
+            patient
+                .create_items(&[treated_effect(&medic_name)?])
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+/// Treat the patient's wounds at the given DC and give them an hour of immunity
+async fn treat_wounds(dc: f64) -> Result<(), Error> {
+    let medic = medic()?;
+    let patient = patient(&medic)?;
+    if immune_until("treatWounds", &medic, &patient)?.is_some() {
+        UI::notify_warn(&format!(
+            "{} was treated by {} in the last hour and is still immune to their Treat Wounds.",
+            patient.name(),
+            medic.name()
+        ));
+        return Ok(());
+    }
+    if heal(&medic, &patient, dc, "Treat Wounds").await? {
+        record_immunity("treatWounds", &medic, &patient, HOUR).await?;
+        mark_treated(&medic, &patient).await?;
+    }
+    Ok(())
+}
+
+/// Roll Medicine against the DC and apply the healing, or damage on a critical failure
+///
+/// Returns false if the check was cancelled.
+async fn heal(medic: &Actor, patient: &Actor, dc: f64, action: &str) -> Result<bool, Error> {
+    let bonus = DIFFICULTIES
+        .iter()
+        .find(|(_, difficulty, _)| *difficulty == dc)
+        .map(|(_, _, bonus)| *bonus)
+        .unwrap_or_default();
+
+    let Some(check) = medic.roll_skill("medicine", dc, None).await? else {
+        return Ok(false);
+    };
+    let degree = check
+        .degree_of_success()
+        .ctx("Medicine degree of success")?;
+    // whether the roll heals or damages the patient, if it does either
+    let outcome = match degree {
+        DegreeOfSuccess::CriticalSuccess => Some((format!("4d8 + {bonus}"), true)),
+        DegreeOfSuccess::Success => Some((format!("2d8 + {bonus}"), true)),
+        DegreeOfSuccess::Failure => None,
+        DegreeOfSuccess::CriticalFailure => Some(("1d8".to_string(), false)),
+    };
+    let Some((formula, healing)) = outcome else {
+        return Ok(true);
+    };
+
+    let amount = Roll::evaluate(&formula).await?.total();
+    let summary = if healing {
+        format!("regains <strong>{amount}</strong> Hit Points")
+    } else {
+        format!("takes <strong>{amount}</strong> damage")
+    };
+    Message::create(&format!(
+        "<p><strong>{patient_name}</strong> {summary} from {medic_name}'s {action} ({formula}).</p>",
+        patient_name = patient.name(),
+        medic_name = medic.name(),
+    ))
+    .await?;
+    if patient.is_owner() {
+        let amount = if healing { -amount } else { amount };
+        patient
+            .apply_damage(
+                &JsValue::from(amount),
+                patient.active_token().as_ref(),
+                healing,
+                false,
+            )
+            .await?;
+    } else {
+        UI::notify_warn(&format!(
+            "You don't own {}, so ask the GM to apply the result.",
+            patient.name()
+        ));
+    }
+    Ok(true)
+}
+
+/// Ask which DC to attempt, then carry on with the chosen one
+async fn choose_dc(action: &str, on_choose: fn(f64)) -> Result<(), Error> {
+    let medic = medic()?;
+    let patient = patient(&medic)?;
+    let labels: Vec<String> = DIFFICULTIES
+        .iter()
+        .map(|(rank, dc, _)| format!("{rank} (DC {dc})"))
+        .collect();
+    let buttons = DIFFICULTIES
+        .iter()
+        .zip(&labels)
+        .map(|((rank, dc, _), label)| {
+            let dc = *dc;
+            let choose_fn = Closure::wrap(Box::new(move || on_choose(dc)) as Box<dyn Fn()>);
+            (
+                *rank,
+                label.as_str(),
+                Some(choose_fn.into_js_value().unchecked_into()),
+            )
+        })
+        .collect();
+
+    application::show_dialog(
+        action,
+        format!(
+            "<p>{} uses {action} on {}. Choose the DC to attempt.</p>",
+            medic.name(),
+            patient.name()
+        ),
+        buttons,
+    )
+    .await
+}
+
+fn spawn_treat_wounds(dc: f64) {
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = treat_wounds(dc).await {
+            cprintln!("Error treating wounds: {err}");
+            UI::notify_error(&err.to_string());
+        }
+    });
+}
+
+pub fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.treatWounds(dc)
+        let treat_wounds_fn = Closure::wrap(Box::new(|dc: JsValue| match dc.as_f64() {
+            Some(dc) => spawn_treat_wounds(dc),
+            None => wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = choose_dc("Treat Wounds", spawn_treat_wounds).await {
+                    cprintln!("Error treating wounds: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            }),
+        }) as Box<dyn Fn(JsValue)>);
+        register_api("treatWounds", &treat_wounds_fn.into_js_value());
+    });
+
+    hook!("ready", || {
+        let result = socket::on(ID, |data| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = receive(data).await {
+                    cprintln!("Error recording medicine immunity: {err}");
+                }
+            });
+        });
+        if let Err(err) = result {
+            cprintln!("Error listening for medicine immunity: {err}");
+        }
+    });
+
+    battle_medicine::init();
+}
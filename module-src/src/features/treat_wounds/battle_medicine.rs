@@ -0,0 +1,100 @@
+use super::{choose_dc, heal, immune_until, medic, patient, record_immunity, HOUR};
+use crate::features::register_api;
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::hook;
+use wasm_bindgen::prelude::*;
+
+const DAY: f64 = 86_400.0;
+
+/// Tell the current user in chat that the patient is still immune
+async fn warn_immune(medic: &Actor, patient: &Actor, until: f64) -> Result<(), Error> {
+    let game = Game::instance()?;
+    let remaining = until - game.world_time();
+    let remaining = if remaining > HOUR {
+        format!("{} hours", (remaining / HOUR).ceil())
+    } else {
+        format!("{} minutes", (remaining / 60.0).ceil())
+    };
+    Message::create_whisper(
+        &format!(
+            "<p><strong>{patient_name}</strong> is immune to {medic_name}'s Battle Medicine for another {remaining}.</p>",
+            patient_name = patient.name(),
+            medic_name = medic.name(),
+        ),
+        &game.user()?.id().into_iter().collect::<Vec<_>>(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Use Battle Medicine on the patient at the given DC and record their immunity
+async fn battle_medicine(dc: f64) -> Result<(), Error> {
+    let medic = medic()?;
+    let patient = patient(&medic)?;
+    if let Some(until) = immune_until("battleMedicine", &medic, &patient)? {
+        return warn_immune(&medic, &patient, until).await;
+    }
+    if !heal(&medic, &patient, dc, "Battle Medicine").await? {
+        return Ok(());
+    }
+    // Godless Healing shortens the immunity to an hour
+    let duration = if medic.has_feat("godless-healing") {
+        HOUR
+    } else {
+        DAY
+    };
+    record_immunity("battleMedicine", &medic, &patient, duration).await
+}
+
+/// Warn when the Battle Medicine feat is posted to chat for a target that's still immune
+async fn check_posted_feat(message: Message) -> Result<(), Error> {
+    if message.item_slug().as_deref() != Some("battle-medicine") {
+        return Ok(());
+    }
+    let user = Game::instance()?.user()?;
+    if message.author().and_then(|author| author.id()) != user.id() {
+        return Ok(());
+    }
+    let Some(medic) = message.speaker_actor() else {
+        return Ok(());
+    };
+    let Some(patient) = user.targets().into_iter().find_map(|token| token.actor()) else {
+        return Ok(());
+    };
+    if let Some(until) = immune_until("battleMedicine", &medic, &patient)? {
+        warn_immune(&medic, &patient, until).await?;
+    }
+    Ok(())
+}
+
+fn spawn_battle_medicine(dc: f64) {
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = battle_medicine(dc).await {
+            cprintln!("Error using battle medicine: {err}");
+            UI::notify_error(&err.to_string());
+        }
+    });
+}
+
+pub(super) fn init() {
+    hook!("init", || {
+        // game.modules.get("johnys-module").api.battleMedicine(dc)
+        let battle_medicine_fn = Closure::wrap(Box::new(|dc: JsValue| match dc.as_f64() {
+            Some(dc) => spawn_battle_medicine(dc),
+            None => wasm_bindgen_futures::spawn_local(async {
+                if let Err(err) = choose_dc("Battle Medicine", spawn_battle_medicine).await {
+                    cprintln!("Error using battle medicine: {err}");
+                    UI::notify_error(&err.to_string());
+                }
+            }),
+        }) as Box<dyn Fn(JsValue)>);
+        register_api("battleMedicine", &battle_medicine_fn.into_js_value());
+    });
+
+    hook!("createChatMessage", async |message: JsValue| {
+        if let Err(err) = check_posted_feat(message.into()).await {
+            cprintln!("Error checking battle medicine immunity: {err}");
+        }
+    });
+}
@@ -0,0 +1,277 @@
+use crate::features::{is_enabled, is_responsible_gm};
+use crate::foundry::application;
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    /// Frightened value of the current combatant's actor when its turn started, by actor UUID
+    static FRIGHTENED_AT_START: RefCell<HashMap<String, f64>> = RefCell::new(HashMap::new());
+}
+
+/// How a condition or effect is handled at the end of a turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EndOfTurnMode {
+    Off,
+    Automatic,
+    Confirm,
+}
+
+impl EndOfTurnMode {
+    fn from_setting(key: &str) -> Self {
+        match get_setting(ID, key).as_string().as_deref() {
+            Some("auto") => EndOfTurnMode::Automatic,
+            Some("confirm") => EndOfTurnMode::Confirm,
+            _ => EndOfTurnMode::Off,
+        }
+    }
+}
+
+/// Check if the GM wants reminding about Frightened at the end of a turn
+///
+/// Automatic reduction was dropped since PF2e already does it, so worlds that picked it get the
+/// reminder instead.
+fn frightened_reminders_enabled() -> bool {
+    matches!(
+        get_setting(ID, "turnConditionsFrightened")
+            .as_string()
+            .as_deref(),
+        Some("confirm" | "auto")
+    )
+}
+
+/// Get the actor of the combatant whose turn just ended
+fn prior_actor(combat: &Combat, prior: &JsValue) -> Option<Actor> {
+    let combatant_id = get_string_property(prior, "combatantId")?;
+    combat.get_combatant(&combatant_id)?.actor()
+}
+
+/// Note the frightened value of the combatant whose turn just started
+fn remember_frightened(combat: &Combat) {
+    let Some(actor) = combat.combatant().and_then(|combatant| combatant.actor()) else {
+        return;
+    };
+    let Some(uuid) = actor.uuid() else {
+        return;
+    };
+    let value = actor.condition_value("frightened").unwrap_or_default();
+    FRIGHTENED_AT_START.with_borrow_mut(|values| values.insert(uuid, value));
+}
+
+/// Take the frightened value noted when the actor's turn started
+fn frightened_at_start(actor: &Actor) -> Option<f64> {
+    let uuid = actor.uuid()?;
+    FRIGHTENED_AT_START.with_borrow_mut(|values| values.remove(&uuid))
+}
+
+/// Remind the GM that the combatant whose turn just ended is frightened, offering to reduce it
+///
+/// PF2e already lowers Frightened at the end of a turn, so there's only a reminder if the value
+/// hasn't dropped since the turn started.
+async fn reduce_frightened(actor: &Actor, at_start: Option<f64>) -> Result<(), Error> {
+    if !frightened_reminders_enabled() || !is_responsible_gm(&Game::instance()?)? {
+        return Ok(());
+    }
+    let Some(value) = actor.condition_value("frightened") else {
+        return Ok(());
+    };
+    if at_start.is_some_and(|at_start| value < at_start) {
+        return Ok(());
+    }
+
+    let name = actor.name();
+    let actor_js = actor.as_js_value().clone();
+    let reduce_fn = Closure::wrap(Box::new(move || {
+        let actor = Actor::from(actor_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = actor.decrease_condition("frightened").await {
+                cprintln!("Error reducing frightened: {err}");
+            }
+        });
+    }) as Box<dyn Fn()>);
+
+    application::show_dialog(
+        "End of Turn: Frightened",
+        format!(
+            "<p>{name}'s turn ended while Frightened {value}. PF2e lowers it at the end of each turn; reduce it now if that didn't happen?</p>"
+        ),
+        vec![
+            (
+                "reduce",
+                "Reduce",
+                Some(reduce_fn.into_js_value().unchecked_into()),
+            ),
+            ("skip", "Skip", None),
+        ],
+    )
+    .await
+}
+
+/// Remove the effects that ran out on the combatant whose turn just ended, asking the GM first if configured
+async fn remove_expired_effects(actor: &Actor) -> Result<(), Error> {
+    let mode = EndOfTurnMode::from_setting("turnConditionsExpiredEffects");
+    if mode == EndOfTurnMode::Off || !is_responsible_gm(&Game::instance()?)? {
+        return Ok(());
+    }
+    let expired: Vec<Item> = actor
+        .effects()
+        .into_iter()
+        .filter(Item::is_expired)
+        .collect();
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    if mode == EndOfTurnMode::Automatic {
+        return delete_effects(actor, &expired).await;
+    }
+
+    let names: Vec<String> = expired.iter().map(Item::name).collect();
+    let actor_js = actor.as_js_value().clone();
+    let effects_js: Vec<JsValue> = expired
+        .iter()
+        .map(|effect| effect.as_js_value().clone())
+        .collect();
+    let remove_fn = Closure::wrap(Box::new(move || {
+        let actor = Actor::from(actor_js.clone());
+        let effects: Vec<Item> = effects_js.iter().cloned().map(Item::from).collect();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = delete_effects(&actor, &effects).await {
+                cprintln!("Error removing expired effects: {err}");
+            }
+        });
+    }) as Box<dyn Fn()>);
+
+    application::show_dialog(
+        "End of Turn: Expired Effects",
+        format!(
+            "<p>{name}'s turn ended and these effects have run out: {effects}. Remove them?</p>",
+            name = actor.name(),
+            effects = names.join(", "),
+        ),
+        vec![
+            (
+                "remove",
+                "Remove",
+                Some(remove_fn.into_js_value().unchecked_into()),
+            ),
+            ("skip", "Skip", None),
+        ],
+    )
+    .await
+}
+
+async fn delete_effects(actor: &Actor, effects: &[Item]) -> Result<(), Error> {
+    let ids: Vec<String> = effects.iter().filter_map(Item::id).collect();
+    actor.delete_items(&ids).await?;
+    let names: Vec<String> = effects.iter().map(Item::name).collect();
+    Message::create(&format!(
+        r#"<p class="johnys-effect-ended">{} ended on {}.</p>"#,
+        names.join(", "),
+        actor.name()
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Remind the owner of a sickened combatant that they can retch to lower it
+async fn prompt_retch(actor: &Actor) -> Result<(), Error> {
+    if !is_enabled("turnConditionsRetch")
+        || !actor.is_owned_by_current_user(GMStrategy::IfNoPlayers, OwnershipLevel::Owner)
+    {
+        return Ok(());
+    }
+    let Some(value) = actor.condition_value("sickened") else {
+        return Ok(());
+    };
+
+    let name = actor.name();
+    let actor_js = actor.as_js_value().clone();
+    let roll_fn = Closure::wrap(Box::new(move || {
+        let actor = Actor::from(actor_js.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(err) = actor.roll_save("fortitude").await {
+                cprintln!("Error rolling retch save: {err}");
+                UI::notify_error(&format!("Unable to roll Fortitude: {err}"));
+            }
+        });
+    }) as Box<dyn Fn()>);
+
+    application::show_dialog(
+        "Sickened",
+        format!(
+            "<p>{name} is Sickened {value}. On their next turn they can spend an action to retch, attempting a Fortitude save against the effect's DC to reduce the condition by 1 (2 on a critical success).</p>"
+        ),
+        vec![
+            (
+                "roll",
+                "Roll Fortitude",
+                Some(roll_fn.into_js_value().unchecked_into()),
+            ),
+            ("dismiss", "Dismiss", None),
+        ],
+    )
+    .await
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("End of Turn Frightened")
+            .hint("When a frightened combatant's turn ends, remind the GM and offer to reduce the condition by 1 if PF2e hasn't already lowered it. PF2e does the reducing itself, so there's no automatic option; worlds that chose the old one get the reminder.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .choices(&[("off", "Off"), ("confirm", "Remind GM")])
+            .default_string("off")
+            .register(ID, "turnConditionsFrightened");
+
+        SettingConfig::new()
+            .name("End of Turn Expired Effects")
+            .hint("When a combatant's turn ends, remove effects on it whose duration has run out and post which ended to chat. Confirm asks the GM first.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .choices(&[
+                ("off", "Off"),
+                ("auto", "Automatic"),
+                ("confirm", "Confirm with GM"),
+            ])
+            .default_string("off")
+            .register(ID, "turnConditionsExpiredEffects");
+
+        SettingConfig::new()
+            .name("Sickened Retch Reminders")
+            .hint("When a sickened combatant's turn ends, remind its owner that they can retch and offer to roll the Fortitude save.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "turnConditionsRetch");
+    });
+
+    hook!(
+        "combatTurnChange",
+        async |combat: JsValue, prior: JsValue| {
+            let combat = Combat::from(combat);
+            let actor = prior_actor(&combat, &prior);
+            let at_start = actor.as_ref().and_then(frightened_at_start);
+            remember_frightened(&combat);
+            let Some(actor) = actor else {
+                return;
+            };
+            if let Err(err) = reduce_frightened(&actor, at_start).await {
+                cprintln!("Error handling frightened at end of turn: {err}");
+            }
+            if let Err(err) = remove_expired_effects(&actor).await {
+                cprintln!("Error removing expired effects at end of turn: {err}");
+            }
+            if let Err(err) = prompt_retch(&actor).await {
+                cprintln!("Error prompting sickened retch: {err}");
+            }
+        }
+    );
+}
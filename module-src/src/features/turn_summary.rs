@@ -0,0 +1,126 @@
+use crate::features::is_enabled;
+use crate::foundry::error::Error;
+use crate::foundry::{cprintln, *};
+use crate::{hook, ID};
+use wasm_bindgen::prelude::*;
+
+/// Seconds in a combat round
+const ROUND_SECONDS: f64 = 6.0;
+
+/// Conditions that leave a creature unable to take reactions
+const NO_REACTIONS: [&str; 5] = [
+    "confused",
+    "paralyzed",
+    "petrified",
+    "stunned",
+    "unconscious",
+];
+
+/// Whisper the owner what's affecting their creature when its turn starts
+async fn summarize_turn(combat: Combat) -> Result<(), Error> {
+    if !is_enabled("turnStartSummary") {
+        return Ok(());
+    }
+    let Some(actor) = combat.combatant().and_then(|combatant| combatant.actor()) else {
+        return Ok(());
+    };
+    if !actor.is_owned_by_current_user(GMStrategy::IfNoPlayers, OwnershipLevel::Owner) {
+        return Ok(());
+    }
+
+    let conditions = actor.conditions();
+    let active: Vec<String> = conditions
+        .iter()
+        .filter(|condition| condition.persistent_damage().is_none())
+        .map(|condition| match condition.condition_value() {
+            Some(value) => format!("{} {value}", condition.name()),
+            None => condition.name(),
+        })
+        .collect();
+    let persistent: Vec<String> = conditions
+        .iter()
+        .filter_map(Item::persistent_damage)
+        .map(|persistent| {
+            format!(
+                "{} {} (DC {})",
+                persistent.formula, persistent.damage_type, persistent.dc
+            )
+        })
+        .collect();
+    let blocking: Vec<String> = conditions
+        .iter()
+        .filter(|condition| {
+            condition
+                .slug()
+                .is_some_and(|slug| NO_REACTIONS.contains(&slug.as_str()))
+        })
+        .map(Item::name)
+        .collect();
+    let expiring: Vec<String> = actor
+        .effects()
+        .iter()
+        .filter(|effect| {
+            effect
+                .remaining_duration()
+                .is_some_and(|remaining| remaining < ROUND_SECONDS)
+        })
+        .map(Item::name)
+        .collect();
+
+    let mut lines = Vec::new();
+    if !active.is_empty() {
+        lines.push(format!("<li>Conditions: {}</li>", active.join(", ")));
+    }
+    if !persistent.is_empty() {
+        lines.push(format!(
+            "<li>Persistent damage: {}</li>",
+            persistent.join(", ")
+        ));
+    }
+    // reactions come back at the start of each turn
+    lines.push(if blocking.is_empty() {
+        "<li>Reaction available</li>".to_string()
+    } else {
+        format!("<li>No reactions while {}</li>", blocking.join(", "))
+    });
+    if !expiring.is_empty() {
+        lines.push(format!(
+            "<li>Ending this turn: {}</li>",
+            expiring.join(", ")
+        ));
+    }
+
+    let user_ids: Vec<String> = Game::instance()?.user()?.id().into_iter().collect();
+    Message::create_whisper(
+        &format!(
+            r#"<div class="johnys-turn-summary"><p><strong>{}'s turn</strong></p><ul>{}</ul></div>"#,
+            actor.name(),
+            lines.join("")
+        ),
+        &user_ids,
+    )
+    .await?;
+    Ok(())
+}
+
+pub fn init() {
+    hook!("init", || {
+        SettingConfig::new()
+            .name("Turn Start Summary")
+            .hint("At the start of your creature's turn, whisper yourself its conditions, persistent damage, whether it has a reaction, and effects ending this turn.")
+            .scope("client")
+            .config(true)
+            .type_boolean()
+            .default_bool(false)
+            .register(ID, "turnStartSummary");
+    });
+
+    hook!(
+        "combatTurnChange",
+        async |combat: JsValue, _prior: JsValue| {
+            if let Err(err) = summarize_turn(combat.into()).await {
+                cprintln!("Error summarizing turn: {err}");
+            }
+        }
+    );
+}
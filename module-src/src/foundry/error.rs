@@ -17,6 +17,12 @@ impl From<&str> for Error {
     }
 }
 
+impl From<String> for Error {
+    fn from(value: String) -> Self {
+        Self::Custom(value)
+    }
+}
+
 impl From<JsValue> for Error {
     fn from(value: JsValue) -> Self {
         Self::JsValue(value)
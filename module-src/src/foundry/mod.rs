@@ -4,6 +4,7 @@
 // It wraps the JavaScript objects in strongly-typed Rust structs.
 #![allow(dead_code)]
 
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 
@@ -53,6 +54,37 @@ extern "C" {
 
     #[wasm_bindgen(js_namespace = ["game", "settings"], js_name = get)]
     pub fn get_setting(module: &str, key: &str) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = ["game", "settings"], js_name = registerMenu)]
+    fn register_menu_raw(module: &str, key: &str, data: &JsValue);
+
+    #[wasm_bindgen(js_namespace = ["game", "keybindings"], js_name = register)]
+    fn register_keybinding_raw(module: &str, action: &str, data: &JsValue);
+
+    #[wasm_bindgen(catch, js_namespace = ["game", "settings"], js_name = set)]
+    async fn set_setting_raw(module: &str, key: &str, value: &JsValue) -> Result<JsValue, JsValue>;
+
+    /// Translate a key from the module's language files
+    #[wasm_bindgen(js_namespace = ["game", "i18n"], js_name = localize)]
+    pub fn localize(key: &str) -> String;
+
+    #[wasm_bindgen(js_namespace = ["game", "i18n"], js_name = format)]
+    fn format_raw(key: &str, data: &JsValue) -> String;
+}
+
+/// Translate a key, filling in `{name}` placeholders from `data`
+pub fn localize_format(key: &str, data: &[(&str, &str)]) -> String {
+    let obj = js_sys::Object::new();
+    for (name, value) in data {
+        js_sys::Reflect::set(&obj, jstr!(name), jstr!(value)).ok();
+    }
+    format_raw(key, &obj)
+}
+
+/// Store a new value for a registered setting
+pub async fn set_setting(module: &str, key: &str, value: &JsValue) -> Result<(), Error> {
+    set_setting_raw(module, key, value).await?;
+    Ok(())
 }
 
 pub fn get_property(obj: &JsValue, key: &str) -> Result<JsValue, JsValue> {
@@ -67,6 +99,27 @@ fn get_f64_property(obj: &JsValue, key: &str) -> Option<f64> {
     get_property(obj, key).ok()?.as_f64()
 }
 
+/// Call a method on a JS object with the given arguments
+pub fn call_method(obj: &JsValue, method: &str, args: &[&JsValue]) -> Result<JsValue, Error> {
+    let method_fn = get_property(obj, method)?;
+    let js_args: js_sys::Array = args.iter().copied().collect();
+    Ok(js_sys::Reflect::apply(
+        method_fn.unchecked_ref(),
+        obj,
+        &js_args,
+    )?)
+}
+
+/// Call a method that returns a promise and wait for it to resolve
+pub async fn call_method_async(
+    obj: &JsValue,
+    method: &str,
+    args: &[&JsValue],
+) -> Result<JsValue, Error> {
+    let promise = call_method(obj, method, args)?;
+    Ok(JsFuture::from(js_sys::Promise::resolve(&promise)).await?)
+}
+
 /// Builder for creating Foundry VTT settings
 pub struct SettingConfig {
     config: js_sys::Object,
@@ -120,6 +173,13 @@ impl SettingConfig {
         self
     }
 
+    pub fn type_object(self) -> Self {
+        let global = js_sys::global();
+        let object_constructor = js_sys::Reflect::get(&global, jstr!("Object")).unwrap();
+        js_sys::Reflect::set(&self.config, jstr!("type"), &object_constructor).unwrap();
+        self
+    }
+
     pub fn default_string(self, default: &str) -> Self {
         js_sys::Reflect::set(&self.config, jstr!("default"), jstr!(default)).unwrap();
         self
@@ -135,6 +195,27 @@ impl SettingConfig {
         self
     }
 
+    pub fn default_value(self, default: &JsValue) -> Self {
+        js_sys::Reflect::set(&self.config, jstr!("default"), default).unwrap();
+        self
+    }
+
+    /// Render a number setting as a slider between `min` and `max`
+    pub fn range(self, min: f64, max: f64, step: f64) -> Self {
+        let range_obj = js_sys::Object::new();
+        js_sys::Reflect::set(&range_obj, jstr!("min"), &JsValue::from(min)).unwrap();
+        js_sys::Reflect::set(&range_obj, jstr!("max"), &JsValue::from(max)).unwrap();
+        js_sys::Reflect::set(&range_obj, jstr!("step"), &JsValue::from(step)).unwrap();
+        js_sys::Reflect::set(&self.config, jstr!("range"), &range_obj).unwrap();
+        self
+    }
+
+    /// Show a FilePicker for this string setting, e.g. "audio", "image", or "any"
+    pub fn file_picker(self, kind: &str) -> Self {
+        js_sys::Reflect::set(&self.config, jstr!("filePicker"), jstr!(kind)).unwrap();
+        self
+    }
+
     pub fn choices(self, choices: &[(&str, &str)]) -> Self {
         let choices_obj = js_sys::Object::new();
         for (key, value) in choices {
@@ -149,6 +230,249 @@ impl SettingConfig {
     }
 }
 
+/// Builder for a button in the module settings that opens a custom window
+pub struct SettingsMenu {
+    config: js_sys::Object,
+}
+
+impl SettingsMenu {
+    pub fn new() -> Self {
+        Self {
+            config: js_sys::Object::new(),
+        }
+    }
+
+    pub fn name(self, name: &str) -> Self {
+        js_sys::Reflect::set(&self.config, jstr!("name"), jstr!(name)).unwrap();
+        self
+    }
+
+    /// Text shown on the button itself
+    pub fn label(self, label: &str) -> Self {
+        js_sys::Reflect::set(&self.config, jstr!("label"), jstr!(label)).unwrap();
+        self
+    }
+
+    pub fn hint(self, hint: &str) -> Self {
+        js_sys::Reflect::set(&self.config, jstr!("hint"), jstr!(hint)).unwrap();
+        self
+    }
+
+    /// Font Awesome icon class, e.g. "fa-solid fa-bars"
+    pub fn icon(self, icon: &str) -> Self {
+        js_sys::Reflect::set(&self.config, jstr!("icon"), jstr!(icon)).unwrap();
+        self
+    }
+
+    /// Only show the menu to GMs
+    pub fn restricted(self, restricted: bool) -> Self {
+        js_sys::Reflect::set(
+            &self.config,
+            jstr!("restricted"),
+            &JsValue::from(restricted),
+        )
+        .unwrap();
+        self
+    }
+
+    /// Run `on_open` when the menu button is clicked
+    ///
+    /// Foundry expects an application class here; all it does is construct it and call `render`,
+    /// so a bare class whose `render` calls back into rust is enough.
+    pub fn on_open(self, on_open: impl Fn() + 'static) -> Self {
+        let callback = Closure::wrap(Box::new(on_open) as Box<dyn Fn()>);
+        let factory = js_sys::Function::new_with_args(
+            "open",
+            "return class { render() { open(); return this; } }",
+        );
+        let class = factory
+            .call1(&JsValue::NULL, &callback.into_js_value())
+            .unwrap();
+        js_sys::Reflect::set(&self.config, jstr!("type"), &class).unwrap();
+        self
+    }
+
+    pub fn register(self, module_id: &str, key: &str) {
+        register_menu_raw(module_id, key, &self.config);
+    }
+}
+
+/// Builder for a keybinding players can rebind under Configure Controls
+///
+/// Keybindings have to be registered during the `init` hook.
+pub struct Keybinding {
+    config: js_sys::Object,
+    editable: js_sys::Array,
+}
+
+impl Keybinding {
+    pub fn new() -> Self {
+        Self {
+            config: js_sys::Object::new(),
+            editable: js_sys::Array::new(),
+        }
+    }
+
+    pub fn name(self, name: &str) -> Self {
+        js_sys::Reflect::set(&self.config, jstr!("name"), jstr!(name)).unwrap();
+        self
+    }
+
+    pub fn hint(self, hint: &str) -> Self {
+        js_sys::Reflect::set(&self.config, jstr!("hint"), jstr!(hint)).unwrap();
+        self
+    }
+
+    /// Add a default binding, e.g. `key("KeyR", &["Shift"])`
+    ///
+    /// Without one the action starts unbound.
+    pub fn key(self, key: &str, modifiers: &[&str]) -> Self {
+        let binding = js_sys::Object::new();
+        let modifiers: js_sys::Array = modifiers.iter().map(|m| JsValue::from_str(m)).collect();
+        js_sys::Reflect::set(&binding, jstr!("key"), jstr!(key)).unwrap();
+        js_sys::Reflect::set(&binding, jstr!("modifiers"), &modifiers).unwrap();
+        self.editable.push(&binding);
+        self
+    }
+
+    /// Only let GMs use the keybinding
+    pub fn restricted(self, restricted: bool) -> Self {
+        js_sys::Reflect::set(
+            &self.config,
+            jstr!("restricted"),
+            &JsValue::from(restricted),
+        )
+        .unwrap();
+        self
+    }
+
+    /// Run `on_down` when the key is pressed
+    pub fn on_down(self, on_down: impl Fn() + 'static) -> Self {
+        // returning true tells Foundry the key was handled
+        let callback = Closure::wrap(Box::new(move || {
+            on_down();
+            true
+        }) as Box<dyn Fn() -> bool>);
+        js_sys::Reflect::set(&self.config, jstr!("onDown"), &callback.into_js_value()).unwrap();
+        self
+    }
+
+    pub fn register(self, module_id: &str, action: &str) {
+        js_sys::Reflect::set(&self.config, jstr!("editable"), &self.editable).unwrap();
+        register_keybinding_raw(module_id, action, &self.config);
+    }
+}
+
+/// Build an entry for a Foundry context menu
+///
+/// `condition` decides if the entry is shown for the clicked element, `callback` runs when chosen.
+pub fn context_menu_entry(
+    name: &str,
+    icon: &str,
+    condition: impl Fn(HtmlElement) -> bool + 'static,
+    callback: impl Fn(HtmlElement) + 'static,
+) -> JsValue {
+    let entry = js_sys::Object::new();
+    let condition =
+        Closure::wrap(Box::new(move |target: JsValue| condition(target.into()))
+            as Box<dyn Fn(JsValue) -> bool>);
+    let callback = Closure::wrap(
+        Box::new(move |target: JsValue| callback(target.into())) as Box<dyn Fn(JsValue)>
+    );
+    js_sys::Reflect::set(&entry, jstr!("name"), jstr!(name)).unwrap();
+    js_sys::Reflect::set(
+        &entry,
+        jstr!("icon"),
+        jstr!(&format!(r#"<i class="{icon}"></i>"#)),
+    )
+    .unwrap();
+    js_sys::Reflect::set(&entry, jstr!("condition"), &condition.into_js_value()).unwrap();
+    js_sys::Reflect::set(&entry, jstr!("callback"), &callback.into_js_value()).unwrap();
+    entry.into()
+}
+
+/// Messaging between clients over the module's socket
+///
+/// Requires `"socket": true` in module.json.
+pub mod socket {
+    use super::*;
+
+    fn event_name(module_id: &str) -> String {
+        format!("module.{module_id}")
+    }
+
+    /// Send data to every other connected client
+    pub fn emit<T: Serialize + ?Sized>(module_id: &str, data: &T) -> Result<(), Error> {
+        let data = data
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| Error::Custom(format!("Failed to serialize socket data: {e}")))?;
+        let socket = get_path!(&JsValue::from(js_sys::global()), "game.socket")?;
+        call_method(&socket, "emit", &[jstr!(&event_name(module_id)), &data])?;
+        Ok(())
+    }
+
+    /// Listen for data sent by other clients
+    pub fn on(module_id: &str, handler: impl Fn(JsValue) + 'static) -> Result<(), Error> {
+        let handler = Closure::wrap(Box::new(handler) as Box<dyn Fn(JsValue)>);
+        let socket = get_path!(&JsValue::from(js_sys::global()), "game.socket")?;
+        call_method(
+            &socket,
+            "on",
+            &[jstr!(&event_name(module_id)), &handler.into_js_value()],
+        )?;
+        Ok(())
+    }
+
+    /// Listen for data sent by other clients, along with the ID of the user who sent it
+    pub fn on_with_sender(
+        module_id: &str,
+        handler: impl Fn(JsValue, String) + 'static,
+    ) -> Result<(), Error> {
+        // Foundry passes the sender's user ID after the data
+        let handler = Closure::wrap(Box::new(move |data: JsValue, user_id: JsValue| {
+            handler(data, user_id.as_string().unwrap_or_default())
+        }) as Box<dyn Fn(JsValue, JsValue)>);
+        let socket = get_path!(&JsValue::from(js_sys::global()), "game.socket")?;
+        call_method(
+            &socket,
+            "on",
+            &[jstr!(&event_name(module_id)), &handler.into_js_value()],
+        )?;
+        Ok(())
+    }
+}
+
+/// Play a sound effect for this client only
+pub fn play_sound(src: &str, volume: f64) -> Result<(), Error> {
+    let global: JsValue = js_sys::global().into();
+    let audio_helper = get_path!(&global, "foundry.audio.AudioHelper")?;
+
+    let data = js_sys::Object::new();
+    js_sys::Reflect::set(&data, jstr!("src"), jstr!(src))?;
+    js_sys::Reflect::set(&data, jstr!("volume"), &JsValue::from(volume))?;
+    js_sys::Reflect::set(&data, jstr!("autoplay"), &JsValue::TRUE)?;
+    js_sys::Reflect::set(&data, jstr!("loop"), &JsValue::FALSE)?;
+
+    call_method(&audio_helper, "play", &[&data, &JsValue::FALSE])?;
+    Ok(())
+}
+
+/// Escape text such as a document name so it can be put into HTML as-is
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 pub struct Game {
     inner: JsValue,
 }
@@ -163,6 +487,40 @@ impl UI {
         Ok(Self { inner })
     }
 
+    /// Check if the chat log is the open tab of an expanded sidebar
+    pub fn is_chat_visible(&self) -> bool {
+        let Ok(sidebar) = get_property(&self.inner, "sidebar") else {
+            return false;
+        };
+        let expanded = get_property(&sidebar, "expanded")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let active_tab = get_path!(&sidebar, "tabGroups.primary")
+            .ok()
+            .and_then(|v| v.as_string());
+        expanded && active_tab.as_deref() == Some("chat")
+    }
+
+    /// Switch the sidebar to the chat log, expanding the sidebar too if `expand` is set
+    pub fn show_chat(&self, expand: bool) -> Result<(), Error> {
+        let sidebar = get_property(&self.inner, "sidebar")?;
+        if expand {
+            call_method(&sidebar, "expand", &[])?;
+        }
+        call_method(&sidebar, "changeTab", &[jstr!("chat"), jstr!("primary")])?;
+        Ok(())
+    }
+
+    /// Find a message's entry in the chat log
+    pub fn chat_message_element(&self, message_id: &str) -> Option<HtmlElement> {
+        let element = get_path!(&self.inner, "chat.element").ok()?;
+        HtmlElement::from(element)
+            .query_selector(&format!(r#"[data-message-id="{message_id}"]"#))
+            .ok()
+            .flatten()
+    }
+
     /// Convenience method to show an error notification
     pub fn notify_error(message: &str) {
         if let Ok(ui) = Self::instance() {
@@ -222,6 +580,38 @@ impl Game {
         Ok(inner.into())
     }
 
+    /// Get the in-game time, in seconds
+    pub fn world_time(&self) -> f64 {
+        get_path!(&self.inner, "time.worldTime")
+            .ok()
+            .and_then(|v| v.as_f64())
+            .unwrap_or_default()
+    }
+
+    /// Move the in-game clock forward
+    pub async fn advance_time(&self, seconds: f64) -> Result<(), Error> {
+        let time = get_property(&self.inner, "time")?;
+        call_method_async(&time, "advance", &[&JsValue::from(seconds)]).await?;
+        Ok(())
+    }
+
+    /// Get the active combat encounter, if any
+    pub fn combat(&self) -> Option<Combat> {
+        let combat = get_property(&self.inner, "combat").ok()?;
+        if combat.is_null() || combat.is_undefined() {
+            None
+        } else {
+            Some(combat.into())
+        }
+    }
+
+    /// Get all actors in the world
+    pub fn actors(&self) -> Vec<Actor> {
+        get_property(&self.inner, "actors")
+            .map(|actors| js_iter!(actors).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
     /// Get all users in the game
     pub fn users(&self) -> Result<UserCollection, Error> {
         let inner = get_property(&self.inner, "users")?;
@@ -244,12 +634,35 @@ impl Game {
         }
     }
 
+    /// Get the chat log's messages, oldest first
+    pub fn messages(&self) -> Vec<Message> {
+        get_property(&self.inner, "messages")
+            .map(|messages| js_iter!(messages).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
     /// Resolve a UUID to an actor
     pub async fn from_uuid(uuid: &str) -> Result<Actor, Error> {
         let inner = from_uuid_raw(uuid).await?;
         Ok(inner.into())
     }
 
+    /// Load an actor by UUID, accepting a token's UUID in place of its actor's
+    pub async fn actor_from_uuid(uuid: &str) -> Result<Actor, Error> {
+        let document = from_uuid_raw(uuid).await?;
+        if document.is_null() || document.is_undefined() {
+            return Err(Error::Custom(format!("Nothing found for {uuid}")));
+        }
+        if get_string_property(&document, "documentName").as_deref() == Some("Token") {
+            let actor = get_property(&document, "actor")?;
+            if actor.is_null() || actor.is_undefined() {
+                return Err(Error::Custom(format!("Token {uuid} has no actor")));
+            }
+            return Ok(actor.into());
+        }
+        Ok(document.into())
+    }
+
     /// Get the currently controlled tokens
     pub fn controlled_tokens(&self) -> Vec<Token> {
         let mut tokens = Vec::new();
@@ -258,10 +671,8 @@ impl Game {
             if let Ok(tokens_layer) = get_property(&canvas, "tokens") {
                 if let Ok(controlled) = get_property(&tokens_layer, "controlled") {
                     if let Ok(Some(iter)) = js_sys::try_iter(&controlled) {
-                        for item in iter {
-                            if let Ok(inner) = item {
-                                tokens.push(inner.into());
-                            }
+                        for inner in iter.flatten() {
+                            tokens.push(inner.into());
                         }
                     }
                 }
@@ -273,21 +684,7 @@ impl Game {
 
     /// Get the current user's targeted tokens
     pub fn user_targets(&self) -> Vec<Token> {
-        let mut tokens = Vec::new();
-
-        if let Ok(user) = self.user() {
-            if let Ok(targets) = get_property(user.as_js_value(), "targets") {
-                if let Ok(Some(iter)) = js_sys::try_iter(&targets) {
-                    for item in iter {
-                        if let Ok(inner) = item {
-                            tokens.push(inner.into());
-                        }
-                    }
-                }
-            }
-        }
-
-        tokens
+        self.user().map(|user| user.targets()).unwrap_or_default()
     }
 
     /// Get the token currently being hovered over
@@ -304,6 +701,57 @@ impl Game {
         None
     }
 
+    /// Find the placed token for a token document's UUID, if it's on the current scene
+    pub async fn token_from_uuid(uuid: &str) -> Result<Option<Token>, Error> {
+        let document = from_uuid_raw(uuid).await?;
+        if document.is_null() || document.is_undefined() {
+            return Ok(None);
+        }
+        let token = get_property(&document, "object")?;
+        if token.is_null() || token.is_undefined() {
+            Ok(None)
+        } else {
+            Ok(Some(token.into()))
+        }
+    }
+
+    /// Get how many canvas pixels there are to a foot on the current scene
+    pub fn pixels_per_foot(&self) -> Option<f64> {
+        let dimensions = get_path!(&self.inner, "canvas.dimensions").ok()?;
+        Some(get_f64_property(&dimensions, "size")? / get_f64_property(&dimensions, "distance")?)
+    }
+
+    /// Get every token on the current scene
+    pub fn scene_tokens(&self) -> Vec<Token> {
+        get_path!(&self.inner, "canvas.tokens.placeables")
+            .map(|placeables| js_iter!(placeables).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
+    /// Check if a wall blocks the line between two canvas points, for "move" or "sight"
+    pub fn wall_between(from: (f64, f64), to: (f64, f64), kind: &str) -> bool {
+        let global: JsValue = js_sys::global().into();
+        let Ok(backend) = get_path!(&global, &format!("CONFIG.Canvas.polygonBackends.{kind}"))
+        else {
+            return false;
+        };
+        let point = |(x, y): (f64, f64)| {
+            let point = js_sys::Object::new();
+            js_sys::Reflect::set(&point, jstr!("x"), &JsValue::from(x)).ok();
+            js_sys::Reflect::set(&point, jstr!("y"), &JsValue::from(y)).ok();
+            point
+        };
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, jstr!("type"), jstr!(kind)).ok();
+        js_sys::Reflect::set(&options, jstr!("mode"), jstr!("any")).ok();
+        call_method(
+            &backend,
+            "testCollision",
+            &[&point(from), &point(to), &options],
+        )
+        .is_ok_and(|hit| hit.is_truthy())
+    }
+
     pub fn is_module_active(module_id: &str) -> bool {
         let Some(game) = Self::instance().ok() else {
             return false;
@@ -337,23 +785,21 @@ impl Game {
         let placeables = get_property(&tokens_layer, "placeables").ok()?;
 
         if let Ok(Some(iter)) = js_sys::try_iter(&placeables) {
-            for token_result in iter {
-                if let Ok(token_js) = token_result {
-                    if let Ok(token_actor) = get_property(&token_js, "actor") {
-                        let token_actor_id = get_string_property(&token_actor, "id");
-                        let token_actor_uuid = get_string_property(&token_actor, "uuid");
-
-                        // Try matching by ID first, then by full UUID
-                        if let Some(id) = &token_actor_id {
-                            if id == actor_id {
-                                return Some(Token::from(token_js));
-                            }
+            for token_js in iter.flatten() {
+                if let Ok(token_actor) = get_property(&token_js, "actor") {
+                    let token_actor_id = get_string_property(&token_actor, "id");
+                    let token_actor_uuid = get_string_property(&token_actor, "uuid");
+
+                    // Try matching by ID first, then by full UUID
+                    if let Some(id) = &token_actor_id {
+                        if id == actor_id {
+                            return Some(Token::from(token_js));
                         }
+                    }
 
-                        if let Some(token_uuid) = &token_actor_uuid {
-                            if token_uuid == uuid {
-                                return Some(Token::from(token_js));
-                            }
+                    if let Some(token_uuid) = &token_actor_uuid {
+                        if token_uuid == uuid {
+                            return Some(Token::from(token_js));
                         }
                     }
                 }
@@ -448,6 +894,101 @@ impl Module {
     }
 }
 
+/// A PIXI graphics object for drawing shapes on the canvas
+pub struct Graphics {
+    inner: JsValue,
+}
+
+impl Graphics {
+    pub fn new() -> Result<Self, Error> {
+        let global: JsValue = js_sys::global().into();
+        let class = get_path!(&global, "PIXI.Graphics")?;
+        let inner = js_sys::Reflect::construct(class.unchecked_ref(), &js_sys::Array::new())?;
+        Ok(Graphics { inner })
+    }
+
+    /// Draw a lightly filled circle with a solid outline, centered on (x, y)
+    pub fn draw_ring(
+        &self,
+        x: f64,
+        y: f64,
+        radius: f64,
+        color: u32,
+        alpha: f64,
+    ) -> Result<(), Error> {
+        let color = JsValue::from(color);
+        call_method(
+            &self.inner,
+            "lineStyle",
+            &[&JsValue::from(3), &color, &JsValue::from(alpha)],
+        )?;
+        call_method(
+            &self.inner,
+            "beginFill",
+            &[&color, &JsValue::from(alpha * 0.15)],
+        )?;
+        call_method(
+            &self.inner,
+            "drawCircle",
+            &[&JsValue::from(x), &JsValue::from(y), &JsValue::from(radius)],
+        )?;
+        call_method(&self.inner, "endFill", &[])?;
+        Ok(())
+    }
+
+    /// Draw a small dark square with white text, like a counter in the corner of an icon
+    pub fn draw_badge(&self, x: f64, y: f64, size: f64, text: &str) -> Result<(), Error> {
+        call_method(
+            &self.inner,
+            "beginFill",
+            &[&JsValue::from(0x000000), &JsValue::from(0.75)],
+        )?;
+        call_method(
+            &self.inner,
+            "drawRoundedRect",
+            &[
+                &JsValue::from(x),
+                &JsValue::from(y),
+                &JsValue::from(size),
+                &JsValue::from(size),
+                &JsValue::from(2),
+            ],
+        )?;
+        call_method(&self.inner, "endFill", &[])?;
+
+        let global: JsValue = js_sys::global().into();
+        let class = get_path!(&global, "PIXI.Text")?;
+        let style = js_sys::Object::new();
+        js_sys::Reflect::set(&style, jstr!("fontSize"), &JsValue::from(size * 0.8))?;
+        js_sys::Reflect::set(&style, jstr!("fill"), &JsValue::from(0xffffff))?;
+        js_sys::Reflect::set(&style, jstr!("fontWeight"), jstr!("bold"))?;
+        let label = js_sys::Reflect::construct(
+            class.unchecked_ref(),
+            &js_sys::Array::of2(jstr!(text), &style),
+        )?;
+        call_method(
+            &get_property(&label, "anchor")?,
+            "set",
+            &[&JsValue::from(0.5)],
+        )?;
+        js_sys::Reflect::set(&label, jstr!("x"), &JsValue::from(x + size / 2.0))?;
+        js_sys::Reflect::set(&label, jstr!("y"), &JsValue::from(y + size / 2.0))?;
+        call_method(&self.inner, "addChild", &[&label])?;
+        Ok(())
+    }
+
+    /// Remove the graphics and its children from the canvas, unless it went with whatever it was
+    /// drawn on
+    pub fn destroy(&self) {
+        if get_property(&self.inner, "destroyed").is_ok_and(|destroyed| !destroyed.is_truthy()) {
+            // the badge text and other children would otherwise leak
+            let options = js_sys::Object::new();
+            js_sys::Reflect::set(&options, jstr!("children"), &JsValue::TRUE).ok();
+            call_method(&self.inner, "destroy", &[&options]).ok();
+        }
+    }
+}
+
 /// Represents a token on the canvas
 pub struct Token {
     inner: JsValue,
@@ -477,6 +1018,131 @@ impl Token {
             .map(|inner| inner.into())
     }
 
+    /// Get the token's document
+    pub fn document(&self) -> Result<JsValue, Error> {
+        Ok(get_property(&self.inner, "document")?)
+    }
+
+    /// Get the token's bounds in screen coordinates as (left, top, right, bottom)
+    pub fn client_bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        let global: JsValue = js_sys::global().into();
+        let canvas = get_path!(&global, "canvas").ok()?;
+        let bounds = get_property(&self.inner, "bounds").ok()?;
+        let corner = |x: &str, y: &str| -> Option<(f64, f64)> {
+            let point = js_sys::Object::new();
+            js_sys::Reflect::set(&point, jstr!("x"), &get_property(&bounds, x).ok()?).ok()?;
+            js_sys::Reflect::set(&point, jstr!("y"), &get_property(&bounds, y).ok()?).ok()?;
+            let client = call_method(&canvas, "clientCoordinatesFromCanvas", &[&point]).ok()?;
+            Some((
+                get_f64_property(&client, "x")?,
+                get_f64_property(&client, "y")?,
+            ))
+        };
+        let (left, top) = corner("left", "top")?;
+        let (right, bottom) = corner("right", "bottom")?;
+        Some((left, top, right, bottom))
+    }
+
+    /// Get the token's center on the canvas
+    pub fn center(&self) -> Option<(f64, f64)> {
+        let center = get_property(&self.inner, "center").ok()?;
+        Some((
+            get_f64_property(&center, "x")?,
+            get_f64_property(&center, "y")?,
+        ))
+    }
+
+    /// Get the token's bounds on the canvas as (left, top, right, bottom)
+    pub fn bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        let bounds = get_property(&self.inner, "bounds").ok()?;
+        Some((
+            get_f64_property(&bounds, "left")?,
+            get_f64_property(&bounds, "top")?,
+            get_f64_property(&bounds, "right")?,
+            get_f64_property(&bounds, "bottom")?,
+        ))
+    }
+
+    /// Get the token's width and height in pixels
+    pub fn size(&self) -> Option<(f64, f64)> {
+        Some((
+            get_f64_property(&self.inner, "w")?,
+            get_f64_property(&self.inner, "h")?,
+        ))
+    }
+
+    /// Get the status and effect icons drawn on the token, as the image URL each shows and its
+    /// position and size relative to the token
+    pub fn effect_icons(&self) -> Vec<(String, f64, f64, f64)> {
+        let Ok(children) = get_path!(&self.inner, "effects.children") else {
+            return Vec::new();
+        };
+        js_iter!(children)
+            .filter_map(|icon| {
+                Some((
+                    get_path!(&icon, "texture.baseTexture.resource.src")
+                        .ok()?
+                        .as_string()?,
+                    get_f64_property(&icon, "x")?,
+                    get_f64_property(&icon, "y")?,
+                    get_f64_property(&icon, "width")?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Draw graphics on the token, positioned relative to its top left corner, so they move with it
+    pub fn add_child(&self, graphics: &Graphics) -> Result<(), Error> {
+        call_method(&self.inner, "addChild", &[&graphics.inner])?;
+        Ok(())
+    }
+
+    /// Target or untarget the token for the current user, optionally clearing their other targets
+    pub fn set_target(&self, targeted: bool, release_others: bool) -> Result<(), Error> {
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &options,
+            jstr!("releaseOthers"),
+            &JsValue::from(release_others),
+        )?;
+        call_method(
+            &self.inner,
+            "setTarget",
+            &[&JsValue::from(targeted), &options],
+        )?;
+        Ok(())
+    }
+
+    /// Get the token's disposition: -1 hostile, 0 neutral, 1 friendly, -2 secret
+    pub fn disposition(&self) -> Option<f64> {
+        get_path!(&self.inner, "document.disposition")
+            .ok()?
+            .as_f64()
+    }
+
+    /// Check if the token can be seen on this client, accounting for vision and hidden tokens
+    pub fn is_visible(&self) -> bool {
+        get_property(&self.inner, "visible")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Check if the GM has hidden the token from players
+    pub fn is_hidden(&self) -> bool {
+        get_path!(&self.inner, "document.hidden")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Get the distance to another token in feet, measured the way PF2e measures reach
+    pub fn distance_to(&self, other: &Token) -> Option<f64> {
+        call_method(&self.inner, "distanceTo", &[&other.inner])
+            .ok()?
+            .as_f64()
+    }
+
     /// Get items directly from token.actor.items (works even with limited permissions)
     pub fn actor_items(&self) -> Vec<Item> {
         let mut items = Vec::new();
@@ -484,10 +1150,8 @@ impl Token {
         if let Ok(actor) = get_property(&self.inner, "actor") {
             if let Ok(items_collection) = get_property(&actor, "items") {
                 if let Ok(Some(iter)) = js_sys::try_iter(&items_collection) {
-                    for item_result in iter {
-                        if let Ok(inner) = item_result {
-                            items.push(inner.into());
-                        }
+                    for inner in iter.flatten() {
+                        items.push(inner.into());
                     }
                 }
             }
@@ -524,21 +1188,64 @@ impl User {
         get_string_property(&self.inner, "name")
     }
 
-    /// Check if this user is a GM
-    pub fn is_gm(&self) -> bool {
-        get_property(&self.inner, "isGM")
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false)
+    /// Target exactly the tokens with these IDs, releasing every other target
+    pub fn update_token_targets(&self, token_ids: &[String]) -> Result<(), Error> {
+        let ids: js_sys::Array = token_ids
+            .iter()
+            .map(|id| JsValue::from(id.as_str()))
+            .collect();
+        call_method(&self.inner, "updateTokenTargets", &[&ids])?;
+        Ok(())
     }
 
-    /// Get a flag value
-    pub fn get_flag(&self, scope: &str, key: &str) -> JsValue {
-        // Call getFlag method on the document
-        let get_flag_fn = get_property(&self.inner, "getFlag").expect("getFlag method");
-        let args = js_sys::Array::new();
-        args.push(jstr!(scope));
-        args.push(jstr!(key));
+    /// Get the tokens this user is currently targeting
+    pub fn targets(&self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+
+        if let Ok(targets) = get_property(&self.inner, "targets") {
+            if let Ok(Some(iter)) = js_sys::try_iter(&targets) {
+                for inner in iter.flatten() {
+                    tokens.push(inner.into());
+                }
+            }
+        }
+
+        tokens
+    }
+
+    /// Get the user's assigned character
+    pub fn character(&self) -> Option<Actor> {
+        let character = get_property(&self.inner, "character").ok()?;
+        if character.is_null() || character.is_undefined() {
+            None
+        } else {
+            Some(character.into())
+        }
+    }
+
+    /// Check if this user is currently connected
+    pub fn is_active(&self) -> bool {
+        get_property(&self.inner, "active")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Check if this user is a GM
+    pub fn is_gm(&self) -> bool {
+        get_property(&self.inner, "isGM")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Get a flag value
+    pub fn get_flag(&self, scope: &str, key: &str) -> JsValue {
+        // Call getFlag method on the document
+        let get_flag_fn = get_property(&self.inner, "getFlag").expect("getFlag method");
+        let args = js_sys::Array::new();
+        args.push(jstr!(scope));
+        args.push(jstr!(key));
 
         js_sys::Reflect::apply(get_flag_fn.unchecked_ref(), &self.inner, &args)
             .unwrap_or(JsValue::UNDEFINED)
@@ -570,6 +1277,16 @@ pub struct UserCollection {
 }
 
 impl UserCollection {
+    /// Get the GM responsible for GM-only work, the active GM with the lowest ID
+    pub fn active_gm(&self) -> Option<User> {
+        let gm = get_property(&self.inner, "activeGM").ok()?;
+        if gm.is_null() || gm.is_undefined() {
+            None
+        } else {
+            Some(gm.into())
+        }
+    }
+
     /// Get a user by ID
     pub fn get(&self, id: &str) -> Option<User> {
         let get_fn = get_property(&self.inner, "get").ok()?;
@@ -585,10 +1302,8 @@ impl UserCollection {
     pub fn iter(&self) -> impl Iterator<Item = User> {
         let mut users = Vec::new();
         if let Ok(Some(iter)) = js_sys::try_iter(&self.inner) {
-            for item in iter {
-                if let Ok(inner) = item {
-                    users.push(inner.into());
-                }
+            for inner in iter.flatten() {
+                users.push(inner.into());
             }
         }
         users.into_iter()
@@ -615,7 +1330,7 @@ impl GMStrategy {
         }
     }
 
-    pub fn to_setting_value(&self) -> &'static str {
+    pub fn to_setting_value(self) -> &'static str {
         match self {
             GMStrategy::Normal => "normal",
             GMStrategy::Never => "never",
@@ -658,6 +1373,93 @@ impl GMStrategy {
     }
 }
 
+/// Minimum document ownership level for a user to count as owning an actor
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OwnershipLevel {
+    Observer,
+    #[default]
+    Owner,
+}
+
+impl OwnershipLevel {
+    pub fn from_setting_value(value: &str) -> Self {
+        match value {
+            "observer" => OwnershipLevel::Observer,
+            "owner" => OwnershipLevel::Owner,
+            _ => OwnershipLevel::default(),
+        }
+    }
+
+    pub fn to_setting_value(self) -> &'static str {
+        match self {
+            OwnershipLevel::Observer => "observer",
+            OwnershipLevel::Owner => "owner",
+        }
+    }
+
+    /// The numeric level in Foundry's `CONST.DOCUMENT_OWNERSHIP_LEVELS`
+    pub fn level(self) -> f64 {
+        match self {
+            OwnershipLevel::Observer => 2.0,
+            OwnershipLevel::Owner => 3.0,
+        }
+    }
+
+    pub fn from_settings(module_id: &str) -> Self {
+        let value = get_setting(module_id, "ownershipLevel");
+        if let Some(s) = value.as_string() {
+            Self::from_setting_value(&s)
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Register the OwnershipLevel setting
+    pub fn register_setting(module_id: &str) {
+        SettingConfig::new()
+            .name("Popup Ownership Level")
+            .hint("The ownership a user needs on an actor for its damage to pop out for them. Observer includes co-GMs and players watching companions.")
+            .scope("world")
+            .config(true)
+            .type_string()
+            .default_string(OwnershipLevel::default().to_setting_value())
+            .choices(&[("owner", "Owner"), ("observer", "Observer")])
+            .register(module_id, "ownershipLevel");
+    }
+}
+
+/// Runes on a PF2e weapon or armor
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemRunes {
+    /// Item bonus from the potency rune, 0 if none
+    pub potency: u8,
+    /// Striking rune grade: 1 striking, 2 greater, 3 major
+    pub striking: u8,
+    /// Resilient rune grade: 1 resilient, 2 greater, 3 major
+    pub resilient: u8,
+    /// Property rune slugs, e.g. "flaming"
+    pub property: Vec<String>,
+}
+
+/// Current condition of a PF2e shield
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShieldStatus {
+    pub hp: f64,
+    pub max_hp: f64,
+    pub hardness: f64,
+    pub broken: bool,
+    pub destroyed: bool,
+}
+
+/// The damage a persistent damage condition deals each turn
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistentDamage {
+    pub formula: String,
+    pub damage_type: String,
+    /// The flat check DC to end it
+    pub dc: f64,
+}
+
 /// Represents an item in Foundry
 pub struct Item {
     inner: JsValue,
@@ -690,6 +1492,39 @@ impl Item {
         get_string_property(&self.inner, "img")
     }
 
+    /// Get the item's UUID
+    pub fn uuid(&self) -> Option<String> {
+        get_string_property(&self.inner, "uuid")
+    }
+
+    /// Get when the item was last modified, in milliseconds since the epoch
+    pub fn modified_time(&self) -> Option<f64> {
+        get_path!(&self.inner, "_stats.modifiedTime").ok()?.as_f64()
+    }
+
+    /// Get the item's description as unenriched HTML
+    pub fn description(&self) -> String {
+        get_path!(&self.inner, "system.description.value")
+            .ok()
+            .and_then(|v| v.as_string())
+            .unwrap_or_default()
+    }
+
+    /// Check if the current user has at least observer permission on this item
+    pub fn is_observable(&self) -> bool {
+        let Ok(user) = Game::instance().and_then(|game| game.user()) else {
+            return false;
+        };
+        call_method(
+            &self.inner,
+            "testUserPermission",
+            &[user.as_js_value(), jstr!("OBSERVER")],
+        )
+        .ok()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    }
+
     /// Get the item's carry type (worn, held, stowed, etc.)
     pub fn carry_type(&self) -> Option<String> {
         get_path!(&self.inner, "system.equipped.carryType")
@@ -697,6 +1532,125 @@ impl Item {
             .as_string()
     }
 
+    /// Get the number of hands the item is held in, zero if it isn't held
+    pub fn hands_held(&self) -> f64 {
+        get_path!(&self.inner, "system.equipped.handsHeld")
+            .ok()
+            .and_then(|v| v.as_f64())
+            .unwrap_or_default()
+    }
+
+    /// Get the item's rarity: common, uncommon, rare, or unique
+    pub fn rarity(&self) -> Option<String> {
+        get_path!(&self.inner, "system.traits.rarity")
+            .ok()?
+            .as_string()
+    }
+
+    /// Get how many of this item are in the stack
+    pub fn quantity(&self) -> f64 {
+        get_path!(&self.inner, "system.quantity")
+            .ok()
+            .and_then(|v| v.as_f64())
+            .unwrap_or(1.0)
+    }
+
+    /// Set how many of the item there are
+    pub async fn set_quantity(&self, value: f64) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(&changes, jstr!("system.quantity"), &JsValue::from(value))?;
+        self.update(&changes).await
+    }
+
+    /// Get the value of the whole stack in copper pieces, from its price per batch
+    pub fn total_price(&self) -> f64 {
+        let Ok(price) = get_path!(&self.inner, "system.price") else {
+            return 0.0;
+        };
+        let value = get_property(&price, "value").unwrap_or(JsValue::UNDEFINED);
+        let coins = |denomination: &str| get_f64_property(&value, denomination).unwrap_or_default();
+        let copper = coins("pp") * 1000.0 + coins("gp") * 100.0 + coins("sp") * 10.0 + coins("cp");
+        let per = get_f64_property(&price, "per").unwrap_or(1.0).max(1.0);
+        copper * self.quantity() / per
+    }
+
+    /// Check if the item is a stack of coins
+    pub fn is_coins(&self) -> bool {
+        get_property(&self.inner, "isCoinage")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Get the charges left on a consumable and its maximum
+    pub fn uses(&self) -> Option<(f64, f64)> {
+        let uses = get_path!(&self.inner, "system.uses").ok()?;
+        Some((
+            get_property(&uses, "value").ok()?.as_f64()?,
+            get_property(&uses, "max").ok()?.as_f64()?,
+        ))
+    }
+
+    /// Set the charges left on a consumable
+    pub async fn set_uses(&self, value: f64) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(&changes, jstr!("system.uses.value"), &JsValue::from(value))?;
+        self.update(&changes).await
+    }
+
+    /// Check if the consumable should be deleted once it's used up
+    pub fn auto_destroy(&self) -> bool {
+        get_path!(&self.inner, "system.uses.autoDestroy")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    }
+
+    /// Get a weapon's group, e.g. "sword" or "bow"
+    pub fn weapon_group(&self) -> Option<String> {
+        get_path!(&self.inner, "system.group").ok()?.as_string()
+    }
+
+    /// Get a ranged weapon's range increment and maximum range in feet, or None for melee weapons
+    pub fn range(&self) -> Option<(f64, f64)> {
+        let range = get_property(&self.inner, "range").ok()?;
+        let increment = get_f64_property(&range, "increment")?;
+        let max = get_f64_property(&range, "max").unwrap_or(increment * 6.0);
+        Some((increment, max))
+    }
+
+    /// Get the ammunition selected for this weapon, if any
+    pub fn ammo(&self) -> Option<Item> {
+        let ammo = get_property(&self.inner, "ammo").ok()?;
+        if ammo.is_null() || ammo.is_undefined() {
+            None
+        } else {
+            Some(ammo.into())
+        }
+    }
+
+    /// Get how many hands the item needs to be used, from its usage
+    pub fn hands_required(&self) -> u8 {
+        match get_path!(&self.inner, "system.usage.value")
+            .ok()
+            .and_then(|v| v.as_string())
+            .as_deref()
+        {
+            Some("held-in-two-hands") => 2,
+            _ => 1,
+        }
+    }
+
+    /// Get the actor that owns this item, if any
+    pub fn actor(&self) -> Option<Actor> {
+        let actor = get_property(&self.inner, "actor").ok()?;
+        if actor.is_null() || actor.is_undefined() {
+            None
+        } else {
+            Some(actor.into())
+        }
+    }
+
     /// Check if the item is currently wielded with two hands
     pub fn is_two_handed(&self) -> bool {
         get_path!(&self.inner, "system.equipped.handsHeld")
@@ -712,6 +1666,226 @@ impl Item {
             .unwrap_or_default()
     }
 
+    /// Get the fundamental and property runes etched into this weapon or armor
+    pub fn runes(&self) -> ItemRunes {
+        let Ok(runes) = get_path!(&self.inner, "system.runes") else {
+            return ItemRunes::default();
+        };
+        let number = |key: &str| get_f64_property(&runes, key).unwrap_or_default() as u8;
+        ItemRunes {
+            potency: number("potency"),
+            striking: number("striking"),
+            resilient: number("resilient"),
+            property: get_property(&runes, "property")
+                .map(|property| js_iter!(property).filter_map(|r| r.as_string()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Get the hit points and hardness of a shield, or None if this isn't a shield
+    pub fn shield_status(&self) -> Option<ShieldStatus> {
+        if self.item_type().as_deref() != Some("shield") {
+            return None;
+        }
+        let system = get_property(&self.inner, "system").ok()?;
+        let flag = |key: &str| {
+            get_property(&self.inner, key)
+                .ok()
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        };
+        Some(ShieldStatus {
+            hp: get_path!(&system, "hp.value").ok()?.as_f64()?,
+            max_hp: get_path!(&system, "hp.max").ok()?.as_f64()?,
+            hardness: get_f64_property(&system, "hardness").unwrap_or_default(),
+            broken: flag("isBroken"),
+            destroyed: flag("isDestroyed"),
+        })
+    }
+
+    /// Check if the item has been identified; items without identification data always are
+    pub fn is_identified(&self) -> bool {
+        get_property(&self.inner, "isIdentified")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    }
+
+    /// Get the name shown to players while the item is unidentified
+    pub fn unidentified_name(&self) -> Option<String> {
+        get_path!(&self.inner, "system.identification.unidentified.name")
+            .ok()?
+            .as_string()
+    }
+
+    /// Get the image shown to players while the item is unidentified
+    pub fn unidentified_img(&self) -> Option<String> {
+        get_path!(&self.inner, "system.identification.unidentified.img")
+            .ok()?
+            .as_string()
+    }
+
+    /// Check if the item is magical
+    pub fn is_magical(&self) -> bool {
+        get_property(&self.inner, "isMagical")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Check if the item is currently invested
+    pub fn is_invested(&self) -> bool {
+        get_path!(&self.inner, "system.equipped.invested")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Invest or uninvest a worn item with the invested trait
+    pub async fn set_invested(&self, invested: bool) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &changes,
+            jstr!("system.equipped.invested"),
+            &JsValue::from(invested),
+        )?;
+        self.update(&changes).await
+    }
+
+    /// Get the item's slug, e.g. "frightened" for that condition
+    pub fn slug(&self) -> Option<String> {
+        get_path!(&self.inner, "system.slug").ok()?.as_string()
+    }
+
+    /// Check if an effect's duration has run out
+    pub fn is_expired(&self) -> bool {
+        get_property(&self.inner, "isExpired")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Get a valued condition's value, e.g. 2 for frightened 2, or None for conditions without one
+    pub fn condition_value(&self) -> Option<f64> {
+        get_path!(&self.inner, "system.value.value").ok()?.as_f64()
+    }
+
+    /// Get the item that granted this one, like the spell effect that made a creature slowed
+    pub fn granted_by(&self) -> Option<Item> {
+        let item = get_property(&self.inner, "grantedBy").ok()?;
+        if item.is_null() || item.is_undefined() {
+            None
+        } else {
+            Some(item.into())
+        }
+    }
+
+    /// Get how many seconds are left on an effect, or None if it lasts until removed
+    pub fn remaining_duration(&self) -> Option<f64> {
+        get_path!(&self.inner, "remainingDuration.remaining")
+            .ok()?
+            .as_f64()
+            .filter(|remaining| remaining.is_finite())
+    }
+
+    /// Get the damage and recovery DC of a persistent damage condition
+    pub fn persistent_damage(&self) -> Option<PersistentDamage> {
+        let persistent = get_path!(&self.inner, "system.persistent").ok()?;
+        Some(PersistentDamage {
+            formula: get_string_property(&persistent, "formula")?,
+            damage_type: get_string_property(&persistent, "damageType").unwrap_or_default(),
+            dc: get_f64_property(&persistent, "dc").unwrap_or(15.0),
+        })
+    }
+
+    /// Get how many uses are left of an ability with a limited frequency, its maximum, and the period,
+    /// e.g. "day"
+    pub fn frequency(&self) -> Option<(f64, f64, String)> {
+        let frequency = get_path!(&self.inner, "system.frequency").ok()?;
+        Some((
+            get_property(&frequency, "value").ok()?.as_f64()?,
+            get_property(&frequency, "max").ok()?.as_f64()?,
+            get_string_property(&frequency, "per")?,
+        ))
+    }
+
+    /// Set how many uses are left of an ability with a limited frequency
+    pub async fn set_frequency_uses(&self, value: f64) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &changes,
+            jstr!("system.frequency.value"),
+            &JsValue::from(value),
+        )?;
+        self.update(&changes).await
+    }
+
+    /// Update the item's data, with keys like "system.frequency.value"
+    pub async fn update(&self, changes: &js_sys::Object) -> Result<(), Error> {
+        call_method_async(&self.inner, "update", &[changes]).await?;
+        Ok(())
+    }
+
+    /// Get the save a spell calls for and whether it's a basic save, e.g. ("reflex", true)
+    pub fn spell_save(&self) -> Option<(String, bool)> {
+        let save = get_path!(&self.inner, "system.defense.save").ok()?;
+        Some((
+            get_string_property(&save, "statistic")?,
+            get_property(&save, "basic")
+                .ok()
+                .and_then(|v| v.as_bool())
+                .unwrap_or_default(),
+        ))
+    }
+
+    /// Get the spell DC of the spellcasting entry a spell is cast from
+    pub fn spell_dc(&self) -> Option<f64> {
+        get_path!(&self.inner, "spellcasting.statistic.dc.value")
+            .ok()?
+            .as_f64()
+    }
+
+    /// Get the ID of the spellcasting entry a spell belongs to
+    pub fn spellcasting_entry_id(&self) -> Option<String> {
+        get_path!(&self.inner, "system.location.value")
+            .ok()?
+            .as_string()
+    }
+
+    /// Check if the spell is a cantrip, which never uses a slot
+    pub fn is_cantrip(&self) -> bool {
+        get_property(&self.inner, "isCantrip")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Delete the item from its actor
+    pub async fn delete(&self) -> Result<(), Error> {
+        call_method_async(&self.inner, "delete", &[]).await?;
+        Ok(())
+    }
+
+    /// Get the ID of the container this item is stored in, if any
+    pub fn container_id(&self) -> Option<String> {
+        get_path!(&self.inner, "system.containerId")
+            .ok()?
+            .as_string()
+    }
+
+    /// Get the UUID of the actor owning this item, if it's embedded in one
+    pub fn actor_uuid(&self) -> Option<String> {
+        get_path!(&self.inner, "actor.uuid").ok()?.as_string()
+    }
+
+    /// Get a copy of the item's source data for creating it on another actor, taken out of any container
+    pub fn transfer_data(&self) -> Result<JsValue, Error> {
+        let data = call_method(&self.inner, "toObject", &[])?;
+        let system = get_property(&data, "system")?;
+        js_sys::Reflect::set(&system, jstr!("containerId"), &JsValue::NULL)?;
+        Ok(data)
+    }
+
     /// Check if this is a physical inventory item (not a spell, action, effect, etc.)
     pub fn is_physical_item(&self) -> bool {
         if let Some(item_type) = self.item_type() {
@@ -739,45 +1913,735 @@ impl Item {
     }
 }
 
-/// Represents an actor in Foundry
-pub struct Actor {
+/// A PF2e spellcasting entry, like a wizard's prepared arcane spells
+pub struct SpellcastingEntry {
     inner: JsValue,
 }
 
-impl From<JsValue> for Actor {
+impl From<JsValue> for SpellcastingEntry {
     fn from(inner: JsValue) -> Self {
-        Actor { inner }
+        SpellcastingEntry { inner }
     }
 }
 
-impl Actor {
+impl SpellcastingEntry {
+    pub fn id(&self) -> Option<String> {
+        get_string_property(&self.inner, "id")
+    }
+
     pub fn name(&self) -> String {
-        get_string_property(&self.inner, "name").unwrap_or_else(|| "Unknown".to_string())
+        get_string_property(&self.inner, "name").unwrap_or_default()
     }
 
-    pub fn id(&self) -> Option<String> {
-        get_string_property(&self.inner, "id")
+    /// How the entry's spells are cast: "prepared", "spontaneous", "innate", "focus", or "items"
+    fn preparation(&self) -> Option<String> {
+        get_path!(&self.inner, "system.prepared.value")
+            .ok()?
+            .as_string()
     }
 
-    /// Check if a specific user owns this actor (ownership level >= 3)
-    pub fn is_owned_by(&self, user: &User, count_gm: GMStrategy) -> bool {
-        let Some(user_id) = user.id() else {
-            return false;
-        };
-        let is_gm = user.is_gm();
+    /// Check if the entry's spells are prepared each day
+    pub fn is_prepared(&self) -> bool {
+        self.preparation().as_deref() == Some("prepared")
+    }
 
-        let Ok(ownership) = get_property(&self.inner, "ownership") else {
-            return false;
-        };
-        let level_num = get_property(&ownership, &user_id)
-            .map(|l| l.as_f64())
-            .ok()
-            .flatten()
-            .unwrap_or_default();
-        let owns = level_num >= 3.0;
+    /// Check if the entry casts from spell slots, i.e. it's prepared or spontaneous
+    pub fn uses_slots(&self) -> bool {
+        matches!(
+            self.preparation().as_deref(),
+            Some("prepared") | Some("spontaneous")
+        )
+    }
 
-        match count_gm {
-            GMStrategy::Normal => owns,
+    /// Get the slots left and the total at a rank, counting unexpended prepared spells for
+    /// prepared casters
+    pub fn slots(&self, rank: u8) -> Option<(f64, f64)> {
+        let slot = get_path!(&self.inner, &format!("system.slots.slot{rank}")).ok()?;
+        let max = get_property(&slot, "max").ok()?.as_f64()?;
+        if max <= 0.0 {
+            return None;
+        }
+        let prepared = self.preparation().as_deref() == Some("prepared");
+        let remaining = if prepared {
+            get_property(&slot, "prepared")
+                .map(|prepared| {
+                    js_iter!(prepared)
+                        .filter(|spell| {
+                            get_string_property(spell, "id").is_some()
+                                && !get_property(spell, "expended")
+                                    .ok()
+                                    .and_then(|v| v.as_bool())
+                                    .unwrap_or_default()
+                        })
+                        .count() as f64
+                })
+                .unwrap_or_default()
+        } else {
+            get_property(&slot, "value").ok()?.as_f64()?
+        };
+        Some((remaining, max))
+    }
+
+    /// Expend a slot for casting the spell at the given rank
+    pub async fn consume(&self, spell: &Item, rank: u8) -> Result<(), Error> {
+        let spellcasting = get_property(&self.inner, "spellcasting")?;
+        call_method_async(
+            &spellcasting,
+            "consume",
+            &[spell.as_js_value(), &JsValue::from(rank)],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Represents an actor in Foundry
+pub struct Actor {
+    inner: JsValue,
+}
+
+impl From<JsValue> for Actor {
+    fn from(inner: JsValue) -> Self {
+        Actor { inner }
+    }
+}
+
+impl Actor {
+    pub fn name(&self) -> String {
+        get_string_property(&self.inner, "name").unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    pub fn id(&self) -> Option<String> {
+        get_string_property(&self.inner, "id")
+    }
+
+    pub fn uuid(&self) -> Option<String> {
+        get_string_property(&self.inner, "uuid")
+    }
+
+    /// Get the actor's portrait image path
+    pub fn img(&self) -> Option<String> {
+        get_string_property(&self.inner, "img")
+    }
+
+    /// Get the creature's level
+    pub fn level(&self) -> Option<f64> {
+        get_property(&self.inner, "level")
+            .ok()
+            .and_then(|v| v.as_f64())
+            .or_else(|| {
+                get_path!(&self.inner, "system.details.level.value")
+                    .ok()?
+                    .as_f64()
+            })
+    }
+
+    /// Get the creature's traits, like "undead" or "humanoid"
+    pub fn traits(&self) -> Vec<String> {
+        get_path!(&self.inner, "system.traits.value")
+            .ok()
+            .map(|v| js_iter!(v).filter_map(|t| t.as_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the creature's rarity, e.g. "common" or "unique"
+    pub fn rarity(&self) -> Option<String> {
+        get_path!(&self.inner, "system.traits.rarity")
+            .ok()?
+            .as_string()
+    }
+
+    /// Get a saving throw modifier: "fortitude", "reflex", or "will"
+    pub fn save_modifier(&self, save: &str) -> Option<f64> {
+        get_path!(&self.inner, &format!("system.saves.{save}.value"))
+            .ok()?
+            .as_f64()
+    }
+
+    /// Get the creature's weaknesses or resistances ("weaknesses" or "resistances"), e.g. "fire 5"
+    pub fn weaknesses_or_resistances(&self, kind: &str) -> Vec<String> {
+        get_path!(&self.inner, &format!("system.attributes.{kind}"))
+            .map(|entries| {
+                js_iter!(entries)
+                    .filter_map(|entry| {
+                        let kind = get_string_property(&entry, "type")?;
+                        let value = get_property(&entry, "value").ok()?.as_f64()?;
+                        Some(format!("{kind} {value}"))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get what the creature is immune to, e.g. "poison"
+    pub fn immunities(&self) -> Vec<String> {
+        get_path!(&self.inner, "system.attributes.immunities")
+            .map(|entries| {
+                js_iter!(entries)
+                    .filter_map(|entry| get_string_property(&entry, "type"))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the creature's Armor Class
+    pub fn armor_class(&self) -> Option<f64> {
+        get_path!(&self.inner, "system.attributes.ac.value")
+            .ok()?
+            .as_f64()
+    }
+
+    /// Get how far the creature can reach with its attacks, in feet
+    pub fn attack_reach(&self) -> f64 {
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, jstr!("action"), jstr!("attack")).ok();
+        call_method(&self.inner, "getReach", &[&options])
+            .ok()
+            .and_then(|reach| reach.as_f64())
+            .unwrap_or(5.0)
+    }
+
+    /// Get the actor's type, e.g. "character" or "npc"
+    pub fn actor_type(&self) -> Option<String> {
+        get_string_property(&self.inner, "type")
+    }
+
+    /// Set the creature's current hit points
+    pub async fn set_hit_points(&self, value: f64) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &changes,
+            jstr!("system.attributes.hp.value"),
+            &JsValue::from(value),
+        )?;
+        self.update(&changes).await
+    }
+
+    /// Get the creature's maximum hit points
+    pub fn max_hit_points(&self) -> Option<f64> {
+        get_path!(&self.inner, "system.attributes.hp.max")
+            .ok()?
+            .as_f64()
+    }
+
+    /// Get an attribute modifier: "str", "dex", "con", "int", "wis", or "cha"
+    pub fn attribute_modifier(&self, attribute: &str) -> Option<f64> {
+        get_path!(&self.inner, &format!("system.abilities.{attribute}.mod"))
+            .ok()?
+            .as_f64()
+    }
+
+    /// Get the actor's focus points and the size of their focus pool
+    pub fn focus_points(&self) -> Option<(f64, f64)> {
+        let focus = get_path!(&self.inner, "system.resources.focus").ok()?;
+        Some((
+            get_property(&focus, "value").ok()?.as_f64()?,
+            get_property(&focus, "max").ok()?.as_f64()?,
+        ))
+    }
+
+    /// Set the actor's focus points
+    pub async fn set_focus_points(&self, value: f64) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &changes,
+            jstr!("system.resources.focus.value"),
+            &JsValue::from(value),
+        )?;
+        self.update(&changes).await
+    }
+
+    /// Get an alchemist's infused reagents and their daily maximum
+    pub fn infused_reagents(&self) -> Option<(f64, f64)> {
+        let reagents = get_path!(&self.inner, "system.resources.crafting.infusedReagents").ok()?;
+        Some((
+            get_property(&reagents, "value").ok()?.as_f64()?,
+            get_property(&reagents, "max").ok()?.as_f64()?,
+        ))
+    }
+
+    /// Set an alchemist's infused reagents
+    pub async fn set_infused_reagents(&self, value: f64) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &changes,
+            jstr!("system.resources.crafting.infusedReagents.value"),
+            &JsValue::from(value),
+        )?;
+        self.update(&changes).await
+    }
+
+    /// Update the actor's data, with keys like "system.attributes.hp.value"
+    pub async fn update(&self, changes: &js_sys::Object) -> Result<(), Error> {
+        call_method_async(&self.inner, "update", &[changes]).await?;
+        Ok(())
+    }
+
+    /// Get a character's hero points and their maximum
+    pub fn hero_points(&self) -> Option<(f64, f64)> {
+        let hero_points = get_path!(&self.inner, "system.resources.heroPoints").ok()?;
+        Some((
+            get_property(&hero_points, "value").ok()?.as_f64()?,
+            get_property(&hero_points, "max").ok()?.as_f64()?,
+        ))
+    }
+
+    /// Set a character's hero points
+    pub async fn set_hero_points(&self, value: f64) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &changes,
+            jstr!("system.resources.heroPoints.value"),
+            &JsValue::from(value),
+        )?;
+        self.update(&changes).await
+    }
+
+    /// Get the NPC's elite or weak adjustment, if it has one
+    pub fn adjustment(&self) -> Option<String> {
+        get_path!(&self.inner, "system.attributes.adjustment")
+            .ok()?
+            .as_string()
+    }
+
+    /// Apply PF2e's elite or weak adjustment to an NPC, or remove it with None
+    pub async fn apply_adjustment(&self, adjustment: Option<&str>) -> Result<(), Error> {
+        let adjustment = adjustment.map_or(JsValue::NULL, JsValue::from_str);
+        call_method_async(&self.inner, "applyAdjustment", &[&adjustment]).await?;
+        Ok(())
+    }
+
+    /// Get a character's experience points toward their next level
+    pub fn experience(&self) -> Option<f64> {
+        get_path!(&self.inner, "system.details.xp.value")
+            .ok()?
+            .as_f64()
+    }
+
+    /// Set a character's experience points
+    pub async fn set_experience(&self, value: f64) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &changes,
+            jstr!("system.details.xp.value"),
+            &JsValue::from(value),
+        )?;
+        self.update(&changes).await
+    }
+
+    /// Get the statistic a character rolls for initiative, e.g. "perception" or "stealth"
+    pub fn initiative_statistic(&self) -> Option<String> {
+        get_path!(&self.inner, "system.initiative.statistic")
+            .ok()?
+            .as_string()
+    }
+
+    /// Set the statistic a character rolls for initiative, e.g. "perception" or "stealth"
+    pub async fn set_initiative_statistic(&self, statistic: &str) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &changes,
+            jstr!("system.initiative.statistic"),
+            jstr!(statistic),
+        )?;
+        self.update(&changes).await
+    }
+
+    /// Check if any player owns this actor
+    pub fn has_player_owner(&self) -> bool {
+        get_property(&self.inner, "hasPlayerOwner")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Get the creature's speeds as movement type and feet, land speed first
+    pub fn speeds(&self) -> Vec<(String, f64)> {
+        let Ok(speed) = get_path!(&self.inner, "system.attributes.speed") else {
+            return Vec::new();
+        };
+        // prepared actors have a total with bonuses and penalties applied
+        let feet = |speed: &JsValue| {
+            get_f64_property(speed, "total").or_else(|| get_f64_property(speed, "value"))
+        };
+        let land = feet(&speed).map(|feet| ("land".to_string(), feet));
+        let others = get_property(&speed, "otherSpeeds")
+            .ok()
+            .and_then(|others| others.dyn_into::<js_sys::Array>().ok())
+            .map(|others| {
+                others
+                    .iter()
+                    .filter_map(|other| Some((get_string_property(&other, "type")?, feet(&other)?)))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        land.into_iter().chain(others).collect()
+    }
+
+    /// Get the creature's current hit points
+    pub fn hit_points(&self) -> Option<f64> {
+        get_path!(&self.inner, "system.attributes.hp.value")
+            .ok()?
+            .as_f64()
+    }
+
+    /// Get the creature's temporary hit points
+    pub fn temp_hit_points(&self) -> Option<f64> {
+        get_path!(&self.inner, "system.attributes.hp.temp")
+            .ok()?
+            .as_f64()
+    }
+
+    /// Check if the actor gets the critical specialization effect of a weapon, given the attack's
+    /// roll options
+    pub fn has_critical_specialization(&self, weapon: &Item, options: &[String]) -> bool {
+        let Ok(synthetics) = get_path!(&self.inner, "synthetics.criticalSpecializations.standard")
+        else {
+            return false;
+        };
+        let options: js_sys::Set = js_sys::Set::new(
+            &options
+                .iter()
+                .map(|option| JsValue::from_str(option))
+                .collect::<js_sys::Array>(),
+        );
+        // each synthetic returns a note for weapons it grants the effect to, or null
+        js_iter!(synthetics).any(|synthetic| {
+            synthetic
+                .dyn_ref::<js_sys::Function>()
+                .and_then(|synthetic| {
+                    synthetic
+                        .call2(&JsValue::NULL, weapon.as_js_value(), &options)
+                        .ok()
+                })
+                .is_some_and(|note| !note.is_null() && !note.is_undefined())
+        })
+    }
+
+    /// Check if the actor has a combatant in the active combat
+    pub fn in_combat(&self) -> bool {
+        get_property(&self.inner, "inCombat")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Check if the actor has a shield raised that can still block
+    pub fn has_raised_shield(&self) -> bool {
+        let Ok(shield) = get_path!(&self.inner, "attributes.shield") else {
+            return false;
+        };
+        let flag = |key: &str| {
+            get_property(&shield, key)
+                .ok()
+                .and_then(|v| v.as_bool())
+                .unwrap_or_default()
+        };
+        flag("raised") && !flag("broken") && !flag("destroyed")
+    }
+
+    /// Get the actor's Perception modifier
+    pub fn perception_modifier(&self) -> Option<f64> {
+        get_path!(&self.inner, "perception.mod")
+            .ok()
+            .and_then(|v| v.as_f64())
+            .or_else(|| {
+                get_path!(&self.inner, "system.perception.mod")
+                    .ok()?
+                    .as_f64()
+            })
+    }
+
+    /// Get the actor's items
+    pub fn items(&self) -> Vec<Item> {
+        get_property(&self.inner, "items")
+            .map(|items| js_iter!(items).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the conditions currently affecting the actor
+    pub fn conditions(&self) -> Vec<Item> {
+        get_path!(&self.inner, "itemTypes.condition")
+            .map(|conditions| js_iter!(conditions).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
+    /// Check if the actor has a feat, by slug
+    pub fn has_feat(&self, slug: &str) -> bool {
+        get_path!(&self.inner, "itemTypes.feat")
+            .map(|feats| {
+                js_iter!(feats).any(|feat| Item::from(feat).slug().as_deref() == Some(slug))
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the actor's spellcasting entries
+    pub fn spellcasting_entries(&self) -> Vec<SpellcastingEntry> {
+        get_path!(&self.inner, "itemTypes.spellcastingEntry")
+            .map(|entries| js_iter!(entries).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the effects on the actor
+    pub fn effects(&self) -> Vec<Item> {
+        get_path!(&self.inner, "itemTypes.effect")
+            .map(|effects| js_iter!(effects).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the auras the creature emits, like Bless or a commander's banner, as slug and radius in feet
+    pub fn auras(&self) -> Vec<(String, f64)> {
+        let Some(auras) = get_property(&self.inner, "auras")
+            .ok()
+            .and_then(|auras| call_method(&auras, "values", &[]).ok())
+        else {
+            return Vec::new();
+        };
+        js_iter!(auras)
+            .filter_map(|aura| {
+                Some((
+                    get_string_property(&aura, "slug")?,
+                    get_f64_property(&aura, "radius")?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Get the actor's skills, including lores, as slug, label, and proficiency rank from 0 to 4
+    pub fn skills(&self) -> Vec<(String, String, u8)> {
+        let Ok(skills) = get_property(&self.inner, "skills") else {
+            return Vec::new();
+        };
+        if !skills.is_object() {
+            return Vec::new();
+        }
+        js_sys::Object::values(skills.unchecked_ref())
+            .iter()
+            .filter_map(|skill| {
+                Some((
+                    get_string_property(&skill, "slug")?,
+                    get_string_property(&skill, "label")?,
+                    get_f64_property(&skill, "rank").unwrap_or_default() as u8,
+                ))
+            })
+            .collect()
+    }
+
+    /// Add coins to the actor's inventory, given in copper pieces
+    pub async fn add_coins(&self, copper: f64) -> Result<(), Error> {
+        let inventory = get_property(&self.inner, "inventory")?;
+        let coins = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &coins,
+            jstr!("gp"),
+            &JsValue::from((copper / 100.0).floor()),
+        )?;
+        js_sys::Reflect::set(
+            &coins,
+            jstr!("sp"),
+            &JsValue::from((copper % 100.0 / 10.0).floor()),
+        )?;
+        js_sys::Reflect::set(&coins, jstr!("cp"), &JsValue::from(copper % 10.0))?;
+        call_method_async(&inventory, "addCoins", &[&coins]).await?;
+        Ok(())
+    }
+
+    /// Add items, such as effects, to the actor from their source data
+    pub async fn create_items(&self, items: &[JsValue]) -> Result<(), Error> {
+        let items: js_sys::Array = items.iter().collect();
+        call_method_async(
+            &self.inner,
+            "createEmbeddedDocuments",
+            &[jstr!("Item"), &items],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Delete items from the actor by ID in a single update
+    pub async fn delete_items(&self, ids: &[String]) -> Result<(), Error> {
+        let ids: js_sys::Array = ids.iter().map(|id| JsValue::from_str(id)).collect();
+        call_method_async(
+            &self.inner,
+            "deleteEmbeddedDocuments",
+            &[jstr!("Item"), &ids],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Get the value of a condition on the actor, e.g. 2 for frightened 2, or None if it doesn't have it
+    pub fn condition_value(&self, slug: &str) -> Option<f64> {
+        let condition = self
+            .conditions()
+            .into_iter()
+            .find(|condition| condition.slug().as_deref() == Some(slug))?;
+        Some(condition.condition_value().unwrap_or(1.0))
+    }
+
+    /// Lower a valued condition by one, removing it at zero
+    pub async fn decrease_condition(&self, slug: &str) -> Result<(), Error> {
+        call_method_async(&self.inner, "decreaseCondition", &[jstr!(slug)]).await?;
+        Ok(())
+    }
+
+    /// Raise a valued condition to the given value, adding it if the actor doesn't have it
+    pub async fn increase_condition(&self, slug: &str, value: f64) -> Result<(), Error> {
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, jstr!("value"), &JsValue::from(value))?;
+        call_method_async(&self.inner, "increaseCondition", &[jstr!(slug), &options]).await?;
+        Ok(())
+    }
+
+    /// Roll a recovery check for a dying character
+    pub async fn roll_recovery(&self) -> Result<(), Error> {
+        call_method_async(&self.inner, "rollRecovery", &[]).await?;
+        Ok(())
+    }
+
+    /// Roll one of the actor's saving throws: "fortitude", "reflex", or "will"
+    pub async fn roll_save(&self, save: &str) -> Result<(), Error> {
+        let statistic = get_path!(&self.inner, &format!("saves.{save}"))?;
+        call_method_async(&statistic, "roll", &[]).await?;
+        Ok(())
+    }
+
+    /// Roll one of the actor's saving throws against a DC without the roll dialog, returning None
+    /// if the roll was cancelled
+    ///
+    /// `origin` is the item forcing the save, like a spell, so its roll options apply.
+    pub async fn roll_save_against(
+        &self,
+        save: &str,
+        dc: f64,
+        origin: Option<&Item>,
+    ) -> Result<Option<Roll>, Error> {
+        let statistic = get_path!(&self.inner, &format!("saves.{save}"))?;
+        let dc_obj = js_sys::Object::new();
+        js_sys::Reflect::set(&dc_obj, jstr!("value"), &JsValue::from(dc))?;
+        let args = js_sys::Object::new();
+        js_sys::Reflect::set(&args, jstr!("dc"), &dc_obj)?;
+        js_sys::Reflect::set(&args, jstr!("skipDialog"), &JsValue::TRUE)?;
+        if let Some(origin) = origin {
+            js_sys::Reflect::set(&args, jstr!("item"), origin.as_js_value())?;
+        }
+        let roll = call_method_async(&statistic, "roll", &[&args]).await?;
+        Ok((!roll.is_null() && !roll.is_undefined()).then(|| roll.into()))
+    }
+
+    /// Roll a skill check against a DC, returning None if the roll was cancelled
+    ///
+    /// `roll_mode` overrides the user's roll mode, e.g. "blindroll" for a secret check.
+    pub async fn roll_skill(
+        &self,
+        skill: &str,
+        dc: f64,
+        roll_mode: Option<&str>,
+    ) -> Result<Option<Roll>, Error> {
+        let statistic = get_path!(&self.inner, &format!("skills.{skill}"))?;
+        let dc_obj = js_sys::Object::new();
+        js_sys::Reflect::set(&dc_obj, jstr!("value"), &JsValue::from(dc))?;
+        let args = js_sys::Object::new();
+        js_sys::Reflect::set(&args, jstr!("dc"), &dc_obj)?;
+        if let Some(roll_mode) = roll_mode {
+            js_sys::Reflect::set(&args, jstr!("rollMode"), jstr!(roll_mode))?;
+        }
+        let roll = call_method_async(&statistic, "roll", &[&args]).await?;
+        Ok((!roll.is_null() && !roll.is_undefined()).then(|| roll.into()))
+    }
+
+    /// Get the Bulk this actor is carrying and their encumbrance thresholds
+    pub fn inventory_bulk(&self) -> Option<InventoryBulk> {
+        let bulk = get_path!(&self.inner, "inventory.bulk").ok()?;
+        let flag = |key: &str| {
+            get_property(&bulk, key)
+                .ok()
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        };
+        Some(InventoryBulk {
+            normal: get_path!(&bulk, "value.normal").ok()?.as_f64()?,
+            light: get_path!(&bulk, "value.light")
+                .ok()
+                .and_then(|v| v.as_f64())
+                .unwrap_or_default(),
+            encumbered_after: get_f64_property(&bulk, "encumberedAfter").unwrap_or_default(),
+            max: get_f64_property(&bulk, "maxBulk").unwrap_or_default(),
+            encumbered: flag("isEncumbered"),
+            over_max: flag("isOverMax"),
+        })
+    }
+
+    /// Get the token representing this actor on the current scene, if any
+    pub fn active_token(&self) -> Option<Token> {
+        let tokens = call_method(&self.inner, "getActiveTokens", &[]).ok()?;
+        js_iter!(tokens).next().map(Into::into)
+    }
+
+    /// Apply damage (or healing, if negative) to this actor via the PF2e API
+    ///
+    /// `damage` may be a number or a damage roll; rolls keep their damage types for IWR.
+    pub async fn apply_damage(
+        &self,
+        damage: &JsValue,
+        token: Option<&Token>,
+        skip_iwr: bool,
+        shield_block: bool,
+    ) -> Result<(), Error> {
+        let params = js_sys::Object::new();
+        js_sys::Reflect::set(&params, jstr!("damage"), damage)?;
+        if let Some(token) = token {
+            js_sys::Reflect::set(&params, jstr!("token"), &token.document()?)?;
+        }
+        js_sys::Reflect::set(&params, jstr!("skipIWR"), &JsValue::from(skip_iwr))?;
+        js_sys::Reflect::set(
+            &params,
+            jstr!("shieldBlockRequest"),
+            &JsValue::from(shield_block),
+        )?;
+        call_method_async(&self.inner, "applyDamage", &[&params]).await?;
+        Ok(())
+    }
+
+    /// Get a flag value
+    pub fn get_flag(&self, scope: &str, key: &str) -> JsValue {
+        call_method(&self.inner, "getFlag", &[jstr!(scope), jstr!(key)])
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+
+    /// Set a flag value
+    pub async fn set_flag(&self, scope: &str, key: &str, value: &JsValue) -> Result<(), Error> {
+        call_method_async(&self.inner, "setFlag", &[jstr!(scope), jstr!(key), value]).await?;
+        Ok(())
+    }
+
+    /// Check if the current user has owner permission on this actor
+    pub fn is_owner(&self) -> bool {
+        get_property(&self.inner, "isOwner")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Check if a specific user has at least `level` ownership of this actor, counting GMs per `count_gm`
+    pub fn is_owned_by(&self, user: &User, count_gm: GMStrategy, level: OwnershipLevel) -> bool {
+        let Some(user_id) = user.id() else {
+            return false;
+        };
+        let is_gm = user.is_gm();
+
+        let Ok(ownership) = get_property(&self.inner, "ownership") else {
+            return false;
+        };
+        let level_num = get_property(&ownership, &user_id)
+            .map(|l| l.as_f64())
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        let owns = level_num >= level.level();
+
+        match count_gm {
+            GMStrategy::Normal => owns,
             GMStrategy::Never => {
                 if is_gm {
                     false
@@ -787,7 +2651,7 @@ impl Actor {
             }
             GMStrategy::OnlyIfExclusive => {
                 if is_gm && owns {
-                    let has_non_gm_owners = self.has_non_gm_owners();
+                    let has_non_gm_owners = self.has_non_gm_owners(level);
                     !has_non_gm_owners
                 } else {
                     owns
@@ -795,7 +2659,7 @@ impl Actor {
             }
             GMStrategy::IfNoPlayers => {
                 if is_gm {
-                    let has_non_gm_owners = self.has_non_gm_owners();
+                    let has_non_gm_owners = self.has_non_gm_owners(level);
                     !has_non_gm_owners
                 } else {
                     owns
@@ -804,62 +2668,244 @@ impl Actor {
         }
     }
 
-    /// Check if there are any non-GM owners of this actor
-    fn has_non_gm_owners(&self) -> bool {
-        let Ok(ownership) = get_property(&self.inner, "ownership") else {
-            return false;
-        };
-        let Ok(game) = Game::instance() else {
-            return false;
-        };
-        let Ok(users) = game.users() else {
-            return false;
-        };
+    /// Check if there are any non-GM owners of this actor at `level` or above
+    fn has_non_gm_owners(&self, level: OwnershipLevel) -> bool {
+        let Ok(ownership) = get_property(&self.inner, "ownership") else {
+            return false;
+        };
+        let Ok(game) = Game::instance() else {
+            return false;
+        };
+        let Ok(users) = game.users() else {
+            return false;
+        };
+
+        let ownership_obj: &js_sys::Object = ownership.unchecked_ref();
+        let keys = js_sys::Object::keys(ownership_obj);
+
+        for i in 0..keys.length() {
+            let Some(user_id) = keys.get(i).as_string() else {
+                continue;
+            };
+
+            // Skip "default" ownership key
+            if user_id == "default" {
+                continue;
+            }
+
+            let Ok(user_level) = get_property(&ownership, &user_id) else {
+                continue;
+            };
+            let Some(level_num) = user_level.as_f64() else {
+                continue;
+            };
+
+            if level_num >= level.level() {
+                // Check if this owner is not a GM
+                if let Some(user) = users.get(&user_id) {
+                    if !user.is_gm() {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Check if the current user owns this actor
+    pub fn is_owned_by_current_user(&self, count_gm: GMStrategy, level: OwnershipLevel) -> bool {
+        let Ok(game) = Game::instance() else {
+            return false;
+        };
+        let Ok(user) = game.user() else { return false };
+        self.is_owned_by(&user, count_gm, level)
+    }
+
+    /// Get the underlying JsValue (for compatibility)
+    pub fn as_js_value(&self) -> &JsValue {
+        &self.inner
+    }
+}
+
+/// Carried Bulk of a PF2e actor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InventoryBulk {
+    /// Whole Bulk carried
+    pub normal: f64,
+    /// Light items not yet adding up to a whole Bulk
+    pub light: f64,
+    /// The actor is encumbered above this much Bulk
+    pub encumbered_after: f64,
+    /// The most Bulk the actor can carry
+    pub max: f64,
+    pub encumbered: bool,
+    pub over_max: bool,
+}
+
+/// Represents a combat encounter
+pub struct Combat {
+    inner: JsValue,
+}
+
+impl From<JsValue> for Combat {
+    fn from(inner: JsValue) -> Self {
+        Combat { inner }
+    }
+}
+
+impl Combat {
+    /// Get the combatant whose turn it is
+    pub fn combatant(&self) -> Option<Combatant> {
+        let combatant = get_property(&self.inner, "combatant").ok()?;
+        if combatant.is_null() || combatant.is_undefined() {
+            None
+        } else {
+            Some(combatant.into())
+        }
+    }
+
+    /// Get the current round number
+    pub fn round(&self) -> f64 {
+        get_f64_property(&self.inner, "round").unwrap_or_default()
+    }
+
+    /// Find a combatant by ID
+    pub fn get_combatant(&self, id: &str) -> Option<Combatant> {
+        self.combatants()
+            .into_iter()
+            .find(|combatant| combatant.id().as_deref() == Some(id))
+    }
+
+    /// Get every combatant in turn order
+    pub fn combatants(&self) -> Vec<Combatant> {
+        get_property(&self.inner, "turns")
+            .map(|turns| js_iter!(turns).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the underlying JsValue
+    pub fn as_js_value(&self) -> &JsValue {
+        &self.inner
+    }
+}
+
+/// Represents a participant in a combat encounter
+pub struct Combatant {
+    inner: JsValue,
+}
+
+impl From<JsValue> for Combatant {
+    fn from(inner: JsValue) -> Self {
+        Combatant { inner }
+    }
+}
+
+impl Combatant {
+    pub fn id(&self) -> Option<String> {
+        get_string_property(&self.inner, "id")
+    }
+
+    pub fn name(&self) -> String {
+        get_string_property(&self.inner, "name").unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    /// Get the combatant's actor
+    pub fn actor(&self) -> Option<Actor> {
+        let actor = get_property(&self.inner, "actor").ok()?;
+        if actor.is_null() || actor.is_undefined() {
+            None
+        } else {
+            Some(actor.into())
+        }
+    }
+
+    /// Get the combatant's initiative, if it's been rolled
+    pub fn initiative(&self) -> Option<f64> {
+        get_f64_property(&self.inner, "initiative")
+    }
+
+    /// Get the combatant's token on the canvas
+    pub fn token(&self) -> Option<Token> {
+        let token = get_path!(&self.inner, "token.object").ok()?;
+        if token.is_null() || token.is_undefined() {
+            None
+        } else {
+            Some(token.into())
+        }
+    }
+
+    /// Get the underlying JsValue
+    pub fn as_js_value(&self) -> &JsValue {
+        &self.inner
+    }
+}
 
-        let ownership_obj: &js_sys::Object = ownership.unchecked_ref();
-        let keys = js_sys::Object::keys(ownership_obj);
+/// A measured template document, like a spell's burst or cone
+pub struct MeasuredTemplate {
+    inner: JsValue,
+}
 
-        for i in 0..keys.length() {
-            let Some(user_id) = keys.get(i).as_string() else {
-                continue;
-            };
+impl From<JsValue> for MeasuredTemplate {
+    fn from(inner: JsValue) -> Self {
+        MeasuredTemplate { inner }
+    }
+}
 
-            // Skip "default" ownership key
-            if user_id == "default" {
-                continue;
-            }
+impl MeasuredTemplate {
+    /// Get the ID of the user who placed the template
+    pub fn author_id(&self) -> Option<String> {
+        get_path!(&self.inner, "author.id").ok()?.as_string()
+    }
 
-            // Get ownership level (3 = OWNER)
-            let Ok(level) = get_property(&ownership, &user_id) else {
-                continue;
-            };
-            let Some(level_num) = level.as_f64() else {
-                continue;
-            };
+    /// Get the kind of item the template came from, e.g. "spell"
+    pub fn origin_type(&self) -> Option<String> {
+        get_path!(&self.inner, "flags.pf2e.origin.type")
+            .ok()?
+            .as_string()
+    }
 
-            if level_num >= 3.0 {
-                // Check if this owner is not a GM
-                if let Some(user) = users.get(&user_id) {
-                    if !user.is_gm() {
-                        return true;
-                    }
-                }
-            }
-        }
+    /// Get the UUID of the actor whose item placed the template
+    pub fn origin_actor_uuid(&self) -> Option<String> {
+        get_path!(&self.inner, "flags.pf2e.origin.actor")
+            .ok()?
+            .as_string()
+    }
 
-        false
+    /// Get the template's shape: "circle", "cone", "rect", or "ray"
+    pub fn shape(&self) -> Option<String> {
+        get_string_property(&self.inner, "t")
     }
 
-    /// Check if the current user owns this actor
-    pub fn is_owned_by_current_user(&self, count_gm: GMStrategy) -> bool {
-        let Ok(game) = Game::instance() else {
-            return false;
-        };
-        let Ok(user) = game.user() else { return false };
-        self.is_owned_by(&user, count_gm)
+    /// Get the template's origin on the canvas
+    pub fn origin(&self) -> Option<(f64, f64)> {
+        Some((
+            get_f64_property(&self.inner, "x")?,
+            get_f64_property(&self.inner, "y")?,
+        ))
     }
 
-    /// Get the underlying JsValue (for compatibility)
+    /// Get the template's length or radius in feet
+    pub fn distance(&self) -> f64 {
+        get_f64_property(&self.inner, "distance").unwrap_or_default()
+    }
+
+    /// Get the direction the template points, in degrees clockwise from east
+    pub fn direction(&self) -> f64 {
+        get_f64_property(&self.inner, "direction").unwrap_or_default()
+    }
+
+    /// Get a cone's angle in degrees
+    pub fn angle(&self) -> f64 {
+        get_f64_property(&self.inner, "angle").unwrap_or(90.0)
+    }
+
+    /// Get a line's width in feet
+    pub fn width(&self) -> f64 {
+        get_f64_property(&self.inner, "width").unwrap_or(5.0)
+    }
+
+    /// Get the underlying JsValue
     pub fn as_js_value(&self) -> &JsValue {
         &self.inner
     }
@@ -887,6 +2933,136 @@ impl Message {
         get_string_property(&self.inner, "content")
     }
 
+    /// Replace the message content
+    pub async fn set_content(&self, content: &str) -> Result<(), Error> {
+        let changes = js_sys::Object::new();
+        js_sys::Reflect::set(&changes, jstr!("content"), jstr!(content))?;
+        call_method_async(&self.inner, "update", &[&changes]).await?;
+        Ok(())
+    }
+
+    /// Check if the current user can update this message
+    pub fn is_owner(&self) -> bool {
+        get_property(&self.inner, "isOwner")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Get a flag value
+    pub fn get_flag(&self, scope: &str, key: &str) -> JsValue {
+        call_method(&self.inner, "getFlag", &[jstr!(scope), jstr!(key)])
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+
+    /// Set a flag value
+    pub async fn set_flag(&self, scope: &str, key: &str, value: &JsValue) -> Result<(), Error> {
+        call_method_async(&self.inner, "setFlag", &[jstr!(scope), jstr!(key), value]).await?;
+        Ok(())
+    }
+
+    /// Get the user who created this message
+    pub fn author(&self) -> Option<User> {
+        let author = get_property(&self.inner, "author").ok()?;
+        if author.is_null() || author.is_undefined() {
+            None
+        } else {
+            Some(author.into())
+        }
+    }
+
+    /// Get the message's flavor text, such as the header of a check
+    pub fn flavor(&self) -> Option<String> {
+        get_string_property(&self.inner, "flavor")
+    }
+
+    /// Check if the roll in this message is hidden from its author
+    pub fn is_blind(&self) -> bool {
+        get_property(&self.inner, "blind")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Check if the message is only shown to some users
+    pub fn is_whisper(&self) -> bool {
+        get_property(&self.inner, "whisper")
+            .ok()
+            .map(|whisper| js_sys::Array::from(&whisper).length() > 0)
+            .unwrap_or_default()
+    }
+
+    /// Render this message's chat card, with system listeners attached
+    pub async fn render_html(&self) -> Result<HtmlElement, Error> {
+        Ok(call_method_async(&self.inner, "renderHTML", &[])
+            .await?
+            .into())
+    }
+
+    /// Get the UUID of the item or actor this message originated from
+    pub fn origin_uuid(&self) -> Option<String> {
+        get_path!(&self.inner, "flags.pf2e.origin.uuid")
+            .ok()?
+            .as_string()
+    }
+
+    /// Get the actor who spoke this message
+    pub fn speaker_actor(&self) -> Option<Actor> {
+        let actor = get_property(&self.inner, "actor").ok()?;
+        if actor.is_null() || actor.is_undefined() {
+            None
+        } else {
+            Some(actor.into())
+        }
+    }
+
+    /// Check if this is a recurring persistent damage roll or its recovery flat check
+    pub fn is_persistent_damage(&self) -> bool {
+        let persistent_roll = self.first_roll().is_some_and(|roll| {
+            get_path!(roll.as_js_value(), "options.evaluatePersistent")
+                .ok()
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        });
+        let persistent_context = self.pf2e_context().is_some_and(|context| {
+            context
+                .options()
+                .iter()
+                .any(|o| o == "item:slug:persistent-damage" || o == "persistent")
+        });
+        persistent_roll || persistent_context
+    }
+
+    /// Check if the message content links to effect or condition items for players to apply
+    pub fn has_effect_links(&self) -> bool {
+        const EFFECT_MARKERS: [&str; 7] = [
+            "spell-effects.",
+            "feat-effects.",
+            "equipment-effects.",
+            "bestiary-effects.",
+            "other-effects.",
+            "conditionitems.",
+            "{Effect:",
+        ];
+        self.content()
+            .is_some_and(|content| EFFECT_MARKERS.iter().any(|marker| content.contains(marker)))
+    }
+
+    /// Check if the message content contains inline `@Check` enrichers
+    pub fn has_inline_checks(&self) -> bool {
+        self.content().is_some_and(|content| {
+            content.contains("@Check[") || content.contains("data-pf2-check")
+        })
+    }
+
+    /// Check if the current user may see the message's content, e.g. it isn't a blind roll
+    pub fn is_content_visible(&self) -> bool {
+        get_property(&self.inner, "isContentVisible")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    }
+
     pub fn pf2e_type(&self) -> Option<String> {
         let type_val = get_path!(&self.inner, "flags.pf2e.context.type").ok()?;
         type_val.as_string()
@@ -897,10 +3073,8 @@ impl Message {
         let mut rolls = Vec::new();
         if let Ok(rolls_val) = get_property(&self.inner, "rolls") {
             if let Ok(Some(iter)) = js_sys::try_iter(&rolls_val) {
-                for item in iter {
-                    if let Ok(inner) = item {
-                        rolls.push(inner.into());
-                    }
+                for inner in iter.flatten() {
+                    rolls.push(inner.into());
                 }
             }
         }
@@ -932,12 +3106,10 @@ impl Message {
             return vec![];
         };
 
-        for target_result in iter {
-            if let Ok(target_uuid) = target_result {
-                if let Some(uuid_str) = target_uuid.as_string() {
-                    if let Ok(token_js) = from_uuid_raw(&uuid_str).await {
-                        targets.push(token_js.into());
-                    }
+        for target_uuid in iter.flatten() {
+            if let Some(uuid_str) = target_uuid.as_string() {
+                if let Ok(token_js) = from_uuid_raw(&uuid_str).await {
+                    targets.push(token_js.into());
                 }
             }
         }
@@ -945,6 +3117,26 @@ impl Message {
         targets
     }
 
+    /// Get the save requested by pf2e-toolbelt's targetHelper, if any
+    pub fn toolbelt_save(&self) -> Option<ToolbeltSave> {
+        let save = get_path!(&self.inner, "flags.pf2e-toolbelt.targetHelper.save").ok()?;
+        if save.is_null() || save.is_undefined() {
+            None
+        } else {
+            Some(save.into())
+        }
+    }
+
+    /// Get the damage PF2e actually applied to an actor, for "damage taken" messages
+    pub fn applied_damage(&self) -> Option<AppliedDamage> {
+        let applied = get_path!(&self.inner, "flags.pf2e.appliedDamage").ok()?;
+        if applied.is_null() || applied.is_undefined() {
+            None
+        } else {
+            Some(applied.into())
+        }
+    }
+
     /// Get all target actor UUIDs
     pub async fn target_uuids(&self) -> Vec<String> {
         let mut uuids = Vec::new();
@@ -968,49 +3160,295 @@ impl Message {
         uuids
     }
 
-    /// Pop out this message into its own window
-    pub async fn popout(&self) -> Result<(), Error> {
-        let global = js_sys::global();
-        let foundry = get_property(&global, "foundry")?;
-        let applications = get_property(&foundry, "applications")?;
-        let sidebar = get_property(&applications, "sidebar")?;
-        let apps = get_property(&sidebar, "apps")?;
-        let chat_popout_class = get_property(&apps, "ChatPopout")?;
+    /// Pop out this message into its own window, optionally at a given position
+    pub async fn popout(
+        &self,
+        position: Option<&ApplicationPosition>,
+    ) -> Result<Application, Error> {
+        let global = js_sys::global();
+        let foundry = get_property(&global, "foundry")?;
+        let applications = get_property(&foundry, "applications")?;
+        let sidebar = get_property(&applications, "sidebar")?;
+        let apps = get_property(&sidebar, "apps")?;
+        let chat_popout_class = get_property(&apps, "ChatPopout")?;
+
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, jstr!("message"), &self.inner)?;
+        if let Some(position) = position {
+            let position = serde_wasm_bindgen::to_value(position)
+                .map_err(|e| Error::Custom(format!("Failed to serialize position: {e}")))?;
+            js_sys::Reflect::set(&options, jstr!("position"), &position)?;
+        }
+
+        let args = js_sys::Array::new();
+        args.push(&options);
+        let popout = js_sys::Reflect::construct(chat_popout_class.unchecked_ref(), &args)?;
+
+        let render_fn = get_property(&popout, "render")?;
+        let render_args = js_sys::Array::new();
+        render_args.push(&JsValue::from(true)); // force: true
+
+        let promise = js_sys::Reflect::apply(render_fn.unchecked_ref(), &popout, &render_args)?;
+
+        JsFuture::from(js_sys::Promise::from(promise)).await?;
+        Ok(popout.into())
+    }
+
+    /// Create a new chat message
+    pub async fn create(content: &str) -> Result<Message, Error> {
+        Self::create_whisper(content, &[]).await
+    }
+
+    /// Create a chat message only the given users can see, or everyone if there are none
+    pub async fn create_whisper(content: &str, user_ids: &[String]) -> Result<Message, Error> {
+        let obj = js_sys::Object::new();
+        js_sys::Reflect::set(&obj, jstr!("content"), jstr!(content))?;
+        if !user_ids.is_empty() {
+            let whisper: js_sys::Array = user_ids.iter().map(|id| JsValue::from_str(id)).collect();
+            js_sys::Reflect::set(&obj, jstr!("whisper"), &whisper)?;
+        }
+
+        let global = js_sys::global();
+        let chat_message_class = get_property(&global, "ChatMessage")?;
+        let create_fn = get_property(&chat_message_class, "create")?;
+
+        let args = js_sys::Array::new();
+        args.push(&obj);
+
+        let promise =
+            js_sys::Reflect::apply(create_fn.unchecked_ref(), &chat_message_class, &args)?;
+
+        let inner = JsFuture::from(js_sys::Promise::from(promise)).await?;
+        Ok(inner.into())
+    }
+
+    /// Reroll the check in this message with PF2e's reroll, spending one of the speaker's hero points
+    pub async fn reroll_with_hero_point(&self) -> Result<(), Error> {
+        let check = get_path!(&JsValue::from(js_sys::global()), "game.pf2e.Check")?;
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, jstr!("heroPoint"), &JsValue::TRUE)?;
+        call_method_async(&check, "rerollFromMessage", &[&self.inner, &options]).await?;
+        Ok(())
+    }
+
+    /// Get the item the message was posted from, like a spell or feat
+    pub fn item(&self) -> Option<Item> {
+        let item = get_property(&self.inner, "item").ok()?;
+        if item.is_null() || item.is_undefined() {
+            None
+        } else {
+            Some(item.into())
+        }
+    }
+
+    /// Get the rank a spell in this message was cast at
+    pub fn cast_rank(&self) -> Option<u8> {
+        get_path!(&self.inner, "flags.pf2e.origin.castRank")
+            .ok()
+            .and_then(|v| v.as_f64())
+            .map(|rank| rank as u8)
+    }
+
+    /// Get the slug of the item the message was posted from, like a feat or action card
+    pub fn item_slug(&self) -> Option<String> {
+        get_path!(&self.inner, "item.system.slug").ok()?.as_string()
+    }
+
+    /// Get the underlying JsValue (for compatibility)
+    pub fn as_js_value(&self) -> &JsValue {
+        &self.inner
+    }
+}
+
+/// The HP and shield changes PF2e made when damage or healing was applied
+pub struct AppliedDamage {
+    inner: JsValue,
+}
+
+impl From<JsValue> for AppliedDamage {
+    fn from(inner: JsValue) -> Self {
+        AppliedDamage { inner }
+    }
+}
+
+impl AppliedDamage {
+    /// Get the UUID of the actor the damage was applied to
+    pub fn actor_uuid(&self) -> Option<String> {
+        get_string_property(&self.inner, "uuid")
+    }
+
+    /// Check if this was healing rather than damage
+    pub fn is_healing(&self) -> bool {
+        get_property(&self.inner, "isHealing")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Check if this application has since been undone
+    pub fn is_reverted(&self) -> bool {
+        get_property(&self.inner, "isReverted")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Get what a changed value was before the damage, e.g. for "system.attributes.hp.value"
+    pub fn previous_value(&self, path: &str) -> Option<f64> {
+        let updates = get_property(&self.inner, "updates")
+            .ok()?
+            .dyn_into::<js_sys::Array>()
+            .ok()?;
+        updates
+            .iter()
+            .find(|update| get_string_property(update, "path").as_deref() == Some(path))
+            .and_then(|update| get_f64_property(&update, "value"))
+    }
+
+    /// Check if anything actually changed, which is false when the damage was fully resisted
+    pub fn has_changes(&self) -> bool {
+        let updated = get_property(&self.inner, "updates")
+            .ok()
+            .and_then(|updates| updates.dyn_into::<js_sys::Array>().ok())
+            .is_some_and(|updates| updates.length() > 0);
+        let shield_damaged = get_path!(&self.inner, "shield.damage")
+            .ok()
+            .and_then(|v| v.as_f64())
+            .is_some_and(|damage| damage > 0.0);
+        updated || shield_damaged
+    }
+
+    /// Get the underlying JsValue
+    pub fn as_js_value(&self) -> &JsValue {
+        &self.inner
+    }
+}
+
+/// A saving throw requested through pf2e-toolbelt's targetHelper
+pub struct ToolbeltSave {
+    inner: JsValue,
+}
+
+impl From<JsValue> for ToolbeltSave {
+    fn from(inner: JsValue) -> Self {
+        ToolbeltSave { inner }
+    }
+}
+
+impl ToolbeltSave {
+    /// Get the save statistic (fortitude, reflex, or will)
+    pub fn statistic(&self) -> Option<String> {
+        get_string_property(&self.inner, "statistic")
+    }
+
+    /// Get the save DC
+    pub fn dc(&self) -> Option<f64> {
+        get_f64_property(&self.inner, "dc")
+    }
+
+    /// Check if this is a basic save
+    pub fn basic(&self) -> bool {
+        get_property(&self.inner, "basic")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Get the underlying JsValue
+    pub fn as_js_value(&self) -> &JsValue {
+        &self.inner
+    }
+}
+
+/// Represents a roll result
+pub struct Roll {
+    inner: JsValue,
+}
 
-        let options = js_sys::Object::new();
-        js_sys::Reflect::set(&options, jstr!("message"), &self.inner)?;
+impl From<JsValue> for Roll {
+    fn from(inner: JsValue) -> Self {
+        Roll { inner }
+    }
+}
 
-        let args = js_sys::Array::new();
-        args.push(&options);
-        let popout = js_sys::Reflect::construct(chat_popout_class.unchecked_ref(), &args)?;
+impl Roll {
+    /// Roll a dice formula, e.g. "1d20 + 5", without posting it to chat
+    pub async fn evaluate(formula: &str) -> Result<Roll, Error> {
+        let global: JsValue = js_sys::global().into();
+        let roll_class = get_property(&global, "Roll")?;
+        let roll = js_sys::Reflect::construct(
+            roll_class.unchecked_ref(),
+            &js_sys::Array::of1(jstr!(formula)),
+        )?;
+        Ok(call_method_async(&roll, "evaluate", &[]).await?.into())
+    }
 
-        let render_fn = get_property(&popout, "render")?;
-        let render_args = js_sys::Array::new();
-        render_args.push(&JsValue::from(true)); // force: true
+    /// Roll a PF2e damage formula, e.g. "2d6[fire]", keeping its damage types for IWR
+    pub async fn evaluate_damage(formula: &str) -> Result<Roll, Error> {
+        let rolls = get_path!(&JsValue::from(js_sys::global()), "CONFIG.Dice.rolls")?;
+        let damage_roll_class = js_iter!(rolls)
+            .find(|class| get_string_property(class, "name").as_deref() == Some("DamageRoll"))
+            .ctx("PF2e DamageRoll class")?;
+        let roll = js_sys::Reflect::construct(
+            damage_roll_class.unchecked_ref(),
+            &js_sys::Array::of1(jstr!(formula)),
+        )?;
+        Ok(call_method_async(&roll, "evaluate", &[]).await?.into())
+    }
 
-        let promise = js_sys::Reflect::apply(render_fn.unchecked_ref(), &popout, &render_args)?;
+    /// Get the total result of the roll
+    pub fn total(&self) -> f64 {
+        get_f64_property(&self.inner, "total").unwrap_or(0.0)
+    }
 
-        JsFuture::from(js_sys::Promise::from(promise)).await?;
-        Ok(())
+    /// Serialize the evaluated roll, including each die's result
+    pub fn to_json(&self) -> Option<String> {
+        js_sys::JSON::stringify(&self.inner).ok()?.as_string()
     }
 
-    /// Create a new chat message
-    pub async fn create(content: &str) -> Result<Message, Error> {
-        let obj = js_sys::Object::new();
-        js_sys::Reflect::set(&obj, jstr!("content"), jstr!(content))?;
+    /// Check if this is a PF2e damage roll that only heals
+    pub fn is_healing(&self) -> bool {
+        let Ok(kinds) = get_property(&self.inner, "kinds") else {
+            return false;
+        };
+        let kinds: Vec<String> = js_iter!(kinds).filter_map(|k| k.as_string()).collect();
+        kinds.iter().any(|k| k == "healing") && !kinds.iter().any(|k| k == "damage")
+    }
 
-        let global = js_sys::global();
-        let chat_message_class = get_property(&global, "ChatMessage")?;
-        let create_fn = get_property(&chat_message_class, "create")?;
+    /// Get the PF2e damage instances (one per damage type) making up this roll
+    pub fn damage_instances(&self) -> Vec<DamageInstance> {
+        let Ok(instances) = get_property(&self.inner, "instances") else {
+            return vec![];
+        };
+        js_iter!(instances).map(Into::into).collect()
+    }
 
-        let args = js_sys::Array::new();
-        args.push(&obj);
+    /// Get the natural result of the roll's d20, the kept one if rolled with fortune or misfortune
+    pub fn natural_d20(&self) -> Option<f64> {
+        let dice = get_property(&self.inner, "dice").ok()?;
+        js_iter!(dice)
+            .find(|die| get_f64_property(die, "faces") == Some(20.0))
+            .and_then(|die| get_f64_property(&die, "total"))
+    }
 
-        let promise =
-            js_sys::Reflect::apply(create_fn.unchecked_ref(), &chat_message_class, &args)?;
+    /// Get the roll's formula
+    pub fn formula(&self) -> String {
+        get_string_property(&self.inner, "formula").unwrap_or_default()
+    }
 
-        let inner = JsFuture::from(js_sys::Promise::from(promise)).await?;
-        Ok(inner.into())
+    /// Create a copy of this roll with every term multiplied and then increased
+    pub fn alter(&self, multiply: f64, add: f64) -> Result<Roll, Error> {
+        let clone = call_method(&self.inner, "clone", &[])?;
+        let altered = call_method(&clone, "alter", &[&multiply.into(), &add.into()])?;
+        Ok(altered.into())
+    }
+
+    /// Get the outcome of a check roll made against a DC
+    pub fn degree_of_success(&self) -> Option<DegreeOfSuccess> {
+        let index = get_property(&self.inner, "degreeOfSuccess")
+            .ok()?
+            .as_f64()?;
+        DegreeOfSuccess::from_index(index as u8)
     }
 
     /// Get the underlying JsValue (for compatibility)
@@ -1019,29 +3457,130 @@ impl Message {
     }
 }
 
-/// Represents a roll result
-pub struct Roll {
+/// A single damage type's share of a PF2e damage roll
+pub struct DamageInstance {
     inner: JsValue,
 }
 
-impl From<JsValue> for Roll {
+impl From<JsValue> for DamageInstance {
     fn from(inner: JsValue) -> Self {
-        Roll { inner }
+        DamageInstance { inner }
     }
 }
 
-impl Roll {
-    /// Get the total result of the roll
+impl DamageInstance {
+    /// Get the damage type, e.g. "fire" or "slashing"
+    pub fn damage_type(&self) -> String {
+        get_string_property(&self.inner, "type").unwrap_or_default()
+    }
+
+    /// Get the total damage of this type
     pub fn total(&self) -> f64 {
         get_f64_property(&self.inner, "total").unwrap_or(0.0)
     }
 
-    /// Get the underlying JsValue (for compatibility)
+    /// Check if this is persistent damage
+    pub fn is_persistent(&self) -> bool {
+        get_property(&self.inner, "persistent")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Get how much of the total comes from a component such as "precision" or "splash"
+    pub fn component_total(&self, component: &str) -> f64 {
+        call_method(&self.inner, "componentTotal", &[jstr!(component)])
+            .ok()
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0)
+    }
+
+    /// Get the underlying JsValue
     pub fn as_js_value(&self) -> &JsValue {
         &self.inner
     }
 }
 
+/// PF2e check outcome
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DegreeOfSuccess {
+    CriticalFailure,
+    Failure,
+    Success,
+    CriticalSuccess,
+}
+
+impl DegreeOfSuccess {
+    /// Parse the outcome string PF2e stores in message contexts
+    pub fn from_pf2e(value: &str) -> Option<Self> {
+        match value {
+            "criticalFailure" => Some(DegreeOfSuccess::CriticalFailure),
+            "failure" => Some(DegreeOfSuccess::Failure),
+            "success" => Some(DegreeOfSuccess::Success),
+            "criticalSuccess" => Some(DegreeOfSuccess::CriticalSuccess),
+            _ => None,
+        }
+    }
+
+    /// Convert PF2e's numeric degree, 0 for a critical failure through 3 for a critical success
+    pub fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(DegreeOfSuccess::CriticalFailure),
+            1 => Some(DegreeOfSuccess::Failure),
+            2 => Some(DegreeOfSuccess::Success),
+            3 => Some(DegreeOfSuccess::CriticalSuccess),
+            _ => None,
+        }
+    }
+
+    /// Work out the degree of success of a check against a DC, where a natural 20 or 1 moves it a step
+    pub fn from_check(total: f64, dc: f64, natural: Option<f64>) -> Self {
+        let degree: i8 = if total >= dc + 10.0 {
+            3
+        } else if total >= dc {
+            2
+        } else if total > dc - 10.0 {
+            1
+        } else {
+            0
+        };
+        let degree = match natural {
+            Some(20.0) => degree + 1,
+            Some(1.0) => degree - 1,
+            _ => degree,
+        };
+        Self::from_index(degree.clamp(0, 3) as u8).unwrap_or(DegreeOfSuccess::Failure)
+    }
+
+    /// Kebab-case name, for CSS classes
+    pub fn slug(self) -> &'static str {
+        match self {
+            DegreeOfSuccess::CriticalFailure => "critical-failure",
+            DegreeOfSuccess::Failure => "failure",
+            DegreeOfSuccess::Success => "success",
+            DegreeOfSuccess::CriticalSuccess => "critical-success",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DegreeOfSuccess::CriticalFailure => "Critical Failure",
+            DegreeOfSuccess::Failure => "Failure",
+            DegreeOfSuccess::Success => "Success",
+            DegreeOfSuccess::CriticalSuccess => "Critical Success",
+        }
+    }
+
+    pub fn to_pf2e(self) -> &'static str {
+        match self {
+            DegreeOfSuccess::CriticalFailure => "criticalFailure",
+            DegreeOfSuccess::Failure => "failure",
+            DegreeOfSuccess::Success => "success",
+            DegreeOfSuccess::CriticalSuccess => "criticalSuccess",
+        }
+    }
+}
+
 /// PF2e damage context information
 pub struct Pf2eContext {
     inner: JsValue,
@@ -1065,12 +3604,37 @@ impl Pf2eContext {
         Game::from_uuid(&uuid).await
     }
 
+    /// Get the UUID of the targeted token's document
+    pub fn target_token_uuid(&self) -> Option<String> {
+        get_path!(&self.inner, "target.token").ok()?.as_string()
+    }
+
     /// Get the item name (weapon/spell that caused the damage)
     pub fn item_name(&self) -> Option<String> {
         let item = get_property(&self.inner, "item").ok()?;
         get_string_property(&item, "name")
     }
 
+    /// Get the kind of roll, e.g. "attack-roll" or "saving-throw"
+    pub fn roll_type(&self) -> Option<String> {
+        get_string_property(&self.inner, "type")
+    }
+
+    /// Check if this roll is a reroll of an earlier check
+    pub fn is_reroll(&self) -> bool {
+        get_property(&self.inner, "isReroll")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default()
+    }
+
+    /// Get the degree of success of the check this context belongs to
+    pub fn outcome(&self) -> Option<DegreeOfSuccess> {
+        get_string_property(&self.inner, "outcome")
+            .as_deref()
+            .and_then(DegreeOfSuccess::from_pf2e)
+    }
+
     pub fn options(&self) -> Vec<String> {
         get_path!(&self.inner, "options")
             .map(|jsval| {
@@ -1102,6 +3666,22 @@ impl From<JsValue> for HtmlElement {
 }
 
 impl HtmlElement {
+    /// Toggle a CSS class on this element
+    pub fn toggle_class(&self, class_name: &str, force: bool) -> Result<(), Error> {
+        let class_list = get_property(&self.inner, "classList")?;
+        call_method(&class_list, "toggle", &[jstr!(class_name), &force.into()])?;
+        Ok(())
+    }
+
+    /// Smoothly scroll the element into the middle of its scroll container
+    pub fn scroll_into_view(&self) -> Result<(), Error> {
+        let options = js_sys::Object::new();
+        js_sys::Reflect::set(&options, jstr!("behavior"), jstr!("smooth"))?;
+        js_sys::Reflect::set(&options, jstr!("block"), jstr!("center"))?;
+        call_method(&self.inner, "scrollIntoView", &[&options])?;
+        Ok(())
+    }
+
     /// Query for a child element using a CSS selector
     pub fn query_selector(&self, selector: &str) -> Result<Option<HtmlElement>, Error> {
         let query_fn = get_property(&self.inner, "querySelector")?;
@@ -1117,6 +3697,23 @@ impl HtmlElement {
         }
     }
 
+    /// Query for all child elements matching a CSS selector
+    pub fn query_selector_all(&self, selector: &str) -> Vec<HtmlElement> {
+        call_method(&self.inner, "querySelectorAll", &[jstr!(selector)])
+            .map(|nodes| js_iter!(nodes).map(Into::into).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the current value of a form control
+    pub fn value(&self) -> Option<String> {
+        get_string_property(&self.inner, "value")
+    }
+
+    /// Check if a checkbox input is ticked
+    pub fn is_checked(&self) -> bool {
+        get_property(&self.inner, "checked").is_ok_and(|checked| checked.is_truthy())
+    }
+
     pub fn append_child(&self, child: &HtmlElement) -> Result<(), Error> {
         let append_fn = get_property(&self.inner, "appendChild")?;
         let args = js_sys::Array::new();
@@ -1162,6 +3759,41 @@ impl HtmlElement {
         Ok(())
     }
 
+    /// Find the closest ancestor (including itself) matching a CSS selector
+    pub fn closest(&self, selector: &str) -> Option<HtmlElement> {
+        let result = call_method(&self.inner, "closest", &[jstr!(selector)]).ok()?;
+        if result.is_null() || result.is_undefined() {
+            None
+        } else {
+            Some(result.into())
+        }
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<String> {
+        call_method(&self.inner, "getAttribute", &[jstr!(name)])
+            .ok()?
+            .as_string()
+    }
+
+    pub fn remove_event_listener(
+        &self,
+        event_type: &str,
+        callback: &Closure<dyn Fn(JsValue)>,
+    ) -> Result<(), Error> {
+        let remove_listener_fn = get_property(&self.inner, "removeEventListener")?;
+        let args = js_sys::Array::new();
+        args.push(jstr!(event_type));
+        args.push(callback.as_ref());
+        js_sys::Reflect::apply(remove_listener_fn.unchecked_ref(), &self.inner, &args)?;
+        Ok(())
+    }
+
+    /// Remove this element from the document
+    pub fn remove(&self) -> Result<(), Error> {
+        call_method(&self.inner, "remove", &[])?;
+        Ok(())
+    }
+
     /// Insert HTML adjacent to this element
     /// position: "beforebegin", "afterbegin", "beforeend", or "afterend"
     pub fn insert_adjacent_html(&self, position: &str, html: &str) -> Result<(), Error> {
@@ -1178,9 +3810,156 @@ impl HtmlElement {
     }
 }
 
+/// Window placement of an ApplicationV2, as used by its `position` option
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ApplicationPosition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<f64>,
+}
+
+/// Handle to a rendered ApplicationV2 window
+pub struct Application {
+    inner: JsValue,
+}
+
+impl From<JsValue> for Application {
+    fn from(inner: JsValue) -> Self {
+        Application { inner }
+    }
+}
+
+impl Application {
+    /// Get every open application window
+    pub fn instances() -> Vec<Application> {
+        let global: JsValue = js_sys::global().into();
+        let mut apps: Vec<Application> = get_path!(&global, "foundry.applications.instances")
+            .map(|instances| {
+                let values = call_method(&instances, "values", &[]).unwrap_or(JsValue::UNDEFINED);
+                js_iter!(values).map(Into::into).collect()
+            })
+            .unwrap_or_default();
+        // v1 applications are tracked separately in ui.windows
+        if let Ok(windows) = get_path!(&global, "ui.windows") {
+            if let Ok(windows) = windows.dyn_into::<js_sys::Object>() {
+                apps.extend(js_sys::Object::values(&windows).iter().map(Into::into));
+            }
+        }
+        apps
+    }
+
+    /// Find an open ApplicationV2 by its ID
+    pub fn get(id: &str) -> Option<Application> {
+        let global: JsValue = js_sys::global().into();
+        let instances = get_path!(&global, "foundry.applications.instances").ok()?;
+        let app = call_method(&instances, "get", &[jstr!(id)]).ok()?;
+        if app.is_undefined() {
+            None
+        } else {
+            Some(app.into())
+        }
+    }
+
+    /// Find an open chat popout for the given message
+    pub fn find_chat_popout(message_id: &str) -> Option<Application> {
+        Self::instances().into_iter().find(|app| {
+            get_path!(&app.inner, "message.id")
+                .ok()
+                .and_then(|id| id.as_string())
+                .is_some_and(|id| id == message_id)
+        })
+    }
+
+    /// Check if the application is currently rendered
+    pub fn is_rendered(&self) -> bool {
+        get_property(&self.inner, "rendered")
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Get the application's root element
+    pub fn element(&self) -> Option<HtmlElement> {
+        let element = get_property(&self.inner, "element").ok()?;
+        if element.is_null() || element.is_undefined() {
+            None
+        } else {
+            Some(element.into())
+        }
+    }
+
+    /// Get the application's current window position
+    pub fn position(&self) -> Option<ApplicationPosition> {
+        // width and height may be "auto", which we treat as unset
+        let position = get_property(&self.inner, "position").ok()?;
+        Some(ApplicationPosition {
+            left: get_f64_property(&position, "left"),
+            top: get_f64_property(&position, "top"),
+            width: get_f64_property(&position, "width"),
+            height: get_f64_property(&position, "height"),
+        })
+    }
+
+    /// Render the application, opening it if it isn't already
+    pub async fn render(&self) -> Result<(), Error> {
+        call_method_async(&self.inner, "render", &[&JsValue::TRUE]).await?;
+        Ok(())
+    }
+
+    /// Close the application
+    pub async fn close(&self) -> Result<(), Error> {
+        let close_fn = get_property(&self.inner, "close")?;
+        let promise =
+            js_sys::Reflect::apply(close_fn.unchecked_ref(), &self.inner, &js_sys::Array::new())?;
+        JsFuture::from(js_sys::Promise::from(promise)).await?;
+        Ok(())
+    }
+
+    /// Get the underlying JsValue
+    pub fn as_js_value(&self) -> &JsValue {
+        &self.inner
+    }
+}
+
 pub struct Document;
 
 impl Document {
+    /// Check if the game window currently has focus
+    pub fn has_focus() -> bool {
+        js_sys::Reflect::get(&js_sys::global(), jstr!("document"))
+            .and_then(|document| {
+                let has_focus_fn = get_property(&document, "hasFocus")?;
+                js_sys::Reflect::apply(
+                    has_focus_fn.unchecked_ref(),
+                    &document,
+                    &js_sys::Array::new(),
+                )
+            })
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Get the browser window's inner width and height
+    pub fn viewport_size() -> (f64, f64) {
+        let global: JsValue = js_sys::global().into();
+        (
+            get_f64_property(&global, "innerWidth").unwrap_or_default(),
+            get_f64_property(&global, "innerHeight").unwrap_or_default(),
+        )
+    }
+
+    /// Get the document body
+    pub fn body() -> Result<HtmlElement, Error> {
+        let document = js_sys::Reflect::get(&js_sys::global(), jstr!("document"))?;
+        Ok(get_property(&document, "body")?.into())
+    }
+
     /// Create a new HTML element
     pub fn create_element(tag_name: &str) -> Result<HtmlElement, Error> {
         let document = js_sys::Reflect::get(&js_sys::global(), jstr!("document"))?;
@@ -1234,6 +4013,196 @@ impl BestiaryApp {
 /// Module for Foundry VTT Applications
 pub mod application {
     use super::*;
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::thread::LocalKey;
+
+    /// Builder for an ApplicationV2 window whose content is rendered by rust
+    pub struct WindowBuilder {
+        options: js_sys::Object,
+        window: js_sys::Object,
+        controls: js_sys::Array,
+        actions: js_sys::Object,
+        render: JsValue,
+        on_render: JsValue,
+        on_close: JsValue,
+    }
+
+    impl WindowBuilder {
+        pub fn new(id: &str, title: &str) -> Self {
+            let options = js_sys::Object::new();
+            let window = js_sys::Object::new();
+            js_sys::Reflect::set(&options, jstr!("id"), jstr!(id)).unwrap();
+            js_sys::Reflect::set(&window, jstr!("title"), jstr!(title)).unwrap();
+            js_sys::Reflect::set(&window, jstr!("resizable"), &JsValue::TRUE).unwrap();
+            Self {
+                options,
+                window,
+                controls: js_sys::Array::new(),
+                actions: js_sys::Object::new(),
+                render: JsValue::UNDEFINED,
+                on_render: JsValue::UNDEFINED,
+                on_close: JsValue::UNDEFINED,
+            }
+        }
+
+        /// Font Awesome icon class shown in the title bar
+        pub fn icon(self, icon: &str) -> Self {
+            js_sys::Reflect::set(&self.window, jstr!("icon"), jstr!(icon)).unwrap();
+            self
+        }
+
+        /// Extra CSS classes for the window element
+        pub fn classes(self, classes: &[&str]) -> Self {
+            let classes: js_sys::Array = classes.iter().map(|c| JsValue::from_str(c)).collect();
+            js_sys::Reflect::set(&self.options, jstr!("classes"), &classes).unwrap();
+            self
+        }
+
+        pub fn position(self, position: &ApplicationPosition) -> Self {
+            if let Ok(position) = serde_wasm_bindgen::to_value(position) {
+                js_sys::Reflect::set(&self.options, jstr!("position"), &position).unwrap();
+            }
+            self
+        }
+
+        /// Produce the window content, either an HTML string or an element
+        pub fn render<F, Fut>(mut self, render: F) -> Self
+        where
+            F: Fn() -> Fut + 'static,
+            Fut: Future<Output = Result<JsValue, Error>> + 'static,
+        {
+            let callback = Closure::wrap(Box::new(move || {
+                let future = render();
+                wasm_bindgen_futures::future_to_promise(async move {
+                    future.await.map_err(|e| JsValue::from_str(&e.to_string()))
+                })
+            }) as Box<dyn Fn() -> js_sys::Promise>);
+            self.render = callback.into_js_value();
+            self
+        }
+
+        /// Called with the window element after every render, e.g. to attach listeners
+        pub fn on_render(mut self, on_render: impl Fn(HtmlElement) + 'static) -> Self {
+            let callback =
+                Closure::wrap(Box::new(move |element: JsValue| on_render(element.into()))
+                    as Box<dyn Fn(JsValue)>);
+            self.on_render = callback.into_js_value();
+            self
+        }
+
+        /// Add an entry to the window's header controls menu
+        pub fn control(
+            self,
+            action: &str,
+            label: &str,
+            icon: &str,
+            handler: impl Fn() + 'static,
+        ) -> Self {
+            let control = js_sys::Object::new();
+            js_sys::Reflect::set(&control, jstr!("action"), jstr!(action)).unwrap();
+            js_sys::Reflect::set(&control, jstr!("label"), jstr!(label)).unwrap();
+            js_sys::Reflect::set(&control, jstr!("icon"), jstr!(icon)).unwrap();
+            self.controls.push(&control);
+            let callback = Closure::wrap(Box::new(handler) as Box<dyn Fn()>);
+            js_sys::Reflect::set(&self.actions, jstr!(action), &callback.into_js_value()).unwrap();
+            self
+        }
+
+        pub fn on_close(mut self, on_close: impl Fn() + 'static) -> Self {
+            let callback = Closure::wrap(Box::new(on_close) as Box<dyn Fn()>);
+            self.on_close = callback.into_js_value();
+            self
+        }
+
+        /// Create the application; call `render` on the result to show it
+        pub fn build(self) -> Result<Application, Error> {
+            let factory = js_sys::Function::new_with_args(
+                "render, onRender, onClose",
+                r#"
+                return class extends foundry.applications.api.ApplicationV2 {
+                    async _renderHTML() { return render ? await render() : ""; }
+                    _replaceHTML(result, content) {
+                        if (typeof result === "string") content.innerHTML = result;
+                        else content.replaceChildren(result);
+                    }
+                    _onRender() { onRender?.(this.element); }
+                    _onClose() { onClose?.(); }
+                };
+                "#,
+            );
+            let class = factory.call3(
+                &JsValue::NULL,
+                &self.render,
+                &self.on_render,
+                &self.on_close,
+            )?;
+            js_sys::Reflect::set(&self.window, jstr!("controls"), &self.controls)?;
+            js_sys::Reflect::set(&self.options, jstr!("window"), &self.window)?;
+            js_sys::Reflect::set(&self.options, jstr!("actions"), &self.actions)?;
+            let args = js_sys::Array::of1(&self.options);
+            let app = js_sys::Reflect::construct(class.unchecked_ref(), &args)?;
+            Ok(app.into())
+        }
+    }
+
+    /// A window that's only open once at a time, rerendered in place when it's opened again
+    ///
+    /// Keep one in a `thread_local!` and pass that to `open` and `refresh`.
+    #[derive(Default)]
+    pub struct SingletonWindow {
+        window: RefCell<Option<Application>>,
+    }
+
+    impl SingletonWindow {
+        pub const fn new() -> Self {
+            Self {
+                window: RefCell::new(None),
+            }
+        }
+
+        fn get(&self) -> Option<Application> {
+            self.window
+                .borrow()
+                .as_ref()
+                .map(|window| Application::from(window.as_js_value().clone()))
+        }
+
+        pub fn is_open(slot: &'static LocalKey<Self>) -> bool {
+            slot.with(|slot| slot.window.borrow().is_some())
+        }
+
+        /// Render the window, building it first if it isn't open
+        pub async fn open(
+            slot: &'static LocalKey<Self>,
+            build: impl FnOnce() -> WindowBuilder,
+        ) -> Result<(), Error> {
+            let window = match slot.with(Self::get) {
+                Some(window) => window,
+                None => {
+                    let window = build()
+                        .on_close(move || {
+                            slot.with(|slot| slot.window.borrow_mut().take());
+                        })
+                        .build()?;
+                    slot.with(|slot| {
+                        *slot.window.borrow_mut() =
+                            Some(Application::from(window.as_js_value().clone()))
+                    });
+                    window
+                }
+            };
+            window.render().await
+        }
+
+        /// Rerender the window if it's open
+        pub async fn refresh(slot: &'static LocalKey<Self>) -> Result<(), Error> {
+            match slot.with(Self::get) {
+                Some(window) => window.render().await,
+                None => Ok(()),
+            }
+        }
+    }
 
     /// Render a Handlebars template with context data
     pub async fn render_template<T: serde::ser::Serialize + ?Sized>(
@@ -1259,6 +4228,18 @@ pub mod application {
             .ctx("Template did not return a string")
     }
 
+    /// Enrich HTML such as an item description, resolving links, inline rolls, and checks
+    pub async fn enrich_html(content: &str) -> Result<String, Error> {
+        let global: JsValue = js_sys::global().into();
+        // v13 moved TextEditor under foundry.applications.ux
+        let text_editor = get_path!(&global, "foundry.applications.ux.TextEditor.implementation")
+            .or_else(|_| get_property(&global, "TextEditor"))?;
+        call_method_async(&text_editor, "enrichHTML", &[jstr!(content)])
+            .await?
+            .as_string()
+            .ctx("enrichHTML did not return a string")
+    }
+
     /// Show a simple dialog window with custom HTML content
     pub async fn show_dialog(
         title: &str,
@@ -1286,6 +4267,8 @@ pub mod application {
 
         let options = js_sys::Object::new();
         js_sys::Reflect::set(&options, jstr!("height"), jstr!("auto"))?;
+        // hand button callbacks the element rather than a jQuery object
+        js_sys::Reflect::set(&options, jstr!("jQuery"), &JsValue::FALSE)?;
 
         let args = js_sys::Array::new();
         args.push(&dialog_data);